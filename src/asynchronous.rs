@@ -0,0 +1,130 @@
+use std::{
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::{fs, sync::mpsc, task};
+
+#[cfg(feature = "s3")]
+use crate::s3;
+use crate::{
+    output, ExportMediaError, ExtractVisitor, Extraction, Options, Res, SingleRes, INDEX_FILE,
+};
+
+// what an extraction reports as it goes
+pub enum SlideEvent {
+    // a slide as soon as it's read
+    Slide(Box<SingleRes>),
+    // the finished extraction, whose index has all the slides again
+    Done(Box<Extraction>),
+    Error(ExportMediaError),
+}
+
+// the events of an extraction running on the blocking thread pool
+pub struct SlideEvents {
+    receiver: mpsc::Receiver<SlideEvent>,
+}
+
+// sends each slide as an event
+struct SlideSender<'a>(&'a mpsc::Sender<SlideEvent>);
+
+impl ExtractVisitor for SlideSender<'_> {
+    fn on_slide(&mut self, slide: &SingleRes) {
+        // nobody is listening anymore when this fails
+        let _ = self
+            .0
+            .blocking_send(SlideEvent::Slide(Box::new(slide.clone())));
+    }
+}
+
+impl Stream for SlideEvents {
+    type Item = SlideEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<SlideEvent>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+// extract data in memory without blocking the runtime's worker threads
+pub async fn extract_from_bytes(
+    data: Vec<u8>,
+    options: Options,
+) -> Result<Extraction, ExportMediaError> {
+    task::spawn_blocking(move || crate::extract_from_bytes(&data, options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// extract data into dir, awaiting the writes of the files and the index
+pub async fn extract_to_dir(
+    data: Vec<u8>,
+    options: Options,
+    dir: PathBuf,
+) -> Result<Res, ExportMediaError> {
+    let Extraction { res, files } = extract_from_bytes(data, options).await?;
+    for (name, data) in files {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        write_atomically(&path, data).await?;
+    }
+    let j = serde_json::to_string_pretty(&res).unwrap();
+    write_atomically(&dir.join(INDEX_FILE), j.into_bytes()).await?;
+    Ok(res)
+}
+
+// write data to path through a temporary file, as output::write_atomically
+async fn write_atomically(path: &Path, data: Vec<u8>) -> Result<(), ExportMediaError> {
+    let temp = output::temp_path(path);
+    fs::write(&temp, data).await?;
+    fs::rename(&temp, path).await?;
+    Ok(())
+}
+
+// extract data and upload the files and the index to s3://bucket/prefix/,
+// concurrency uploads at a time
+#[cfg(feature = "s3")]
+pub async fn extract_to_s3(
+    data: Vec<u8>,
+    options: Options,
+    url: &str,
+    concurrency: usize,
+) -> Result<Res, ExportMediaError> {
+    let client = s3::Client::from_env(url)?;
+    let Extraction { res, mut files } = extract_from_bytes(data, options).await?;
+    let j = serde_json::to_string_pretty(&res).unwrap();
+    files.insert(INDEX_FILE.to_owned(), j.into_bytes());
+    let mut uploads = task::JoinSet::new();
+    for (name, data) in files {
+        if uploads.len() >= concurrency.max(1) {
+            if let Some(uploaded) = uploads.join_next().await {
+                uploaded.map_err(|e| e.to_string())??;
+            }
+        }
+        let client = client.clone();
+        uploads.spawn_blocking(move || client.put(&name, &data));
+    }
+    while let Some(uploaded) = uploads.join_next().await {
+        uploaded.map_err(|e| e.to_string())??;
+    }
+    Ok(res)
+}
+
+// extract data on the blocking thread pool, streaming each slide as soon as
+// it's read and then the whole extraction. Must be called from within a tokio
+// runtime.
+pub fn slide_events(data: Vec<u8>, options: Options) -> SlideEvents {
+    let (sender, receiver) = mpsc::channel(16);
+    task::spawn_blocking(move || {
+        let extracted = crate::extract_in_memory(&data, options, &mut SlideSender(&sender));
+        let event = match extracted {
+            Ok(extraction) => SlideEvent::Done(Box::new(extraction)),
+            Err(e) => SlideEvent::Error(e),
+        };
+        let _ = sender.blocking_send(event);
+    });
+    SlideEvents { receiver }
+}
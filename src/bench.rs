@@ -0,0 +1,118 @@
+use std::{
+    fs, io,
+    path::Path,
+    process,
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+
+use ppt_img_extract::{
+    extract_archive,
+    logging::Phases,
+    output::{Output, Overwrite},
+    ExportMediaError, Options,
+};
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Number of extractions to time
+    #[arg(short = 'n', long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    iterations: u32,
+
+    /// Discard the extracted files instead of writing them to a temporary directory
+    #[arg(long)]
+    null_sink: bool,
+
+    /// Input file
+    input_file: String,
+}
+
+// extract the input again and again from memory, printing the time each run
+// took, the throughput and how long each phase took on average
+pub fn run(args: &BenchArgs) -> Result<(), ExportMediaError> {
+    let data = fs::read(&args.input_file)?;
+    let doc_title = Path::new(&args.input_file)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let options = Options {
+        profile: true,
+        ..Options::default()
+    };
+    let dir = std::env::temp_dir().join(format!("ppt-img-extract-bench-{}", process::id()));
+    let mut totals = Vec::new();
+    // phase -> total time over all runs, in the order the phases ran
+    let mut phase_totals: Vec<(String, f64)> = Vec::new();
+    for i in 0..args.iterations {
+        let mut output = match args.null_sink {
+            true => Output::null(),
+            false => {
+                fs::create_dir_all(&dir)?;
+                Output::new(&dir, Overwrite::Force)
+            }
+        };
+        let mut phases = Phases::start("open");
+        let start = Instant::now();
+        let extracted = extract_archive(
+            io::Cursor::new(&data),
+            &doc_title,
+            &options,
+            &mut output,
+            &mut phases,
+            &mut (),
+        );
+        let extracted = extracted.and_then(|extracted| {
+            output.finish()?;
+            Ok(extracted)
+        });
+        let elapsed = start.elapsed();
+        let res = match extracted {
+            Ok(extracted) => extracted.res,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(e);
+            }
+        };
+        println!("run {}: {:.1} ms", i + 1, ms(elapsed));
+        totals.push(elapsed);
+        for timing in res
+            .profile
+            .map(|profile| profile.phases)
+            .unwrap_or_default()
+        {
+            match phase_totals
+                .iter_mut()
+                .find(|(phase, _)| *phase == timing.name)
+            {
+                Some((_, total)) => *total += timing.ms,
+                None => phase_totals.push((timing.name, timing.ms)),
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&dir);
+
+    let total: Duration = totals.iter().sum();
+    let mean = total / args.iterations;
+    let (min, max) = (totals.iter().min().unwrap(), totals.iter().max().unwrap());
+    println!(
+        "{} runs, mean {:.1} ms, min {:.1} ms, max {:.1} ms",
+        args.iterations,
+        ms(mean),
+        ms(*min),
+        ms(*max)
+    );
+    println!(
+        "throughput {:.1} MiB/s, {:.1} runs/s",
+        data.len() as f64 / (1024.0 * 1024.0) / mean.as_secs_f64(),
+        1.0 / mean.as_secs_f64()
+    );
+    for (phase, total) in &phase_totals {
+        println!("  {}: {:.1} ms", phase, total / args.iterations as f64);
+    }
+    Ok(())
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
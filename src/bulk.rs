@@ -0,0 +1,81 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use ppt_img_extract::{output::Output, package::PackageType, ExportMediaError, Res};
+
+pub const BULK_FILE: &str = "bulk.ndjson";
+
+// what the index is written as, index.json or documents to load elsewhere
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndexFormat {
+    Index,
+    // an Elasticsearch _bulk request indexing a document a slide
+    EsBulk,
+}
+
+// the document of a slide
+#[derive(Serialize)]
+struct SlideDoc<'a> {
+    deck: &'a str,
+    slide: u32,
+    // the texts one a line, for full-text search
+    text: String,
+    texts: &'a [String],
+    notes: &'a [String],
+    images: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    layout_no: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    master_no: Option<u32>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    languages: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package_type: Option<PackageType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_sha256: Option<&'a str>,
+    tool_version: &'a str,
+}
+
+// write the slides as the action and document lines of a _bulk request into
+// es_index, or the index the request is posted to without one. Documents are
+// identified by the checksum of the presentation, or else its title, and the
+// slide number, so that loading a deck again replaces its slides.
+pub fn write(
+    output: &mut Output,
+    res: &Res,
+    es_index: Option<&str>,
+) -> Result<(), ExportMediaError> {
+    let deck_id = match &res.source {
+        Some(source) => source.sha256.as_str(),
+        None => res.doc_title.as_str(),
+    };
+    let mut lines = String::new();
+    for single_res in res.pages.slides.values() {
+        let mut action = json!({ "_id": format!("{}-{}", deck_id, single_res.page_no) });
+        if let Some(es_index) = es_index {
+            action["_index"] = json!(es_index);
+        }
+        let doc = SlideDoc {
+            deck: &res.doc_title,
+            slide: single_res.page_no,
+            text: single_res.texts.join("\n"),
+            texts: &single_res.texts,
+            notes: &single_res.notes,
+            images: &single_res.images,
+            layout_no: single_res.layout_no,
+            master_no: single_res.master_no,
+            languages: &single_res.languages,
+            package_type: res.package_type,
+            source_sha256: res.source.as_ref().map(|source| source.sha256.as_str()),
+            tool_version: res.tool_version,
+        };
+        lines.push_str(&json!({ "index": action }).to_string());
+        lines.push('\n');
+        lines.push_str(&serde_json::to_string(&doc).unwrap());
+        lines.push('\n');
+    }
+    output.write(BULK_FILE, lines.as_bytes())?;
+    Ok(())
+}
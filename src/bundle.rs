@@ -0,0 +1,72 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::{
+    reveal::{escape, is_web_image},
+    SingleRes,
+};
+
+pub const DIR_BUNDLES: &str = "bundles";
+pub const TEXT_FILE: &str = "text.json";
+pub const PREVIEW_FILE: &str = "preview.html";
+
+// the text of a slide as its bundle has it, the images by their names in the
+// bundle
+#[derive(Debug, Serialize)]
+struct Text<'a> {
+    page_no: u32,
+    texts: &'a [String],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    notes: &'a [String],
+    images: &'a [String],
+}
+
+// bundles/slide_007
+pub fn bundle_dir(page_no: u32) -> String {
+    format!("{}/slide_{:03}", DIR_BUNDLES, page_no)
+}
+
+// the name an image exported as media/image1.png has in a bundle, which holds
+// the images of its slide side by side
+pub fn image_name(image: &str) -> &str {
+    image.rsplit('/').next().unwrap_or(image)
+}
+
+pub fn text(single_res: &SingleRes, images: &[String]) -> String {
+    let text = Text {
+        page_no: single_res.page_no,
+        texts: &single_res.texts,
+        notes: &single_res.notes,
+        images,
+    };
+    serde_json::to_string_pretty(&text).unwrap()
+}
+
+// a page of the slide's text and then its images, linked by their names in
+// the bundle so that it shows wherever the bundle is copied
+pub fn preview(doc_title: &str, single_res: &SingleRes, images: &[String]) -> String {
+    let mut res = String::new();
+    res.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    writeln!(
+        res,
+        "<title>{} - slide {}</title>",
+        escape(doc_title),
+        single_res.page_no
+    )
+    .unwrap();
+    res.push_str("<style>img { max-width: 100%; }</style>\n");
+    res.push_str("</head>\n<body>\n");
+    let mut texts = single_res.texts.iter();
+    if let Some(heading) = texts.next() {
+        writeln!(res, "<h1>{}</h1>", escape(heading)).unwrap();
+    }
+    for text in texts {
+        writeln!(res, "<p>{}</p>", escape(text)).unwrap();
+    }
+    for image in images.iter().filter(|image| is_web_image(image)) {
+        writeln!(res, "<img src=\"{}\" alt=\"\">", escape(image)).unwrap();
+    }
+    res.push_str("</body>\n</html>\n");
+    res
+}
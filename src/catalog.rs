@@ -0,0 +1,140 @@
+use std::{fs, path::Path};
+
+use ppt_img_extract::output::write_atomically;
+use serde::Serialize;
+
+use crate::{
+    keywords::{self, Keyword},
+    ExportMediaError, INDEX_FILE, SCHEMA_VERSION,
+};
+
+pub const CATALOG_FILE: &str = "catalog.json";
+
+// the decks of a batch run, written next to their output directories
+#[derive(Debug, Serialize)]
+struct Catalog {
+    schema_version: &'static str,
+    tool_version: &'static str,
+    decks: Vec<Deck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Deck {
+    pub source: String,
+    doc_title: Option<String>,
+    // paths relative to the catalog
+    pub output_dir: String,
+    index: String,
+    sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+    slide_count: usize,
+    pub slides: Vec<SlideSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SlideSummary {
+    pub page_no: u64,
+    pub texts: Vec<String>,
+    // paths relative to the catalog
+    images: Vec<String>,
+    // the checksums of the images, of those the index has
+    #[serde(skip)]
+    pub image_hashes: Vec<String>,
+    // the terms telling the slide apart from the others of all the decks,
+    // with keywords
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<Keyword>,
+}
+
+// summarize a deck from the index extracted into output_dir/dir, which is
+// that of an earlier run when the extraction was skipped
+pub fn deck(input_file: &str, dir: &str, output_dir: &Path, error: Option<String>) -> Deck {
+    let mut res = Deck {
+        source: input_file.to_owned(),
+        doc_title: None,
+        output_dir: dir.to_owned(),
+        index: format!("{}/{}", dir, INDEX_FILE),
+        sha256: None,
+        thumbnail: None,
+        slide_count: 0,
+        slides: Vec::new(),
+        error,
+    };
+    if res.error.is_some() {
+        return res;
+    }
+    let index: serde_json::Value = match fs::read_to_string(output_dir.join(&res.index))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+    {
+        Some(index) => index,
+        None => return res,
+    };
+    let strings = |value: &serde_json::Value| -> Vec<String> {
+        value
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    res.doc_title = index["doc_title"].as_str().map(String::from);
+    res.sha256 = index["source"]["sha256"].as_str().map(String::from);
+    res.thumbnail = index["thumbnail"]
+        .as_str()
+        .map(|thumbnail| format!("{}/{}", dir, thumbnail));
+    if let Some(slides) = index["pages"]["slides"].as_object() {
+        for slide in slides.values() {
+            let images = strings(&slide["images"]);
+            res.slides.push(SlideSummary {
+                page_no: slide["page_no"].as_u64().unwrap_or_default(),
+                texts: strings(&slide["texts"]),
+                image_hashes: images
+                    .iter()
+                    .filter_map(|image| index["media"][image]["sha256"].as_str())
+                    .map(String::from)
+                    .collect(),
+                images: images
+                    .into_iter()
+                    .map(|image| format!("{}/{}", dir, image))
+                    .collect(),
+                keywords: Vec::new(),
+            });
+        }
+    }
+    res.slides.sort_by_key(|slide| slide.page_no);
+    res.slide_count = res.slides.len();
+    res
+}
+
+// the top n keywords of each slide among the slides of all the decks
+pub fn keywords(decks: &mut [Deck], n: usize) {
+    let docs: Vec<Vec<String>> = decks
+        .iter()
+        .flat_map(|deck| &deck.slides)
+        .map(|slide| keywords::terms(&slide.texts))
+        .collect();
+    let slide_keywords = keywords::tf_idf(&docs, n);
+    let slides = decks.iter_mut().flat_map(|deck| &mut deck.slides);
+    for (slide, keywords) in slides.zip(slide_keywords) {
+        slide.keywords = keywords;
+    }
+}
+
+// write the catalog of decks to path, catalog.json next to their directories
+pub fn write(path: &Path, decks: Vec<Deck>) -> Result<(), ExportMediaError> {
+    let catalog = Catalog {
+        schema_version: SCHEMA_VERSION,
+        tool_version: env!("CARGO_PKG_VERSION"),
+        decks,
+    };
+    let j = serde_json::to_string_pretty(&catalog).map_err(|e| e.to_string())?;
+    write_atomically(path, j.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Cursor, Read},
+};
+
+use clap::ValueEnum;
+use roxmltree::Node;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    limits, package,
+    xml::{self, NodeExt, NS_DRAWINGML as A, NS_RELATIONSHIPS as R},
+    ExportMediaError,
+};
+
+pub const DIR_CHARTS: &str = "charts";
+pub const REL_CHART: &str = "chart";
+// the workbook a chart keeps its data in, embedded in the package
+pub const REL_PACKAGE: &str = "package";
+const REL_WORKSHEET: &str = "worksheet";
+const REL_SHARED_STRINGS: &str = "sharedStrings";
+const NS_CHART: &str = "http://schemas.openxmlformats.org/drawingml/2006/chart";
+const NS_SPREADSHEETML: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartDataFormat {
+    Csv,
+}
+
+// a sheet of the workbook of a chart, exported as a file of its own
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChartSheet {
+    // the chart's place among the charts of the slide, from 1
+    pub chart_no: u32,
+    pub chart: String,
+    pub sheet: String,
+    // exported file name
+    pub file: String,
+}
+
+// a sheet of a workbook, its values in rows from A1
+pub struct Sheet {
+    pub name: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+// charts/slide_007_chart_1_sheet_1.csv
+pub fn csv_file(page_no: u32, chart_no: u32, sheet_no: usize) -> String {
+    format!(
+        "{}/slide_{:03}_chart_{}_sheet_{}.csv",
+        DIR_CHARTS, page_no, chart_no, sheet_no
+    )
+}
+
+// the relationship ids of the charts of a slide in the order they're drawn,
+// from the c:chart@r:id of each graphic frame
+pub fn chart_ids(el: Node) -> Vec<String> {
+    el.descendants()
+        .filter(|el| {
+            el.has_tag_name((NS_CHART, "chart"))
+                && el
+                    .parent()
+                    .is_some_and(|parent| parent.has_tag_name((A, "graphicData")))
+        })
+        .filter_map(|chart| chart.attribute((R, "id")).map(String::from))
+        .collect()
+}
+
+// the sheets of a workbook, each a grid of the values the cells were
+// last saved with, formulas not being evaluated. Cells missing from a sheet
+// are empty.
+pub fn sheets(data: &[u8], max_size: u64) -> Result<Vec<Sheet>, ExportMediaError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(|e| e.to_string())?;
+    let workbook = package::part_rels(&mut archive, "")?
+        .into_iter()
+        .find(|rel| rel.kind == package::REL_OFFICE_DOCUMENT)
+        .ok_or_else(|| String::from("workbook has no main part"))?
+        .target;
+    let workbook_rels = package::part_rels(&mut archive, &workbook)?;
+    let shared_strings = match workbook_rels
+        .iter()
+        .find(|rel| rel.kind == REL_SHARED_STRINGS)
+    {
+        Some(rel) => shared_strings(&read(&mut archive, &rel.target, max_size)?, &rel.target)?,
+        None => Vec::new(),
+    };
+    let targets: HashMap<&str, &str> = workbook_rels
+        .iter()
+        .filter(|rel| rel.kind == REL_WORKSHEET)
+        .map(|rel| (rel.id.as_str(), rel.target.as_str()))
+        .collect();
+    let text = read(&mut archive, &workbook, max_size)?;
+    let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, workbook.clone()))?;
+    let mut sheets = Vec::new();
+    if let Some(sheets_el) = doc.root_element().child(NS_SPREADSHEETML, "sheets") {
+        for sheet in sheets_el.elements() {
+            let target = sheet.attribute((R, "id")).and_then(|id| targets.get(id));
+            if let (Some(name), Some(target)) = (sheet.attribute("name"), target) {
+                sheets.push((name.to_owned(), target.to_string()));
+            }
+        }
+    }
+    let mut res = Vec::new();
+    for (name, target) in sheets {
+        let text = read(&mut archive, &target, max_size)?;
+        let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, target.clone()))?;
+        res.push(Sheet {
+            name,
+            rows: cells(doc.root_element(), &shared_strings),
+        });
+    }
+    Ok(res)
+}
+
+fn read<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+    max_size: u64,
+) -> Result<String, ExportMediaError> {
+    let file = archive.by_name(part_name).map_err(|e| e.to_string())?;
+    Ok(xml::read(limits::Limited::new(file, max_size))?)
+}
+
+// the strings of xl/sharedStrings.xml, the runs of rich text joined and
+// phonetic readings left out
+fn shared_strings(text: &str, part_name: &str) -> Result<Vec<String>, ExportMediaError> {
+    let doc = xml::parse(text).map_err(|e| ExportMediaError::Parse(e, part_name.to_owned()))?;
+    Ok(doc
+        .root_element()
+        .elements()
+        .filter(|si| si.has_tag_name((NS_SPREADSHEETML, "si")))
+        .map(|si| {
+            si.descendants()
+                .filter(|t| {
+                    t.has_tag_name((NS_SPREADSHEETML, "t"))
+                        && !t
+                            .ancestors()
+                            .any(|el| el.has_tag_name((NS_SPREADSHEETML, "rPh")))
+                })
+                .filter_map(|t| t.text())
+                .collect()
+        })
+        .collect())
+}
+
+// the values of the cells of a worksheet as rows from A1, by the reference
+// of each cell or else its place after the one before
+fn cells(worksheet: Node, shared_strings: &[String]) -> Vec<Vec<String>> {
+    // row -> column -> value, both from 0
+    let mut values: BTreeMap<usize, BTreeMap<usize, String>> = BTreeMap::new();
+    let sheet_data = match worksheet.child(NS_SPREADSHEETML, "sheetData") {
+        Some(sheet_data) => sheet_data,
+        None => return Vec::new(),
+    };
+    let mut row_no = 0;
+    for row in sheet_data.elements() {
+        row_no = row
+            .attribute("r")
+            .and_then(|r| r.parse::<usize>().ok())
+            .map(|r| r.saturating_sub(1))
+            .unwrap_or(row_no);
+        let mut col_no = 0;
+        for c in row.elements() {
+            col_no = c.attribute("r").and_then(column).unwrap_or(col_no);
+            if let Some(value) = value(c, shared_strings) {
+                values.entry(row_no).or_default().insert(col_no, value);
+            }
+            col_no += 1;
+        }
+        row_no += 1;
+    }
+    let width = values
+        .values()
+        .filter_map(|row| row.keys().next_back())
+        .max()
+        .map_or(0, |last| last + 1);
+    let height = values.keys().next_back().map_or(0, |last| last + 1);
+    (0..height)
+        .map(|r| {
+            (0..width)
+                .map(|c| {
+                    values
+                        .get(&r)
+                        .and_then(|row| row.get(&c))
+                        .cloned()
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// the column of a cell reference from 0, B7 -> 1
+fn column(reference: &str) -> Option<usize> {
+    let letters: Vec<u8> = reference
+        .bytes()
+        .take_while(u8::is_ascii_alphabetic)
+        .collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let n = letters.iter().fold(0usize, |n, letter| {
+        n * 26 + (letter.to_ascii_uppercase() - b'A' + 1) as usize
+    });
+    Some(n - 1)
+}
+
+// the cached value of a cell by its type c@t
+fn value(c: Node, shared_strings: &[String]) -> Option<String> {
+    let v = c.child(NS_SPREADSHEETML, "v").and_then(|v| v.text());
+    match c.attribute("t") {
+        Some("s") => shared_strings
+            .get(v?.trim().parse::<usize>().ok()?)
+            .cloned(),
+        Some("inlineStr") => Some(
+            c.child(NS_SPREADSHEETML, "is")?
+                .descendants()
+                .filter(|t| t.has_tag_name((NS_SPREADSHEETML, "t")))
+                .filter_map(|t| t.text())
+                .collect(),
+        ),
+        Some("b") => Some(String::from(if v? == "1" { "TRUE" } else { "FALSE" })),
+        _ => v.map(String::from),
+    }
+}
+
+pub fn to_csv(rows: &[Vec<String>]) -> String {
+    let mut res = String::new();
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|field| quote(field)).collect();
+        res.push_str(&fields.join(","));
+        res.push('\n');
+    }
+    res
+}
+
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
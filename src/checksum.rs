@@ -0,0 +1,64 @@
+use std::io::{self, Read, Write};
+
+use md5::Md5;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Checksum {
+    pub size: u64,
+    pub sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5: Option<String>,
+}
+
+// a writer that hashes everything written through it
+pub struct HashingWriter<W> {
+    inner: W,
+    size: u64,
+    sha256: Sha256,
+    md5: Option<Md5>,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W, md5: bool) -> Self {
+        HashingWriter {
+            inner,
+            size: 0,
+            sha256: Sha256::new(),
+            md5: if md5 { Some(Md5::new()) } else { None },
+        }
+    }
+
+    pub fn finish(self) -> Checksum {
+        Checksum {
+            size: self.size,
+            sha256: format!("{:x}", self.sha256.finalize()),
+            md5: self.md5.map(|md5| format!("{:x}", md5.finalize())),
+        }
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.size += n as u64;
+        self.sha256.update(&buf[..n]);
+        if let Some(md5) = self.md5.as_mut() {
+            md5.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// hash everything read from r
+pub fn checksum<R: Read>(r: &mut R, md5: bool) -> io::Result<Checksum> {
+    let mut writer = HashingWriter::new(io::sink(), md5);
+    io::copy(r, &mut writer)?;
+    Ok(writer.finish())
+}
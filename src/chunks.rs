@@ -0,0 +1,121 @@
+use serde::Serialize;
+use std::mem;
+
+use crate::shapes::Shape;
+
+pub const CHUNKS_FILE: &str = "chunks.jsonl";
+pub const DEFAULT_MAX_TOKENS: u32 = 512;
+
+// a piece of the text of a shape, sized for an embedding model, with where it
+// comes from
+#[derive(Debug, Serialize)]
+pub struct Chunk {
+    pub deck: String,
+    pub slide: u32,
+    pub shape_id: u32,
+    pub shape: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    // 0-based place among the chunks of the shape
+    pub n: usize,
+    pub text: String,
+    pub tokens: u32,
+}
+
+// an estimate of the tokens of text, a token per 4 ASCII characters of a word
+// and one per other character, CJK text having about a token a character.
+// The estimate of words joined is the sum of theirs.
+pub fn tokens(text: &str) -> u32 {
+    text.split_whitespace().map(word_tokens).sum()
+}
+
+fn word_tokens(word: &str) -> u32 {
+    let ascii = word.bytes().filter(u8::is_ascii).count() as u32;
+    let other = word.chars().filter(|c| !c.is_ascii()).count() as u32;
+    ascii.div_ceil(4) + other
+}
+
+// the chunks of the shapes of a slide, each of whole paragraphs of a shape up
+// to max_tokens, paragraphs longer than that being split between words
+pub fn chunks(deck: &str, slide: u32, shapes: &[Shape], max_tokens: u32) -> Vec<Chunk> {
+    let mut res = Vec::new();
+    for shape in shapes {
+        let mut pieces = Vec::new();
+        for paragraph in &shape.paragraphs {
+            split(&paragraph.text, max_tokens, &mut pieces);
+        }
+        let mut text = String::new();
+        let mut text_tokens = 0;
+        let mut texts = Vec::new();
+        for (piece, piece_tokens) in pieces {
+            if !text.is_empty() && text_tokens + piece_tokens > max_tokens {
+                texts.push((mem::take(&mut text), text_tokens));
+                text_tokens = 0;
+            }
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&piece);
+            text_tokens += piece_tokens;
+        }
+        if !text.is_empty() {
+            texts.push((text, text_tokens));
+        }
+        res.extend(
+            texts
+                .into_iter()
+                .enumerate()
+                .map(|(n, (text, tokens))| Chunk {
+                    deck: deck.to_owned(),
+                    slide,
+                    shape_id: shape.id,
+                    shape: shape.name.clone(),
+                    placeholder: shape.placeholder.clone(),
+                    n,
+                    text,
+                    tokens,
+                }),
+        );
+    }
+    res
+}
+
+// a paragraph as pieces of at most max_tokens, those of a single word longer
+// than that aside
+fn split(paragraph: &str, max_tokens: u32, pieces: &mut Vec<(String, u32)>) {
+    let paragraph_tokens = tokens(paragraph);
+    if paragraph_tokens == 0 {
+        return;
+    }
+    if paragraph_tokens <= max_tokens {
+        pieces.push((paragraph.to_owned(), paragraph_tokens));
+        return;
+    }
+    let mut piece = String::new();
+    let mut piece_tokens = 0;
+    for word in paragraph.split_whitespace() {
+        let tokens = word_tokens(word);
+        if !piece.is_empty() && piece_tokens + tokens > max_tokens {
+            pieces.push((mem::take(&mut piece), piece_tokens));
+            piece_tokens = 0;
+        }
+        if !piece.is_empty() {
+            piece.push(' ');
+        }
+        piece.push_str(word);
+        piece_tokens += tokens;
+    }
+    if !piece.is_empty() {
+        pieces.push((piece, piece_tokens));
+    }
+}
+
+// a chunk per line
+pub fn to_jsonl(chunks: &[Chunk]) -> String {
+    let mut res = String::new();
+    for chunk in chunks {
+        res.push_str(&serde_json::to_string(chunk).unwrap());
+        res.push('\n');
+    }
+    res
+}
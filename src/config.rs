@@ -0,0 +1,248 @@
+use log::debug;
+#[cfg(not(feature = "lang-detect"))]
+use log::warn;
+use std::{fs, path::Path, time::Duration};
+
+use clap::{parser::ValueSource, ArgMatches};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{
+    bulk::IndexFormat, limits, split::SplitIndex, text::Normalization, ChartDataFormat,
+    ExportMediaError, ExtractArgs, GraphFormat, LayoutFormat, OnFailure,
+};
+
+// looked up in the working directory when no --config is given
+pub const CONFIG_FILE: &str = "ppt-img-extract.toml";
+
+// defaults for the extraction flags, keys are the long flag names
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    output_dir: Option<String>,
+    create_dirs: Option<bool>,
+    fonts: Option<bool>,
+    normalize: Option<Normalization>,
+    trim_text: Option<bool>,
+    detect_lang: Option<bool>,
+    stats: Option<bool>,
+    paragraphs: Option<bool>,
+    shapes: Option<bool>,
+    equations: Option<bool>,
+    mathml: Option<bool>,
+    resolve_inherited: Option<bool>,
+    keep_paths: Option<bool>,
+    sanitize_names: Option<bool>,
+    fix_extensions: Option<bool>,
+    format: Option<IndexFormat>,
+    es_index: Option<String>,
+    notes: Option<bool>,
+    keywords: Option<usize>,
+    image_map: Option<String>,
+    split_index: Option<SplitIndex>,
+    strip_metadata: Option<bool>,
+    exif_report: Option<bool>,
+    anonymize: Option<bool>,
+    srt: Option<bool>,
+    reveal: Option<bool>,
+    shapes_svg: Option<bool>,
+    chart_data: Option<ChartDataFormat>,
+    bundle: Option<bool>,
+    chunks: Option<bool>,
+    max_tokens: Option<u32>,
+    graph: Option<GraphFormat>,
+    layout: Option<LayoutFormat>,
+    no_masters: Option<bool>,
+    exclude_prompt_text: Option<bool>,
+    exclude_placeholders: Option<Vec<String>>,
+    enable: Option<Vec<String>>,
+    disable: Option<Vec<String>>,
+    vba: Option<bool>,
+    md5: Option<bool>,
+    max_uncompressed_size: Option<u64>,
+    max_entries: Option<usize>,
+    max_media_size: Option<u64>,
+    #[serde(deserialize_with = "duration")]
+    timeout: Option<Duration>,
+    on_failure: Option<OnFailure>,
+}
+
+// read the given config file, or ppt-img-extract.toml if there is one
+pub fn load(path: Option<&str>) -> Result<Config, ExportMediaError> {
+    let path = match path {
+        Some(path) => path,
+        None if Path::new(CONFIG_FILE).is_file() => CONFIG_FILE,
+        None => return Ok(Config::default()),
+    };
+    let content = fs::read_to_string(path)?;
+    let config: Config =
+        toml::from_str(&content).map_err(|e| format!("invalid config {}: {}", path, e))?;
+    debug!("config from {}: {:?}", path, config);
+    Ok(config)
+}
+
+impl Config {
+    // fill in the args that weren't given on the command line
+    pub fn apply(self, args: &mut ExtractArgs, matches: &ArgMatches) {
+        set(matches, "output_dir", self.output_dir, &mut args.output_dir);
+        set(
+            matches,
+            "create_dirs",
+            self.create_dirs,
+            &mut args.create_dirs,
+        );
+        set(matches, "fonts", self.fonts, &mut args.fonts);
+        set(
+            matches,
+            "normalize",
+            self.normalize.map(Some),
+            &mut args.normalize,
+        );
+        set(matches, "trim_text", self.trim_text, &mut args.trim_text);
+        #[cfg(feature = "lang-detect")]
+        set(
+            matches,
+            "detect_lang",
+            self.detect_lang,
+            &mut args.detect_lang,
+        );
+        #[cfg(not(feature = "lang-detect"))]
+        if self.detect_lang == Some(true) {
+            warn!("detect-lang in config ignored, built without the lang-detect feature");
+        }
+        set(matches, "stats", self.stats, &mut args.stats);
+        set(matches, "paragraphs", self.paragraphs, &mut args.paragraphs);
+        set(matches, "shapes", self.shapes, &mut args.shapes);
+        set(matches, "equations", self.equations, &mut args.equations);
+        set(matches, "mathml", self.mathml, &mut args.mathml);
+        set(
+            matches,
+            "resolve_inherited",
+            self.resolve_inherited,
+            &mut args.resolve_inherited,
+        );
+        set(matches, "keep_paths", self.keep_paths, &mut args.keep_paths);
+        set(
+            matches,
+            "sanitize_names",
+            self.sanitize_names,
+            &mut args.sanitize_names,
+        );
+        set(
+            matches,
+            "fix_extensions",
+            self.fix_extensions,
+            &mut args.fix_extensions,
+        );
+        set(matches, "format", self.format, &mut args.format);
+        set(
+            matches,
+            "es_index",
+            self.es_index.map(Some),
+            &mut args.es_index,
+        );
+        set(matches, "notes", self.notes, &mut args.notes);
+        set(
+            matches,
+            "keywords",
+            self.keywords.map(Some),
+            &mut args.keywords,
+        );
+        set(
+            matches,
+            "image_map",
+            self.image_map.map(Some),
+            &mut args.image_map,
+        );
+        set(
+            matches,
+            "split_index",
+            self.split_index.map(Some),
+            &mut args.split_index,
+        );
+        set(
+            matches,
+            "strip_metadata",
+            self.strip_metadata,
+            &mut args.strip_metadata,
+        );
+        set(
+            matches,
+            "exif_report",
+            self.exif_report,
+            &mut args.exif_report,
+        );
+        set(matches, "anonymize", self.anonymize, &mut args.anonymize);
+        set(matches, "srt", self.srt, &mut args.srt);
+        set(matches, "reveal", self.reveal, &mut args.reveal);
+        set(matches, "shapes_svg", self.shapes_svg, &mut args.shapes_svg);
+        set(
+            matches,
+            "chart_data",
+            self.chart_data.map(Some),
+            &mut args.chart_data,
+        );
+        set(matches, "bundle", self.bundle, &mut args.bundle);
+        set(matches, "chunks", self.chunks, &mut args.chunks);
+        set(matches, "max_tokens", self.max_tokens, &mut args.max_tokens);
+        set(matches, "graph", self.graph.map(Some), &mut args.graph);
+        set(matches, "layout", self.layout.map(Some), &mut args.layout);
+        set(matches, "no_masters", self.no_masters, &mut args.no_masters);
+        set(
+            matches,
+            "exclude_prompt_text",
+            self.exclude_prompt_text,
+            &mut args.exclude_prompt_text,
+        );
+        set(
+            matches,
+            "exclude_placeholders",
+            self.exclude_placeholders,
+            &mut args.exclude_placeholders,
+        );
+        set(matches, "enable", self.enable, &mut args.enable);
+        set(matches, "disable", self.disable, &mut args.disable);
+        set(matches, "vba", self.vba, &mut args.vba);
+        set(matches, "md5", self.md5, &mut args.md5);
+        set(
+            matches,
+            "max_uncompressed_size",
+            self.max_uncompressed_size,
+            &mut args.max_uncompressed_size,
+        );
+        set(
+            matches,
+            "max_entries",
+            self.max_entries,
+            &mut args.max_entries,
+        );
+        set(
+            matches,
+            "max_media_size",
+            self.max_media_size,
+            &mut args.max_media_size,
+        );
+        set(
+            matches,
+            "timeout",
+            self.timeout.map(Some),
+            &mut args.timeout,
+        );
+        set(matches, "on_failure", self.on_failure, &mut args.on_failure);
+    }
+}
+
+// a duration written as for --timeout, "120s"
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    limits::parse_duration(&s)
+        .map(Some)
+        .map_err(de::Error::custom)
+}
+
+fn set<T>(matches: &ArgMatches, id: &str, value: Option<T>, arg: &mut T) {
+    if let Some(value) = value {
+        if matches.value_source(id) != Some(ValueSource::CommandLine) {
+            *arg = value;
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::io::Cursor;
+
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+
+pub const CONTACT_SHEET_FILE: &str = "_contact_sheet.png";
+
+// size of the square each image is fitted in, and of the label under it
+const TILE: u32 = 240;
+const LABEL: u32 = 28;
+const GAP: u32 = 8;
+const COLUMNS: u32 = 6;
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+// stands in for images that can't be decoded, such as vector images
+const PLACEHOLDER: Rgba<u8> = Rgba([220, 220, 220, 255]);
+const INK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+// 3x5 digits, a row per byte with the leftmost pixel in bit 2
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+const DIGIT_SCALE: u32 = 4;
+
+// fit an image in a tile, none when it can't be decoded
+pub fn thumbnail(data: &[u8]) -> Option<RgbaImage> {
+    let img = image::load_from_memory(data).ok()?;
+    Some(img.thumbnail(TILE, TILE).to_rgba8())
+}
+
+// lay the images out in rows, each labeled with the number of the slide it's
+// on, and encode the sheet as PNG
+pub fn contact_sheet(tiles: &[(u32, Option<&RgbaImage>)]) -> Result<Vec<u8>, String> {
+    let columns = COLUMNS.min(tiles.len().max(1) as u32);
+    let rows = (tiles.len() as u32).div_ceil(COLUMNS).max(1);
+    let mut sheet = RgbaImage::from_pixel(
+        GAP + columns * (TILE + GAP),
+        GAP + rows * (TILE + LABEL + GAP),
+        BACKGROUND,
+    );
+    for (i, (slide_no, img)) in tiles.iter().enumerate() {
+        let x = GAP + (i as u32 % COLUMNS) * (TILE + GAP);
+        let y = GAP + (i as u32 / COLUMNS) * (TILE + LABEL + GAP);
+        match img {
+            // centered in its tile
+            Some(img) => imageops::overlay(
+                &mut sheet,
+                *img,
+                (x + (TILE - img.width()) / 2) as i64,
+                (y + (TILE - img.height()) / 2) as i64,
+            ),
+            None => fill(&mut sheet, x, y, TILE, TILE, PLACEHOLDER),
+        }
+        label(
+            &mut sheet,
+            x,
+            y + TILE + (LABEL - 5 * DIGIT_SCALE) / 2,
+            *slide_no,
+        );
+    }
+    let mut res = Vec::new();
+    sheet
+        .write_to(&mut Cursor::new(&mut res), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(res)
+}
+
+fn fill(sheet: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..y + height {
+        for px in x..x + width {
+            sheet.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn label(sheet: &mut RgbaImage, x: u32, y: u32, slide_no: u32) {
+    for (i, digit) in slide_no.to_string().bytes().enumerate() {
+        let glyph = DIGITS[(digit - b'0') as usize];
+        let gx = x + i as u32 * 4 * DIGIT_SCALE;
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    fill(
+                        sheet,
+                        gx + col * DIGIT_SCALE,
+                        y + row as u32 * DIGIT_SCALE,
+                        DIGIT_SCALE,
+                        DIGIT_SCALE,
+                        INK,
+                    );
+                }
+            }
+        }
+    }
+}
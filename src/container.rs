@@ -0,0 +1,60 @@
+use log::debug;
+use std::{fs, io, io::Read, path::Path};
+
+use ppt_img_extract::{limits, ExportMediaError};
+
+// the extensions of the presentation packages looked for in a container
+const PRESENTATION_EXTENSIONS: &[&str] = &["pptx", "pptm", "ppsx", "ppsm", "potx", "potm"];
+
+// a zip archive of presentations, such as a bundle decks are sent in
+pub struct Container {
+    path: String,
+    archive: zip::ZipArchive<io::BufReader<fs::File>>,
+}
+
+impl Container {
+    pub fn open(path: &str) -> Result<Self, ExportMediaError> {
+        let archivef = fs::File::open(Path::new(path))?;
+        let archive =
+            zip::ZipArchive::new(io::BufReader::new(archivef)).map_err(|e| e.to_string())?;
+        Ok(Container {
+            path: path.to_owned(),
+            archive,
+        })
+    }
+
+    // the names of the presentations in the container, in archive order,
+    // leaving out the resource forks macOS adds to archives it makes
+    pub fn presentations(&self) -> Vec<String> {
+        self.archive
+            .file_names()
+            .filter(|name| {
+                let file_name = name.rsplit('/').next().unwrap_or_default();
+                let extension = file_name
+                    .rsplit_once('.')
+                    .map(|(_, extension)| extension.to_lowercase())
+                    .unwrap_or_default();
+                if name.starts_with("__MACOSX/") || file_name.starts_with("._") {
+                    debug!("skipping {} in {}", name, self.path);
+                    return false;
+                }
+                PRESENTATION_EXTENSIONS.contains(&extension.as_str())
+            })
+            .map(str::to_owned)
+            .collect()
+    }
+
+    // read a presentation into memory, at most max_size bytes of it
+    pub fn read(&mut self, name: &str, max_size: u64) -> Result<Vec<u8>, ExportMediaError> {
+        let file = self.archive.by_name(name).map_err(|e| e.to_string())?;
+        let mut data = Vec::new();
+        limits::Limited::new(file, max_size).read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    // how a presentation in the container is named in logs and the catalog,
+    // bundle.zip/q1/deck.pptx
+    pub fn source(&self, name: &str) -> String {
+        format!("{}/{}", self.path, name)
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+use crate::{checksum, errors::Errors, package, slide, ExportMediaError, Output, Res};
+
+pub const CHANGES_FILE: &str = "changes.json";
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// Old presentation or index.json
+    old: String,
+
+    /// New presentation or index.json
+    new: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+    format: DiffFormat,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum DiffFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+struct SlideSnapshot {
+    page_no: u32,
+    texts: Vec<String>,
+    // sorted image file names, each with its content hash when known
+    images: Vec<String>,
+}
+
+// slides are the same when their content is, wherever they are in the deck
+impl PartialEq for SlideSnapshot {
+    fn eq(&self, other: &Self) -> bool {
+        self.texts == other.texts && self.images == other.images
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DiffReport<'a> {
+    old: &'a str,
+    new: &'a str,
+    added: Vec<u32>,
+    removed: Vec<u32>,
+    modified: Vec<SlideChange>,
+    unchanged: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SlideChange {
+    old_page_no: u32,
+    new_page_no: u32,
+    texts_removed: Vec<String>,
+    texts_added: Vec<String>,
+    images_removed: Vec<String>,
+    images_added: Vec<String>,
+    // unified diff style lines of the slide texts
+    #[serde(skip)]
+    text_lines: Vec<String>,
+}
+
+// compare two presentations, returns whether they differ
+pub fn run(args: &DiffArgs) -> Result<bool, ExportMediaError> {
+    let old = load(&args.old)?;
+    let new = load(&args.new)?;
+    let report = diff(&args.old, &args.new, &old, &new);
+    let changed =
+        !(report.added.is_empty() && report.removed.is_empty() && report.modified.is_empty());
+    match args.format {
+        DiffFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
+        DiffFormat::Text => print_text(&report, &old, &new),
+    }
+    Ok(changed)
+}
+
+fn load(path: &str) -> Result<Vec<SlideSnapshot>, ExportMediaError> {
+    if path.ends_with(".json") {
+        load_index(path)
+    } else {
+        load_presentation(path)
+    }
+}
+
+fn load_index(path: &str) -> Result<Vec<SlideSnapshot>, ExportMediaError> {
+    let content = fs::read_to_string(path)?;
+    let index: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    index_snapshots(path, &index)
+}
+
+// the slides of an index, each image with the hash of the file it was
+// exported as
+fn index_snapshots(
+    path: &str,
+    index: &serde_json::Value,
+) -> Result<Vec<SlideSnapshot>, ExportMediaError> {
+    let slides = index["pages"]["slides"]
+        .as_object()
+        .ok_or_else(|| format!("{} has no slides", path))?;
+    let strings = |value: &serde_json::Value| -> Vec<String> {
+        value
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut res: Vec<SlideSnapshot> = slides
+        .values()
+        .map(|slide| {
+            let mut images: Vec<String> = strings(&slide["images"])
+                .into_iter()
+                .map(|image| match index["media"][&image]["sha256"].as_str() {
+                    Some(hash) => format!("{} (sha256:{})", image, hash),
+                    None => image,
+                })
+                .collect();
+            images.sort();
+            SlideSnapshot {
+                page_no: slide["page_no"].as_u64().unwrap_or_default() as u32,
+                texts: strings(&slide["texts"]),
+                images,
+            }
+        })
+        .collect();
+    res.sort_by_key(|slide| slide.page_no);
+    Ok(res)
+}
+
+fn load_presentation(path: &str) -> Result<Vec<SlideSnapshot>, ExportMediaError> {
+    let archivef = fs::File::open(Path::new(path))?;
+    let mut archive =
+        zip::ZipArchive::new(io::BufReader::new(archivef)).map_err(|e| e.to_string())?;
+    let pkg = package::discover(&mut archive, &mut Errors::default())?;
+
+    let mut res = Vec::new();
+    for (i, part_name) in pkg.slides.iter().enumerate() {
+        let page_no = i as u32 + 1;
+        let texts = slide(
+            archive.by_name(part_name).map_err(|e| e.to_string())?,
+            &[],
+            &HashSet::new(),
+        )?
+        .texts;
+        let media: Vec<String> = package::part_rels(&mut archive, part_name)?
+            .into_iter()
+            .filter(|rel| rel.kind == package::REL_IMAGE && !rel.external)
+            .map(|rel| rel.target)
+            .collect();
+        let mut images = Vec::new();
+        for part_name in media {
+            let file_name = Path::new(&part_name)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            images.push(match media_hash(&mut archive, &part_name) {
+                Ok(hash) => format!("{} ({})", file_name, hash),
+                Err(_) => file_name,
+            });
+        }
+        images.sort();
+        res.push(SlideSnapshot {
+            page_no,
+            texts,
+            images,
+        });
+    }
+    Ok(res)
+}
+
+// compare an extraction with that of an earlier version of the deck, by
+// their slide texts and image hashes, writing the changes into the output
+pub fn baseline(output: &mut Output, path: &str, res: &Res) -> Result<(), ExportMediaError> {
+    let old = load_index(path)?;
+    let index = serde_json::to_value(res).map_err(|e| e.to_string())?;
+    let new = index_snapshots(&res.doc_title, &index)?;
+    let report = diff(path, &res.doc_title, &old, &new);
+    let j = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    output.write(CHANGES_FILE, j.as_bytes())?;
+    Ok(())
+}
+
+fn media_hash<R: Read + io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+) -> Result<String, ExportMediaError> {
+    let mut f = archive.by_name(part_name).map_err(|e| e.to_string())?;
+    let checksum = checksum::checksum(&mut f, false)?;
+    Ok(format!("sha256:{}", checksum.sha256))
+}
+
+// align slides by longest common subsequence, unmatched slides between two
+// aligned ones are paired up as modified and the rest are added or removed
+fn diff<'a>(
+    old_name: &'a str,
+    new_name: &'a str,
+    old: &[SlideSnapshot],
+    new: &[SlideSnapshot],
+) -> DiffReport<'a> {
+    let mut report = DiffReport {
+        old: old_name,
+        new: new_name,
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: Vec::new(),
+        unchanged: 0,
+    };
+    let mut anchors = lcs(old, new);
+    anchors.push((old.len(), new.len()));
+    let (mut i, mut j) = (0, 0);
+    for (anchor_i, anchor_j) in anchors {
+        while i < anchor_i && j < anchor_j {
+            report.modified.push(change(&old[i], &new[j]));
+            i += 1;
+            j += 1;
+        }
+        report
+            .removed
+            .extend(old[i..anchor_i].iter().map(|slide| slide.page_no));
+        report
+            .added
+            .extend(new[j..anchor_j].iter().map(|slide| slide.page_no));
+        if anchor_i < old.len() {
+            report.unchanged += 1;
+        }
+        i = anchor_i + 1;
+        j = anchor_j + 1;
+    }
+    report
+}
+
+fn change(old: &SlideSnapshot, new: &SlideSnapshot) -> SlideChange {
+    let mut res = SlideChange {
+        old_page_no: old.page_no,
+        new_page_no: new.page_no,
+        texts_removed: Vec::new(),
+        texts_added: Vec::new(),
+        images_removed: Vec::new(),
+        images_added: Vec::new(),
+        text_lines: Vec::new(),
+    };
+    let mut anchors = lcs(&old.texts, &new.texts);
+    anchors.push((old.texts.len(), new.texts.len()));
+    let (mut i, mut j) = (0, 0);
+    for (anchor_i, anchor_j) in anchors {
+        for text in &old.texts[i..anchor_i] {
+            res.texts_removed.push(text.clone());
+            res.text_lines.push(format!("-{}", text));
+        }
+        for text in &new.texts[j..anchor_j] {
+            res.texts_added.push(text.clone());
+            res.text_lines.push(format!("+{}", text));
+        }
+        if anchor_i < old.texts.len() {
+            res.text_lines.push(format!(" {}", old.texts[anchor_i]));
+        }
+        i = anchor_i + 1;
+        j = anchor_j + 1;
+    }
+    let mut old_images: BTreeSet<&String> = old.images.iter().collect();
+    for image in &new.images {
+        if !old_images.remove(image) {
+            res.images_added.push(image.clone());
+        }
+    }
+    res.images_removed = old_images.into_iter().cloned().collect();
+    res
+}
+
+// index pairs of a longest common subsequence of a and b
+fn lcs<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut res = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            res.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    res
+}
+
+fn print_text(report: &DiffReport, old: &[SlideSnapshot], new: &[SlideSnapshot]) {
+    println!("--- {}", report.old);
+    println!("+++ {}", report.new);
+    for page_no in &report.removed {
+        println!("@@ slide {} removed @@", page_no);
+        if let Some(slide) = old.iter().find(|slide| slide.page_no == *page_no) {
+            slide.texts.iter().for_each(|text| println!("-{}", text));
+            slide
+                .images
+                .iter()
+                .for_each(|image| println!("-image: {}", image));
+        }
+    }
+    for change in &report.modified {
+        println!(
+            "@@ slide {} -> {} @@",
+            change.old_page_no, change.new_page_no
+        );
+        change
+            .text_lines
+            .iter()
+            .for_each(|line| println!("{}", line));
+        change
+            .images_removed
+            .iter()
+            .for_each(|image| println!("-image: {}", image));
+        change
+            .images_added
+            .iter()
+            .for_each(|image| println!("+image: {}", image));
+    }
+    for page_no in &report.added {
+        println!("@@ slide {} added @@", page_no);
+        if let Some(slide) = new.iter().find(|slide| slide.page_no == *page_no) {
+            slide.texts.iter().for_each(|text| println!("+{}", text));
+            slide
+                .images
+                .iter()
+                .for_each(|image| println!("+image: {}", image));
+        }
+    }
+}
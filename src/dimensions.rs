@@ -0,0 +1,110 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::strip::{Tiff, PNG_SIGNATURE};
+
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+
+// the size of an image in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Dimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+// read the width and height from the header of a PNG, JPEG, GIF, BMP, WebP or
+// TIFF image, none for other media or when the header is cut short
+pub fn dimensions(data: &[u8]) -> Option<Dimensions> {
+    let (width, height) = if data.starts_with(PNG_SIGNATURE) {
+        // IHDR is the first chunk
+        (be32(data, 16)?, be32(data, 20)?)
+    } else if data.starts_with(&[0xff, 0xd8]) {
+        jpeg(data)?
+    } else if data.starts_with(b"GIF8") {
+        (le16(data, 6)? as u32, le16(data, 8)? as u32)
+    } else if data.starts_with(b"BM") {
+        // rows run bottom-up with a positive height and top-down with a
+        // negative one
+        let height = i32::from_le_bytes(data.get(22..26)?.try_into().ok()?);
+        (le32(data, 18)?, height.unsigned_abs())
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        webp(data)?
+    } else if data.starts_with(b"II") || data.starts_with(b"MM") {
+        tiff(data)?
+    } else {
+        return None;
+    };
+    Some(Dimensions { width, height })
+}
+
+// the frame header, SOF0 to SOF15 aside from DHT, JPG and DAC
+fn jpeg(data: &[u8]) -> Option<(u32, u32)> {
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xff {
+        let marker = data[pos + 1];
+        if marker == 0xff {
+            pos += 1;
+            continue;
+        }
+        if marker == 0xda || marker == 0xd9 {
+            return None;
+        }
+        if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let height = u16::from_be_bytes(data.get(pos + 5..pos + 7)?.try_into().ok()?);
+            let width = u16::from_be_bytes(data.get(pos + 7..pos + 9)?.try_into().ok()?);
+            return Some((width as u32, height as u32));
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        pos += 2 + len;
+    }
+    None
+}
+
+// the first chunk, lossy VP8, lossless VP8L or extended VP8X
+fn webp(data: &[u8]) -> Option<(u32, u32)> {
+    let chunk = data.get(12..16)?;
+    let payload = data.get(20..)?;
+    match chunk {
+        b"VP8 " => Some((
+            le16(payload, 6)? as u32 & 0x3fff,
+            le16(payload, 8)? as u32 & 0x3fff,
+        )),
+        b"VP8L" => {
+            let bits = le32(payload, 1)?;
+            Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+        }
+        b"VP8X" => {
+            let width = u32::from_le_bytes([*payload.get(4)?, payload[5], payload[6], 0]);
+            let height = u32::from_le_bytes([*payload.get(7)?, payload[8], payload[9], 0]);
+            Some((width + 1, height + 1))
+        }
+        _ => None,
+    }
+}
+
+// the tags of the first IFD
+fn tiff(data: &[u8]) -> Option<(u32, u32)> {
+    let mut tiff = Tiff::new(data.to_vec());
+    let offset = tiff.first_ifd().ok()??;
+    let (entries, _) = tiff.ifd(offset).ok()?;
+    let tag = |tag| {
+        entries
+            .iter()
+            .find(|entry| entry.tag == tag)
+            .and_then(|entry| tiff.number(entry))
+    };
+    Some((tag(TAG_IMAGE_WIDTH)?, tag(TAG_IMAGE_LENGTH)?))
+}
+
+fn be32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn le32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn le16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use ppt_img_extract::output::write_atomically;
+use serde::Serialize;
+
+use crate::{catalog::Deck, ExportMediaError};
+
+pub const DUPLICATES_FILE: &str = "duplicate_slides.json";
+// the share of their words two slides with the same images have in common
+// from which they're taken for the same slide reworded
+const MIN_SIMILARITY: f64 = 0.8;
+
+#[derive(Debug, Serialize)]
+struct Duplicates<'a> {
+    groups: Vec<Group<'a>>,
+}
+
+// slides that are the same, or else nearly, across the decks of a batch
+#[derive(Debug, Serialize)]
+struct Group<'a> {
+    // the same words and images, not only nearly
+    identical: bool,
+    slides: Vec<SlideRef<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct SlideRef<'a> {
+    source: &'a str,
+    output_dir: &'a str,
+    page_no: u64,
+}
+
+// what a slide is compared by, its words whatever their case, punctuation and
+// layout, and its images by their checksums
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Fingerprint {
+    images: Vec<String>,
+    words: Vec<String>,
+}
+
+impl Fingerprint {
+    fn new(texts: &[String], image_hashes: &[String]) -> Self {
+        let mut images = image_hashes.to_vec();
+        images.sort();
+        images.dedup();
+        let words = texts
+            .iter()
+            .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        Fingerprint { images, words }
+    }
+
+    // the words in common of the words of either, the same images taken for
+    // granted
+    fn similarity(&self, other: &Fingerprint) -> f64 {
+        let a: BTreeSet<&String> = self.words.iter().collect();
+        let b: BTreeSet<&String> = other.words.iter().collect();
+        let union = a.union(&b).count();
+        if union == 0 {
+            return 1.0;
+        }
+        a.intersection(&b).count() as f64 / union as f64
+    }
+}
+
+// group the slides of the decks that are the same or nearly, within a deck
+// or across decks, and write the groups next to the catalog. Slides with
+// neither text nor images are left out.
+pub fn write(output_dir: &Path, decks: &[Deck]) -> Result<(), ExportMediaError> {
+    // fingerprint -> the slides that have it
+    let mut identical: BTreeMap<Fingerprint, Vec<SlideRef>> = BTreeMap::new();
+    for deck in decks {
+        for slide in &deck.slides {
+            let fingerprint = Fingerprint::new(&slide.texts, &slide.image_hashes);
+            if fingerprint.words.is_empty() && fingerprint.images.is_empty() {
+                continue;
+            }
+            identical.entry(fingerprint).or_default().push(SlideRef {
+                source: &deck.source,
+                output_dir: &deck.output_dir,
+                page_no: slide.page_no,
+            });
+        }
+    }
+    // the distinct slides are compared with those of the same images, which
+    // the fingerprints are ordered by
+    let fingerprints: Vec<(Fingerprint, Vec<SlideRef>)> = identical.into_iter().collect();
+    let mut merged: Vec<Option<usize>> = vec![None; fingerprints.len()];
+    for i in 0..fingerprints.len() {
+        if merged[i].is_some() {
+            continue;
+        }
+        for j in i + 1..fingerprints.len() {
+            if fingerprints[j].0.images != fingerprints[i].0.images {
+                break;
+            }
+            if merged[j].is_none()
+                && fingerprints[i].0.similarity(&fingerprints[j].0) >= MIN_SIMILARITY
+            {
+                merged[j] = Some(i);
+            }
+        }
+    }
+    let mut groups: BTreeMap<usize, Group> = BTreeMap::new();
+    for (i, (_, slides)) in fingerprints.into_iter().enumerate() {
+        let group = groups.entry(merged[i].unwrap_or(i)).or_insert(Group {
+            identical: true,
+            slides: Vec::new(),
+        });
+        group.identical &= merged[i].is_none();
+        group.slides.extend(slides);
+    }
+    let duplicates = Duplicates {
+        groups: groups
+            .into_values()
+            .filter(|group| group.slides.len() > 1)
+            .collect(),
+    };
+    let j = serde_json::to_string_pretty(&duplicates).map_err(|e| e.to_string())?;
+    write_atomically(&output_dir.join(DUPLICATES_FILE), j.as_bytes())?;
+    Ok(())
+}
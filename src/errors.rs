@@ -0,0 +1,85 @@
+use log::error;
+use std::{fmt, mem};
+
+use serde::Serialize;
+
+use crate::ExtractVisitor;
+
+// written next to the index when parts failed to extract
+pub const ERRORS_FILE: &str = "errors.json";
+
+// a part the extraction went on without
+#[derive(Debug, Clone, Serialize)]
+pub struct PartError {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part: Option<String>,
+    // the extractor that was running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slide: Option<u32>,
+    pub error: String,
+}
+
+// the errors of an extraction so far, and what it's working on to record them
+// with
+#[derive(Debug, Default)]
+pub struct Errors {
+    pub(crate) phase: Option<&'static str>,
+    pub(crate) slide: Option<u32>,
+    errors: Vec<PartError>,
+}
+
+impl Errors {
+    pub(crate) fn new(phase: &'static str) -> Self {
+        Errors {
+            phase: Some(phase),
+            ..Errors::default()
+        }
+    }
+
+    // log that part failed, what failed being e.g. "failed to read media:",
+    // and record it
+    pub(crate) fn error(&mut self, what: &str, part: Option<&str>, e: impl fmt::Display) {
+        let error = e.to_string();
+        match part {
+            Some(part) => error!("{} {}, error: {}", what, part, error),
+            None => error!("{}, error: {}", what, error),
+        }
+        self.errors.push(PartError {
+            part: part.map(String::from),
+            phase: self.phase,
+            slide: self.slide,
+            error,
+        });
+    }
+
+    pub(crate) fn extend(&mut self, errors: Vec<PartError>) {
+        self.errors.extend(errors);
+    }
+
+    // the errors recorded since the last take
+    pub(crate) fn take(&mut self) -> Vec<PartError> {
+        mem::take(&mut self.errors)
+    }
+}
+
+// collects the errors
+impl ExtractVisitor for Vec<PartError> {
+    fn on_error(&mut self, error: &PartError) {
+        self.push(error.clone());
+    }
+}
+
+// the errors.json of the extraction of source, with the error it failed with
+// if it did
+pub fn report(source: &str, errors: &[PartError], failed: Option<&str>) -> String {
+    let mut report = serde_json::json!({
+        "source": source,
+        "errors": errors,
+    });
+    if let Some(failed) = failed {
+        report["failed"] = serde_json::Value::from(failed);
+    }
+    serde_json::to_string_pretty(&report).unwrap()
+}
@@ -0,0 +1,116 @@
+use serde::Serialize;
+use std::{io::Write, time::Instant};
+
+use ppt_img_extract::{package::Package, ExportMediaError, ExtractVisitor, SingleRes};
+
+// a progress event, printed as one JSON object per line on stderr
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Started {
+        input: &'a str,
+        slides: usize,
+        masters: usize,
+        media: usize,
+    },
+    SlideDone {
+        input: &'a str,
+        slide: u32,
+        slides: usize,
+    },
+    MediaExported {
+        input: &'a str,
+        name: &'a str,
+        bytes: usize,
+    },
+    Finished {
+        input: &'a str,
+        slides: usize,
+        masters: usize,
+        media: usize,
+        bytes: u64,
+        elapsed_ms: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+// the progress of extracting one presentation, reported as it goes
+pub struct Events<'a> {
+    input: &'a str,
+    start: Instant,
+    // totals of the package, for slide_done to tell how far along it is
+    total_slides: usize,
+    slides: usize,
+    masters: usize,
+    media: usize,
+    bytes: u64,
+}
+
+impl<'a> Events<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Events {
+            input,
+            start: Instant::now(),
+            total_slides: 0,
+            slides: 0,
+            masters: 0,
+            media: 0,
+            bytes: 0,
+        }
+    }
+
+    pub fn finished(&self, error: Option<&ExportMediaError>) {
+        emit(&Event::Finished {
+            input: self.input,
+            slides: self.slides,
+            masters: self.masters,
+            media: self.media,
+            bytes: self.bytes,
+            elapsed_ms: self.start.elapsed().as_secs_f64() * 1000.0,
+            error: error.map(|e| e.to_string()),
+        });
+    }
+}
+
+impl ExtractVisitor for Events<'_> {
+    fn on_start(&mut self, pkg: &Package) {
+        self.total_slides = pkg.slides.len();
+        emit(&Event::Started {
+            input: self.input,
+            slides: pkg.slides.len(),
+            masters: pkg.masters.len(),
+            media: pkg.media.len(),
+        });
+    }
+
+    fn on_slide(&mut self, slide: &SingleRes) {
+        self.slides += 1;
+        emit(&Event::SlideDone {
+            input: self.input,
+            slide: slide.page_no,
+            slides: self.total_slides,
+        });
+    }
+
+    fn on_master(&mut self, _master: &SingleRes) {
+        self.masters += 1;
+    }
+
+    fn on_media(&mut self, _part_name: &str, filename: &str, data: &[u8]) {
+        self.media += 1;
+        self.bytes += data.len() as u64;
+        emit(&Event::MediaExported {
+            input: self.input,
+            name: filename,
+            bytes: data.len(),
+        });
+    }
+}
+
+// a whole line at once, so that events and log lines don't interleave
+fn emit(event: &Event) {
+    let mut line = serde_json::to_vec(event).unwrap();
+    line.push(b'\n');
+    let _ = std::io::stderr().write_all(&line);
+}
@@ -0,0 +1,103 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::strip::{Tiff, PNG_SIGNATURE};
+
+const TAG_MAKE: u16 = 271;
+const TAG_MODEL: u16 = 272;
+const TAG_DATE_TIME: u16 = 306;
+const TAG_EXIF_IFD: u16 = 34665;
+const TAG_GPS_IFD: u16 = 34853;
+const TAG_DATE_TIME_ORIGINAL: u16 = 36867;
+
+// the EXIF fields of an exported image that tell where it came from
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Exif {
+    // when the picture was taken, or else last changed, as "YYYY:MM:DD HH:MM:SS"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub make: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    // whether the image carries GPS coordinates
+    pub gps: bool,
+}
+
+// get the EXIF of a JPEG, PNG or TIFF image, none when it has none
+pub fn exif(data: &[u8]) -> Result<Option<Exif>, String> {
+    let tiff = if data.starts_with(&[0xff, 0xd8]) {
+        jpeg_exif(data)
+    } else if data.starts_with(PNG_SIGNATURE) {
+        png_exif(data)
+    } else {
+        Some(data)
+    };
+    let mut tiff = match tiff {
+        Some(tiff) => Tiff::new(tiff.to_vec()),
+        None => return Ok(None),
+    };
+    let offset = match tiff.first_ifd()? {
+        Some(offset) => offset,
+        None => return Ok(None),
+    };
+    let (entries, _) = tiff.ifd(offset)?;
+    let mut res = Exif::default();
+    let mut found = false;
+    for entry in &entries {
+        match entry.tag {
+            TAG_MAKE => res.make = tiff.ascii(entry),
+            TAG_MODEL => res.model = tiff.ascii(entry),
+            TAG_DATE_TIME if res.date.is_none() => res.date = tiff.ascii(entry),
+            TAG_GPS_IFD => res.gps = true,
+            TAG_EXIF_IFD => {
+                let (entries, _) = tiff.ifd(entry.value as usize)?;
+                let original = entries
+                    .iter()
+                    .find(|entry| entry.tag == TAG_DATE_TIME_ORIGINAL)
+                    .and_then(|entry| tiff.ascii(entry));
+                if original.is_some() {
+                    res.date = original;
+                }
+            }
+            _ => continue,
+        }
+        found = true;
+    }
+    Ok(found.then_some(res))
+}
+
+// the TIFF structure in the APP1 segment
+fn jpeg_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xff {
+        let marker = data[pos + 1];
+        // the scan is image data
+        if marker == 0xda || marker == 0xd9 {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let payload = data.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xe1 && payload.starts_with(b"Exif\0\0") {
+            return Some(&payload[6..]);
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+// the eXIf chunk
+fn png_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let chunk = data.get(pos + 8..pos + 8 + len)?;
+        match kind {
+            b"eXIf" => return Some(chunk),
+            b"IEND" => return None,
+            _ => pos += 12 + len,
+        }
+    }
+    None
+}
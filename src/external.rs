@@ -0,0 +1,110 @@
+#[cfg(feature = "http")]
+use log::{debug, trace};
+#[cfg(feature = "http")]
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{errors::Errors, package};
+#[cfg(feature = "http")]
+use crate::{http, output::Output, Options, Res};
+
+// an image linked rather than embedded, a relationship with
+// TargetMode="External"
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ExternalImage {
+    pub rel_id: String,
+    // the target as written, a url or a path on the author's machine
+    pub target: String,
+    // exported file name, when fetched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+// get the externally linked images of a part
+pub fn external_images<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+    errors: &mut Errors,
+) -> Vec<ExternalImage> {
+    match package::part_rels(archive, part_name) {
+        Ok(rels) => rels
+            .into_iter()
+            .filter(|rel| rel.external && rel.kind == package::REL_IMAGE)
+            .map(|rel| ExternalImage {
+                rel_id: rel.id,
+                target: rel.target,
+                image: None,
+            })
+            .collect(),
+        Err(e) => {
+            errors.error("failed to get rels of", Some(part_name), e);
+            Vec::new()
+        }
+    }
+}
+
+// blank the targets, which as paths on the author's machine often name them,
+// with anonymize
+pub fn anonymize(images: &mut [ExternalImage]) {
+    for image in images {
+        image.target.clear();
+    }
+}
+
+// download the http(s) targets into the output as external<n>_<name>, each
+// target once, other targets are left as they are
+#[cfg(feature = "http")]
+pub fn fetch(
+    images: &mut [ExternalImage],
+    fetched: &mut HashMap<String, Option<String>>,
+    output: &mut Output,
+    res: &mut Res,
+    options: &Options,
+    errors: &mut Errors,
+) {
+    for image in images {
+        if let Some(name) = fetched.get(&image.target) {
+            image.image = name.clone();
+            continue;
+        }
+        let url = image.target.as_str();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            debug!("not fetching {}, not an http url", url);
+            continue;
+        }
+        let base_name: String = http::display(url)
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+            .collect();
+        let name = format!("external{}_{}", fetched.len() + 1, base_name);
+        let exported = http::download(url, &[], options.max_media_size)
+            .and_then(|data| crate::export_media(output, &mut data.as_slice(), name, options));
+        let name = match exported {
+            Ok(exported) => {
+                let name = exported.filename;
+                trace!("fetched {} as {}", http::display(url), name);
+                res.exif
+                    .extend(exported.exif.map(|exif| (name.clone(), exif)));
+                res.dimensions.extend(
+                    exported
+                        .dimensions
+                        .map(|dimensions| (name.clone(), dimensions)),
+                );
+                res.media.insert(name.clone(), exported.checksum);
+                Some(name)
+            }
+            Err(e) => {
+                errors.error("failed to fetch", Some(http::display(url)), e);
+                None
+            }
+        };
+        image.image = name.clone();
+        fetched.insert(image.target.clone(), name);
+    }
+}
@@ -0,0 +1,1556 @@
+use log::{debug, info, trace};
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{self, Read},
+    mem,
+    path::Path,
+    time::Instant,
+};
+
+#[cfg(feature = "contact-sheet")]
+use crate::contact;
+#[cfg(feature = "lang-detect")]
+use crate::lang;
+#[cfg(feature = "similar")]
+use crate::phash;
+#[cfg(feature = "plugins")]
+use crate::plugin::{Fields, Plugins};
+#[cfg(feature = "media-probe")]
+use crate::probe;
+use crate::{
+    bundle, chart_data, chunks, default_name,
+    errors::Errors,
+    export_media, external, fonts, graph, keywords, layout, limits, logging,
+    package::{self, ContentTypes, Package},
+    page_no, part_images, part_prompts, rels, reveal, sanitize, shapes, signatures, slide, sniff,
+    srt, svg, template, text, theme, vba, write_media,
+    writer::Decompressed,
+    xml, EffectiveImage, ExportMediaError, ExtractVisitor, GraphFormat, LayoutFormat, MediaWriter,
+    Options, Output, Profile, ReadSeek, Res, SingleRes, Stats,
+};
+
+// what the extractors share while extracting a presentation
+pub struct Context<'a, 'r> {
+    pub(crate) archive: &'a mut zip::ZipArchive<Box<dyn ReadSeek + 'r>>,
+    pub(crate) pkg: &'a Package,
+    pub(crate) options: &'a Options,
+    pub(crate) output: &'a mut Output,
+    pub(crate) res: Res,
+    // media file names and sizes that a dry run would have exported
+    pub(crate) dry_media: Vec<(String, u64)>,
+    pub(crate) profile: Profile,
+    // the parts that failed, until reported to the visitor
+    pub(crate) errors: Errors,
+    // media part -> exported file name, when named after a template or
+    // sanitized
+    media_names: BTreeMap<String, String>,
+    // media are written on a thread of their own, which holds the output
+    // until they all are
+    writer: Option<MediaWriter>,
+    // the package thumbnail, usually docProps/thumbnail.jpeg
+    thumbnail: Option<package::Rel>,
+    // external image target -> exported file name, each fetched once
+    #[cfg(feature = "http")]
+    fetched: HashMap<String, Option<String>>,
+    cues: Vec<srt::Cue>,
+    chunks: Vec<chunks::Chunk>,
+    // the shapes of the slides by page number, kept for their layout
+    layout_shapes: Vec<(u32, Vec<shapes::Shape>)>,
+    #[cfg(feature = "plugins")]
+    plugins: Plugins,
+    // media part -> the fields plugins added, moved to the index once the
+    // media are written
+    #[cfg(feature = "plugins")]
+    media_fields: HashMap<String, Fields>,
+    // the slides whose text matches --grep and the media they show, all of
+    // them without it
+    selection: Option<Selection>,
+    enabled: HashSet<&'static str>,
+    visitor: &'a mut dyn ExtractVisitor,
+}
+
+// a kind of content read from the package, run in turn by extract_archive
+pub trait Extractor {
+    // the name it's enabled and disabled by, and timed under
+    fn name(&self) -> &'static str;
+    // whether it runs unless enabled or disabled by name
+    fn default_enabled(&self, options: &Options) -> bool;
+    // an error ends the extraction, those an extractor gets past it logs
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError>;
+}
+
+// the extractors in the order they run, the slides after the media they
+// show and the files written directly after the media
+pub fn pipeline() -> Vec<Box<dyn Extractor>> {
+    vec![
+        Box::new(Fonts),
+        Box::new(Vba),
+        Box::new(Signatures),
+        Box::new(Media),
+        Box::new(Slides),
+        Box::new(Masters),
+        Box::new(Themes),
+        Box::new(Keywords),
+        Box::new(ShapesSvg),
+        Box::new(ChartData),
+        Box::new(Subtitles),
+        Box::new(Graph),
+        Box::new(Reveal),
+        Box::new(Bundle),
+        Box::new(Chunks),
+        Box::new(Layout),
+        #[cfg(feature = "contact-sheet")]
+        Box::new(ContactSheet),
+        #[cfg(feature = "similar")]
+        Box::new(Similar),
+    ]
+}
+
+// the names of the extractors, for --enable and --disable
+pub fn names() -> Vec<&'static str> {
+    pipeline()
+        .iter()
+        .map(|extractor| extractor.name())
+        .collect()
+}
+
+impl<'a, 'r> Context<'a, 'r> {
+    pub(crate) fn new(
+        archive: &'a mut zip::ZipArchive<Box<dyn ReadSeek + 'r>>,
+        pkg: &'a Package,
+        doc_title: &str,
+        options: &'a Options,
+        output: &'a mut Output,
+        res: Res,
+        visitor: &'a mut dyn ExtractVisitor,
+    ) -> Result<Self, ExportMediaError> {
+        let enabled = pipeline()
+            .iter()
+            .filter(|extractor| {
+                let name = extractor.name().to_owned();
+                (extractor.default_enabled(options) || options.enable.contains(&name))
+                    && !options.disable.contains(&name)
+            })
+            .map(|extractor| extractor.name())
+            .collect();
+        let mut cx = Context {
+            archive,
+            pkg,
+            options,
+            output,
+            res,
+            dry_media: Vec::new(),
+            profile: Profile::default(),
+            errors: Errors::default(),
+            media_names: BTreeMap::new(),
+            writer: None,
+            thumbnail: None,
+            #[cfg(feature = "http")]
+            fetched: HashMap::new(),
+            cues: Vec::new(),
+            chunks: Vec::new(),
+            layout_shapes: Vec::new(),
+            #[cfg(feature = "plugins")]
+            plugins: Plugins::load(&options.plugins)?,
+            #[cfg(feature = "plugins")]
+            media_fields: HashMap::new(),
+            selection: None,
+            enabled,
+            visitor,
+        };
+        cx.name_media(doc_title)?;
+        if let Some(grep) = &options.grep {
+            cx.selection = Some(cx.select(grep));
+        }
+        Ok(cx)
+    }
+
+    pub(crate) fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.contains(name)
+    }
+
+    // the slide size in EMU from the presentation part
+    fn slide_size(&mut self) -> Result<(i64, i64), ExportMediaError> {
+        let file = self
+            .archive
+            .by_name(&self.pkg.main_part)
+            .map_err(|e| e.to_string())?;
+        let text = xml::read(file)?;
+        let doc = xml::parse(&text)
+            .map_err(|e| ExportMediaError::Parse(e, self.pkg.main_part.clone()))?;
+        Ok(svg::slide_size(doc.root_element()))
+    }
+
+    // read the text of the slides to find those matching grep, and the media
+    // they show
+    fn select(&mut self, grep: &Regex) -> Selection {
+        let mut selection = Selection::default();
+        for part_name in &self.pkg.slides {
+            let content = self
+                .archive
+                .by_name(part_name)
+                .map_err(|e| e.to_string())
+                .and_then(|file| slide(file, &self.options.exclude_placeholders, &HashSet::new()));
+            let texts = match content {
+                Ok(content) => text::clean(content.texts, &self.options.text),
+                Err(e) => {
+                    self.errors
+                        .error("failed to read slide:", Some(part_name), e);
+                    continue;
+                }
+            };
+            if !grep.is_match(&texts.join("\n")) {
+                continue;
+            }
+            selection.slides.insert(part_name.clone());
+            let rels = package::part_rels(self.archive, part_name).unwrap_or_else(|e| {
+                self.errors
+                    .error("failed to get rels of", Some(part_name), e);
+                Vec::new()
+            });
+            selection.media.extend(
+                rels.into_iter()
+                    .filter(|rel| !rel.external && self.pkg.media.contains(&rel.target))
+                    .map(|rel| rel.target),
+            );
+        }
+        info!(
+            "{} of {} slides match {}",
+            selection.slides.len(),
+            self.pkg.slides.len(),
+            grep
+        );
+        selection
+    }
+
+    // work out the names media are exported as, which the slides list them
+    // by whether or not the media are exported
+    fn name_media(&mut self, doc_title: &str) -> Result<(), ExportMediaError> {
+        let options = self.options;
+        if let Some(template) = &options.name_template {
+            let doc = Path::new(doc_title).file_stem().unwrap_or_default();
+            self.media_names =
+                template::media_names(self.archive, self.pkg, template, &doc.to_string_lossy())?;
+        }
+        if options.fix_extensions {
+            self.fix_extensions();
+        }
+        if options.sanitize_names {
+            let names = self
+                .pkg
+                .media
+                .iter()
+                .map(|part_name| (part_name.clone(), self.export_name(part_name)))
+                .collect();
+            let sanitized = sanitize::names(names);
+            for (part_name, name) in &sanitized {
+                if *name != self.export_name(part_name) {
+                    self.res
+                        .sanitized_names
+                        .insert(part_name.clone(), name.clone());
+                }
+            }
+            self.media_names = sanitized;
+        }
+        Ok(())
+    }
+
+    // name media whose extension doesn't match their content after what
+    // they are, by their first bytes or else their content type
+    fn fix_extensions(&mut self) {
+        let content_types = package::content_types(self.archive).unwrap_or_else(|e| {
+            self.errors.error("failed to read content types", None, e);
+            ContentTypes::default()
+        });
+        let mut taken: HashSet<String> = self
+            .pkg
+            .media
+            .iter()
+            .map(|part_name| self.export_name(part_name))
+            .collect();
+        for part_name in &self.pkg.media {
+            let mut head = Vec::new();
+            if let Err(e) = self
+                .archive
+                .by_name(part_name)
+                .map_err(|e| io::Error::other(e.to_string()))
+                .and_then(|file| file.take(sniff::SNIFF_LEN).read_to_end(&mut head))
+            {
+                self.errors
+                    .error("failed to read media:", Some(part_name), e);
+                continue;
+            }
+            let sniffed = match sniff::extension(&head).or_else(|| {
+                content_types
+                    .get(part_name)
+                    .and_then(sniff::content_type_extension)
+            }) {
+                Some(sniffed) => sniffed,
+                None => continue,
+            };
+            let name = self.export_name(part_name);
+            // the extension of the file name, not of a directory kept with it
+            let dir_len = name.rfind('/').map_or(0, |i| i + 1);
+            let stem = match name[dir_len..].rsplit_once('.') {
+                Some((_, ext)) if sniff::matches(ext, sniffed) => continue,
+                Some((stem, _)) => &name[..dir_len + stem.len()],
+                None => name.as_str(),
+            };
+            let mut fixed = format!("{}.{}", stem, sniffed);
+            let mut n = 1;
+            while taken.contains(&fixed) {
+                n += 1;
+                fixed = format!("{}_{}.{}", stem, n, sniffed);
+            }
+            debug!("exporting {} as {}, it's {}", part_name, fixed, sniffed);
+            taken.insert(fixed.clone());
+            self.res
+                .corrected_names
+                .insert(part_name.clone(), fixed.clone());
+            self.media_names.insert(part_name.clone(), fixed);
+        }
+    }
+
+    fn export_name(&self, part_name: &str) -> String {
+        export_name(&self.media_names, part_name, self.options.keep_paths)
+    }
+
+    // pass the media the writer decompressed so far on to the visitor and
+    // the plugins
+    pub(crate) fn visit_media(&mut self) {
+        let decompressed: Vec<_> = match &self.writer {
+            Some(writer) => writer.decompressed().collect(),
+            None => return,
+        };
+        for media in decompressed {
+            self.visit(media);
+        }
+    }
+
+    fn visit(&mut self, media: Decompressed) {
+        self.visitor
+            .on_media(&media.part_name, &media.filename, &media.data);
+        if let Some(written) = &media.written {
+            self.visitor.on_media_written(&media.part_name, written);
+        }
+        #[cfg(feature = "plugins")]
+        {
+            let fields = self.plugins.media(&media.filename, &media.data);
+            if !fields.is_empty() {
+                self.media_fields.insert(media.part_name, fields);
+            }
+        }
+    }
+
+    // wait for the media writer to give the output back and list what it
+    // wrote, before anything else is written
+    pub(crate) fn finish_media(&mut self) -> Result<(), ExportMediaError> {
+        self.visit_media();
+        let writer = match self.writer.take() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        let finished = writer.finish()?;
+        *self.output = finished.output;
+        self.errors.extend(finished.errors);
+        for media in finished.decompressed {
+            self.visit(media);
+        }
+        for written in finished.written {
+            if self
+                .thumbnail
+                .as_ref()
+                .is_some_and(|rel| rel.target == written.part_name)
+            {
+                self.res.thumbnail = Some(written.filename.clone());
+            } else {
+                self.profile
+                    .entry_took("media", &written.part_name, written.elapsed);
+            }
+            self.res
+                .exif
+                .extend(written.exif.map(|exif| (written.filename.clone(), exif)));
+            self.res.dimensions.extend(
+                written
+                    .dimensions
+                    .map(|dimensions| (written.filename.clone(), dimensions)),
+            );
+            #[cfg(feature = "plugins")]
+            if let Some(fields) = self.media_fields.remove(&written.part_name) {
+                self.res
+                    .media_fields
+                    .insert(written.filename.clone(), fields);
+            }
+            self.res.media.insert(written.filename, written.checksum);
+        }
+        Ok(())
+    }
+
+    // pass the errors recorded so far on to the visitor
+    pub(crate) fn report_errors(&mut self) {
+        for error in self.errors.take() {
+            self.visitor.on_error(&error);
+        }
+    }
+
+    // write a file made from the package, such as the subtitles or the graph,
+    // returning the name it was written as. Fails only when the media writer
+    // did, taking the output with it
+    fn write_file(
+        &mut self,
+        filename: String,
+        data: Vec<u8>,
+        what: &str,
+    ) -> Result<Option<String>, ExportMediaError> {
+        if self.options.dry_run {
+            self.dry_media.push((filename, data.len() as u64));
+            return Ok(None);
+        }
+        self.finish_media()?;
+        match write_media(
+            self.output,
+            &mut data.as_slice(),
+            filename,
+            self.options.md5,
+        ) {
+            Ok((filename, checksum)) => {
+                self.res.media.insert(filename.clone(), checksum);
+                Ok(Some(filename))
+            }
+            Err(e) => {
+                self.errors.error("failed to export", Some(what), e);
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Selection {
+    slides: HashSet<String>,
+    media: HashSet<String>,
+}
+
+fn selects(selection: &Option<Selection>, part_name: &str, media: bool) -> bool {
+    match selection {
+        Some(selection) if media => selection.media.contains(part_name),
+        Some(selection) => selection.slides.contains(part_name),
+        None => true,
+    }
+}
+
+fn export_name(
+    media_names: &BTreeMap<String, String>,
+    part_name: &str,
+    keep_paths: bool,
+) -> String {
+    media_names
+        .get(part_name)
+        .cloned()
+        .unwrap_or_else(|| default_name(part_name, keep_paths))
+}
+
+struct Fonts;
+
+impl Extractor for Fonts {
+    fn name(&self) -> &'static str {
+        "fonts"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.fonts
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let fonts_output = (!cx.options.dry_run).then_some(&mut *cx.output);
+        match fonts::export_fonts(cx.archive, cx.pkg, fonts_output, &mut cx.errors) {
+            Ok(fonts) => cx.res.fonts = fonts,
+            Err(e) => cx.errors.error("failed to export fonts", None, e),
+        }
+        Ok(())
+    }
+}
+
+struct Vba;
+
+impl Extractor for Vba {
+    fn name(&self) -> &'static str {
+        "vba"
+    }
+
+    // the project is only exported with --vba, but always listed
+    fn default_enabled(&self, _: &Options) -> bool {
+        true
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let vba_output = (cx.options.vba && !cx.options.dry_run).then_some(&mut *cx.output);
+        match vba::vba(cx.archive, cx.pkg, vba_output) {
+            Ok(vba_res) => cx.res.vba = vba_res,
+            Err(e) => cx.errors.error("failed to read VBA project", None, e),
+        }
+        Ok(())
+    }
+}
+
+struct Signatures;
+
+impl Extractor for Signatures {
+    fn name(&self) -> &'static str {
+        "signatures"
+    }
+
+    fn default_enabled(&self, _: &Options) -> bool {
+        true
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        match signatures::signatures(cx.archive, &mut cx.errors) {
+            Ok(mut signatures) => {
+                // self-signed certificates name the signer as the issuer too
+                if cx.options.anonymize {
+                    for signature in signatures.iter_mut() {
+                        signature.signer = None;
+                        signature.subject = None;
+                        signature.issuer = None;
+                    }
+                }
+                cx.res.signatures = signatures
+            }
+            Err(e) => cx.errors.error("failed to read signatures", None, e),
+        }
+        Ok(())
+    }
+}
+
+// the media parts and the package thumbnail
+struct Media;
+
+impl Extractor for Media {
+    fn name(&self) -> &'static str {
+        "media"
+    }
+
+    fn default_enabled(&self, _: &Options) -> bool {
+        true
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let (pkg, options) = (cx.pkg, cx.options);
+        cx.writer = (!options.dry_run).then(|| {
+            MediaWriter::spawn(mem::replace(cx.output, Output::memory()), options.clone())
+        });
+        // the media are only copied out of the archive here, the writer
+        // decompresses them while the slides are parsed
+        for part_name in &pkg.media {
+            if !selects(&cx.selection, part_name, true) {
+                continue;
+            }
+            let file = match cx.archive.by_name(part_name) {
+                Ok(file) => file,
+                Err(e) => {
+                    cx.errors.error("failed to open media:", Some(part_name), e);
+                    continue;
+                }
+            };
+            let filename = export_name(&cx.media_names, part_name, options.keep_paths);
+            if options.dry_run {
+                cx.dry_media.push((filename, file.size()));
+                continue;
+            }
+            if file.size() > options.max_media_size {
+                let e = format!(
+                    "{} bytes is more than the limit of {}",
+                    file.size(),
+                    options.max_media_size
+                );
+                cx.errors.error("skipped media:", Some(part_name), e);
+                continue;
+            }
+            let sent = match &cx.writer {
+                Some(writer) => writer.send(part_name, filename, file, true),
+                None => continue,
+            };
+            if let Err(e) = sent {
+                cx.errors.error("failed to read media:", Some(part_name), e);
+            }
+            cx.visit_media();
+        }
+
+        cx.thumbnail = package::part_rels(cx.archive, "")
+            .unwrap_or_else(|e| {
+                cx.errors.error("failed to get package rels", None, e);
+                Vec::new()
+            })
+            .into_iter()
+            .find(|rel| rel.kind == package::REL_THUMBNAIL && !rel.external);
+        if let Some(rel) = &cx.thumbnail {
+            let ext = match Path::new(&rel.target).extension() {
+                Some(ext) if ext.eq_ignore_ascii_case("jpeg") => "jpg".to_owned(),
+                Some(ext) => ext.to_string_lossy().to_lowercase(),
+                None => "jpg".to_owned(),
+            };
+            let filename = format!("_thumbnail.{}", ext);
+            match cx.archive.by_name(&rel.target) {
+                Ok(file) if options.dry_run => cx.dry_media.push((filename, file.size())),
+                Ok(file) if file.size() > options.max_media_size => {
+                    let e = format!(
+                        "{} bytes is more than the limit of {}",
+                        file.size(),
+                        options.max_media_size
+                    );
+                    cx.errors.error("skipped thumbnail:", Some(&rel.target), e)
+                }
+                Ok(file) => {
+                    if let Some(writer) = &cx.writer {
+                        if let Err(e) = writer.send(&rel.target, filename, file, false) {
+                            cx.errors
+                                .error("failed to read thumbnail:", Some(&rel.target), e);
+                        }
+                    }
+                }
+                Err(e) => cx
+                    .errors
+                    .error("failed to open thumbnail:", Some(&rel.target), e),
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Slides;
+
+impl Extractor for Slides {
+    fn name(&self) -> &'static str {
+        "slides"
+    }
+
+    fn default_enabled(&self, _: &Options) -> bool {
+        true
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        // fetched images are written along with the slides
+        #[cfg(feature = "http")]
+        if cx.options.fetch_external {
+            cx.finish_media()?;
+        }
+        let srt = cx.is_enabled("srt");
+        let chunk = cx.is_enabled("chunks");
+        let layout = cx.is_enabled("layout");
+        let Context {
+            archive,
+            pkg,
+            options,
+            output,
+            res,
+            profile,
+            media_names,
+            #[cfg(feature = "http")]
+            fetched,
+            cues,
+            chunks,
+            layout_shapes,
+            selection,
+            #[cfg(feature = "plugins")]
+            plugins,
+            visitor,
+            errors,
+            ..
+        } = cx;
+        let (pkg, options) = (*pkg, *options);
+        let text_opts = options.text;
+        // images are found by their part name and listed by the name they
+        // were exported as
+        let rename =
+            |image: &mut String| *image = export_name(media_names, image, options.keep_paths);
+
+        // layout and master part -> its images and whether it shows the
+        // master's
+        let mut inherited = HashMap::new();
+        if options.resolve_inherited {
+            for part_name in pkg.layouts.iter().chain(&pkg.masters) {
+                let (mut images, show_master_shapes) = part_images(archive, part_name);
+                images.iter_mut().for_each(rename);
+                images.sort();
+                inherited.insert(part_name.as_str(), (images, show_master_shapes));
+            }
+        }
+
+        let mut deck_stats = options.stats.then(Stats::default);
+        let notes_exclude: Vec<String> = srt::NOTES_EXCLUDE.iter().map(|s| s.to_string()).collect();
+        // layout and master part -> its placeholder texts
+        let mut layout_prompts = HashMap::new();
+        for (i, part_name) in pkg.slides.iter().enumerate() {
+            // numbered among all the slides, matching or not
+            if !selects(selection, part_name, false) {
+                continue;
+            }
+            let page_no = i as u32 + 1;
+            logging::set_slide(Some(page_no));
+            errors.slide = Some(page_no);
+            let start = Instant::now();
+            let mut single_res = SingleRes::new(page_no, part_name.clone(), false);
+            (single_res.layout_no, single_res.master_no) = pkg.layout_and_master(part_name);
+            // image rels, kept to resolve the pictures
+            let mut slide_rels = BTreeMap::new();
+            match archive.by_name(&package::rels_part(part_name)) {
+                Ok(file) => match rels(file, part_name) {
+                    Ok(rels) => {
+                        trace!("got page {:?}, rels: {:?}", page_no, rels);
+                        single_res.images = rels.values().cloned().collect();
+                        slide_rels = rels;
+                    }
+                    Err(e) => errors.error("failed to get rels of", Some(part_name), e),
+                },
+                Err(e) => {
+                    debug!("no rels for {}, error: {}", part_name, e)
+                }
+            }
+            let prompts = match options.exclude_prompt_text {
+                true => {
+                    let layout = pkg.slide_layouts.get(part_name);
+                    let master = layout.and_then(|layout| pkg.layout_masters.get(layout));
+                    layout
+                        .into_iter()
+                        .chain(master)
+                        .flat_map(|part| {
+                            layout_prompts
+                                .entry(part.clone())
+                                .or_insert_with(|| part_prompts(archive, part))
+                                .clone()
+                        })
+                        .collect()
+                }
+                false => HashSet::new(),
+            };
+            let file = match archive.by_name(part_name) {
+                Ok(file) => file,
+                Err(e) => {
+                    errors.error("failed to open slide:", Some(part_name), e);
+                    continue;
+                }
+            };
+            let content = match slide(file, &options.exclude_placeholders, &prompts) {
+                Ok(content) => content,
+                Err(e) => {
+                    errors.error("failed to get slide", Some(part_name), e);
+                    continue;
+                }
+            };
+            trace!("got page {:?}, texts: {:?}", page_no, content.texts);
+            single_res.texts = text::clean(content.texts, &text_opts);
+            if let Some(redactor) = &options.redact {
+                redactor.redact_all(&mut single_res.texts, &mut res.redactions);
+            }
+            let mut notes = pkg
+                .notes
+                .get(part_name)
+                .filter(|_| srt || options.notes)
+                .and_then(|notes_part| archive.by_name(notes_part).ok())
+                .and_then(|file| match slide(file, &notes_exclude, &HashSet::new()) {
+                    Ok(notes) => Some(text::clean(notes.texts, &text_opts)),
+                    Err(e) => {
+                        errors.error("failed to get notes of", Some(part_name), e);
+                        None
+                    }
+                })
+                .filter(|notes| !notes.is_empty());
+            if let (Some(redactor), Some(notes)) = (&options.redact, notes.as_mut()) {
+                redactor.redact_all(notes, &mut res.redactions);
+            }
+            if srt && !content.hidden {
+                cues.push(srt::Cue {
+                    duration_ms: content.advance_ms.unwrap_or(srt::DEFAULT_SLIDE_DURATION_MS),
+                    text: notes
+                        .clone()
+                        .unwrap_or_else(|| single_res.texts.clone())
+                        .join("\n"),
+                });
+            }
+            if options.notes {
+                single_res.notes = notes.unwrap_or_default();
+            }
+            if options.paragraphs {
+                let mut paragraphs = shapes::clean_paragraphs(content.paragraphs, &text_opts);
+                if let Some(redactor) = &options.redact {
+                    redactor.redact_paragraphs(&mut paragraphs, &mut res.redactions);
+                }
+                single_res.paragraphs = Some(paragraphs);
+            }
+            if options.shapes || chunk || layout {
+                let mut shapes = shapes::clean_shapes(content.shapes, &text_opts);
+                if let Some(redactor) = &options.redact {
+                    redactor.redact_shapes(&mut shapes, &mut res.redactions);
+                }
+                if chunk {
+                    chunks.extend(chunks::chunks(
+                        &res.doc_title,
+                        page_no,
+                        &shapes,
+                        options.max_tokens,
+                    ));
+                }
+                if layout {
+                    layout_shapes.push((page_no, shapes.clone()));
+                }
+                single_res.shapes = options.shapes.then_some(shapes);
+            }
+            if options.equations {
+                let mut equations = content.equations;
+                if !options.mathml {
+                    equations
+                        .iter_mut()
+                        .for_each(|equation| equation.mathml = None);
+                }
+                single_res.equations = Some(equations);
+            }
+            single_res.pictures = content.pictures;
+            single_res.background = content.background;
+            #[cfg(feature = "lang-detect")]
+            if options.detect_lang {
+                single_res.languages = lang::detect(&single_res.texts);
+            }
+            single_res.images.iter_mut().for_each(rename);
+            single_res.images.sort();
+            if options.resolve_inherited {
+                let mut effective: Vec<_> = single_res
+                    .images
+                    .iter()
+                    .map(|image| EffectiveImage {
+                        image: image.clone(),
+                        inherited: false,
+                        from: None,
+                    })
+                    .collect();
+                let layout = pkg.slide_layouts.get(part_name);
+                let master = layout.and_then(|layout| pkg.layout_masters.get(layout));
+                let mut show_master_shapes = content.show_master_shapes;
+                for (from, part) in [("layout", layout), ("master", master)] {
+                    let (images, show_master) =
+                        match part.and_then(|part| inherited.get(part.as_str())) {
+                            Some(part) if show_master_shapes => part,
+                            _ => break,
+                        };
+                    for image in images {
+                        if !effective.iter().any(|effective| &effective.image == image) {
+                            effective.push(EffectiveImage {
+                                image: image.clone(),
+                                inherited: true,
+                                from: Some(from),
+                            });
+                        }
+                    }
+                    show_master_shapes = *show_master;
+                }
+                single_res.effective_images = Some(effective);
+            }
+            for picture in single_res.pictures.iter_mut() {
+                picture.image = slide_rels.get(&picture.rel_id).cloned();
+                picture.image.iter_mut().for_each(rename);
+            }
+            single_res.videos = content.videos;
+            if !single_res.videos.is_empty() {
+                let part_rels = package::part_rels(archive, part_name).unwrap_or_else(|e| {
+                    errors.error("failed to get rels of", Some(part_name), e);
+                    Vec::new()
+                });
+                for video in single_res.videos.iter_mut() {
+                    video.poster = video
+                        .poster_rel_id
+                        .as_ref()
+                        .and_then(|rel_id| slide_rels.get(rel_id))
+                        .cloned();
+                    video.poster.iter_mut().for_each(rename);
+                    let rel = match part_rels.iter().find(|rel| rel.id == video.rel_id) {
+                        Some(rel) => rel,
+                        None => continue,
+                    };
+                    if rel.external {
+                        video.media = Some(rel.target.clone());
+                        continue;
+                    }
+                    let mut media = rel.target.clone();
+                    rename(&mut media);
+                    video.media = Some(media);
+                    #[cfg(feature = "media-probe")]
+                    if options.probe_media {
+                        let probed = archive
+                            .by_name(&rel.target)
+                            .map_err(|e| io::Error::other(e.to_string()))
+                            .and_then(|file| {
+                                probe::probe(&mut limits::Limited::new(
+                                    file,
+                                    options.max_media_size,
+                                ))
+                            });
+                        match probed {
+                            Ok(info) => {
+                                video.duration_ms = info.duration_ms;
+                                video.codecs = info.codecs;
+                            }
+                            Err(e) => errors.error("failed to probe", Some(&rel.target), e),
+                        }
+                    }
+                }
+            }
+            if let Some(background) = single_res.background.as_mut() {
+                background.image = background
+                    .rel_id
+                    .as_ref()
+                    .and_then(|rel_id| slide_rels.get(rel_id))
+                    .cloned();
+                background.image.iter_mut().for_each(rename);
+            }
+            if let Some(deck_stats) = deck_stats.as_mut() {
+                let slide_stats =
+                    Stats::new(&single_res.texts, content.bullets, single_res.images.len());
+                *deck_stats += slide_stats;
+                single_res.stats = Some(slide_stats);
+            }
+            single_res.external_images = external::external_images(archive, part_name, errors);
+            #[cfg(feature = "http")]
+            if options.fetch_external && !options.dry_run {
+                external::fetch(
+                    &mut single_res.external_images,
+                    fetched,
+                    output,
+                    res,
+                    options,
+                    errors,
+                );
+            }
+            if options.anonymize {
+                external::anonymize(&mut single_res.external_images);
+            }
+            #[cfg(feature = "plugins")]
+            plugins.slide(&mut single_res);
+            visitor.on_slide(&single_res);
+            res.pages.slides.insert(page_no, single_res);
+            profile.entry("slides", part_name, start);
+        }
+        errors.slide = None;
+        #[cfg(not(feature = "http"))]
+        let _ = output;
+        res.stats = deck_stats;
+        if options.redact.is_some() {
+            info!("redacted {:?}", res.redactions);
+        }
+        Ok(())
+    }
+}
+
+struct Masters;
+
+impl Extractor for Masters {
+    fn name(&self) -> &'static str {
+        "masters"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.masters
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let options = cx.options;
+        let mut masters = BTreeMap::new();
+        for (i, part_name) in cx.pkg.masters.iter().enumerate() {
+            let page_no = i as u32 + 1;
+            let start = Instant::now();
+            let mut page_res = SingleRes::new(page_no, part_name.clone(), true);
+            match cx.archive.by_name(&package::rels_part(part_name)) {
+                Ok(file) => match rels(file, part_name) {
+                    Ok(rels) => {
+                        trace!("got page {:?}, rels: {:?}", page_no, rels);
+                        page_res.images = rels.values().cloned().collect();
+                        page_res.images.iter_mut().for_each(|image| {
+                            *image = export_name(&cx.media_names, image, options.keep_paths)
+                        });
+                        page_res.images.sort();
+                    }
+                    Err(e) => cx.errors.error("failed to get rels of", Some(part_name), e),
+                },
+                Err(e) => {
+                    debug!("no rels for {}, error: {}", part_name, e)
+                }
+            }
+            page_res.external_images =
+                external::external_images(cx.archive, part_name, &mut cx.errors);
+            #[cfg(feature = "http")]
+            if options.fetch_external && !options.dry_run {
+                external::fetch(
+                    &mut page_res.external_images,
+                    &mut cx.fetched,
+                    cx.output,
+                    &mut cx.res,
+                    options,
+                    &mut cx.errors,
+                );
+            }
+            if options.anonymize {
+                external::anonymize(&mut page_res.external_images);
+            }
+            cx.visitor.on_master(&page_res);
+            masters.insert(page_no, page_res);
+            cx.profile.entry("masters", part_name, start);
+        }
+        cx.res.pages.masters = Some(masters);
+        Ok(())
+    }
+}
+
+struct Themes;
+
+impl Extractor for Themes {
+    fn name(&self) -> &'static str {
+        "themes"
+    }
+
+    fn default_enabled(&self, _: &Options) -> bool {
+        true
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        for (i, part_name) in cx.pkg.themes.iter().enumerate() {
+            let theme_no = page_no(part_name).unwrap_or(i as u32 + 1);
+            let start = Instant::now();
+            let file = match cx.archive.by_name(part_name) {
+                Ok(file) => file,
+                Err(e) => {
+                    cx.errors.error("failed to open theme:", Some(part_name), e);
+                    continue;
+                }
+            };
+            match theme::theme(file, theme_no) {
+                Ok(theme_res) => {
+                    trace!("got theme {:?}", theme_res);
+                    cx.res.themes.insert(theme_no, theme_res);
+                    cx.profile.entry("themes", part_name, start);
+                }
+                Err(e) => cx.errors.error("failed to get theme", Some(part_name), e),
+            }
+        }
+        Ok(())
+    }
+}
+
+// the subtitles of the slides read by the slides extractor, so running after
+// it
+struct Subtitles;
+
+impl Extractor for Subtitles {
+    fn name(&self) -> &'static str {
+        "srt"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.srt
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        if cx.cues.is_empty() {
+            return Ok(());
+        }
+        let data = srt::srt(&cx.cues).into_bytes();
+        cx.res.srt = cx.write_file(srt::SRT_FILE.to_owned(), data, "subtitles")?;
+        Ok(())
+    }
+}
+
+struct Graph;
+
+impl Extractor for Graph {
+    fn name(&self) -> &'static str {
+        "graph"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.graph.is_some()
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let format = cx.options.graph.unwrap_or(GraphFormat::Dot);
+        let graph = graph::graph(cx.archive, &mut cx.errors);
+        let data = match format {
+            GraphFormat::Dot => graph::to_dot(&graph),
+            GraphFormat::Json => serde_json::to_string_pretty(&graph).unwrap(),
+        }
+        .into_bytes();
+        cx.res.graph = cx.write_file(format.file_name().to_owned(), data, "graph")?;
+        Ok(())
+    }
+}
+
+// an SVG of the shapes drawn on each slide read by the slides extractor, in
+// the colors of the first theme
+struct ShapesSvg;
+
+impl Extractor for ShapesSvg {
+    fn name(&self) -> &'static str {
+        "shapes-svg"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.shapes_svg
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let size = cx.slide_size()?;
+        let colors = cx
+            .res
+            .themes
+            .values()
+            .next()
+            .map(|theme_res| theme_res.colors.clone())
+            .unwrap_or_default();
+        let slides: Vec<(u32, String)> = cx
+            .res
+            .pages
+            .slides
+            .values()
+            .map(|single_res| (single_res.page_no, single_res.part_name.clone()))
+            .collect();
+        for (page_no, part_name) in slides {
+            let start = Instant::now();
+            let read = cx
+                .archive
+                .by_name(&part_name)
+                .map_err(io::Error::from)
+                .and_then(xml::read);
+            let text = match read {
+                Ok(text) => text,
+                Err(e) => {
+                    cx.errors
+                        .error("failed to read slide:", Some(&part_name), e);
+                    continue;
+                }
+            };
+            let doc = match xml::parse(&text) {
+                Ok(doc) => doc,
+                Err(e) => {
+                    cx.errors
+                        .error("failed to parse slide:", Some(&part_name), e);
+                    continue;
+                }
+            };
+            let data = match svg::svg(doc.root_element(), size, &colors) {
+                Some(data) => data.into_bytes(),
+                None => continue,
+            };
+            let filename = cx.write_file(svg::svg_file(page_no), data, "shapes SVG")?;
+            if let Some(single_res) = cx.res.pages.slides.get_mut(&page_no) {
+                single_res.shapes_svg = filename;
+            }
+            cx.profile.entry("shapes-svg", &part_name, start);
+        }
+        Ok(())
+    }
+}
+
+// the data of the charts of each slide read by the slides extractor, from
+// the workbooks embedded with them
+struct ChartData;
+
+impl Extractor for ChartData {
+    fn name(&self) -> &'static str {
+        "chart-data"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.chart_data.is_some()
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let slides: Vec<(u32, String)> = cx
+            .res
+            .pages
+            .slides
+            .values()
+            .map(|single_res| (single_res.page_no, single_res.part_name.clone()))
+            .collect();
+        for (page_no, part_name) in slides {
+            let start = Instant::now();
+            let read = cx
+                .archive
+                .by_name(&part_name)
+                .map_err(io::Error::from)
+                .and_then(xml::read);
+            let text = match read {
+                Ok(text) => text,
+                Err(e) => {
+                    cx.errors
+                        .error("failed to read slide:", Some(&part_name), e);
+                    continue;
+                }
+            };
+            let chart_ids = match xml::parse(&text) {
+                Ok(doc) => chart_data::chart_ids(doc.root_element()),
+                Err(e) => {
+                    cx.errors
+                        .error("failed to parse slide:", Some(&part_name), e);
+                    continue;
+                }
+            };
+            if chart_ids.is_empty() {
+                continue;
+            }
+            let rels = match package::part_rels(cx.archive, &part_name) {
+                Ok(rels) => rels,
+                Err(e) => {
+                    cx.errors
+                        .error("failed to get rels of", Some(&part_name), e);
+                    continue;
+                }
+            };
+            let mut chart_sheets = Vec::new();
+            for (i, chart_id) in chart_ids.iter().enumerate() {
+                let chart_no = i as u32 + 1;
+                let chart = match rels
+                    .iter()
+                    .find(|rel| rel.id == *chart_id && rel.kind == chart_data::REL_CHART)
+                {
+                    Some(rel) => rel.target.clone(),
+                    None => continue,
+                };
+                // linked workbooks are elsewhere, and charts without one
+                // keep only the values they show
+                let chart_rels = match package::part_rels(cx.archive, &chart) {
+                    Ok(chart_rels) => chart_rels,
+                    Err(e) => {
+                        cx.errors.error("failed to get rels of", Some(&chart), e);
+                        continue;
+                    }
+                };
+                let workbook = chart_rels
+                    .into_iter()
+                    .find(|rel| rel.kind == chart_data::REL_PACKAGE && !rel.external);
+                let workbook = match workbook {
+                    Some(rel) => rel.target,
+                    None => {
+                        debug!("no embedded workbook for {}", chart);
+                        continue;
+                    }
+                };
+                let mut data = Vec::new();
+                let read = cx
+                    .archive
+                    .by_name(&workbook)
+                    .map_err(io::Error::from)
+                    .and_then(|file| {
+                        limits::Limited::new(file, cx.options.max_media_size).read_to_end(&mut data)
+                    });
+                if let Err(e) = read {
+                    cx.errors
+                        .error("failed to read workbook:", Some(&workbook), e);
+                    continue;
+                }
+                let sheets = match chart_data::sheets(&data, cx.options.max_media_size) {
+                    Ok(sheets) => sheets,
+                    Err(e) => {
+                        cx.errors
+                            .error("failed to read workbook:", Some(&workbook), e);
+                        continue;
+                    }
+                };
+                for (sheet_no, sheet) in sheets.into_iter().enumerate() {
+                    let filename = chart_data::csv_file(page_no, chart_no, sheet_no + 1);
+                    let data = chart_data::to_csv(&sheet.rows).into_bytes();
+                    if let Some(file) = cx.write_file(filename, data, "chart data")? {
+                        chart_sheets.push(chart_data::ChartSheet {
+                            chart_no,
+                            chart: chart.clone(),
+                            sheet: sheet.name,
+                            file,
+                        });
+                    }
+                }
+            }
+            if let Some(single_res) = cx.res.pages.slides.get_mut(&page_no) {
+                single_res.chart_data = chart_sheets;
+            }
+            cx.profile.entry("chart-data", &part_name, start);
+        }
+        Ok(())
+    }
+}
+
+// a reveal.js deck of the slides read by the slides extractor
+struct Reveal;
+
+impl Extractor for Reveal {
+    fn name(&self) -> &'static str {
+        "reveal"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.reveal
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let data = reveal::reveal(&cx.res.doc_title, &cx.res.pages.slides).into_bytes();
+        cx.res.reveal = cx.write_file(reveal::REVEAL_FILE.to_owned(), data, "reveal.js deck")?;
+        Ok(())
+    }
+}
+
+// a folder of each slide read by the slides extractor with its images, its
+// text and a page previewing them
+struct Bundle;
+
+impl Extractor for Bundle {
+    fn name(&self) -> &'static str {
+        "bundle"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.bundle
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        if cx.options.dry_run {
+            return Ok(());
+        }
+        cx.finish_media()?;
+        // exported file name -> media part
+        let parts: HashMap<String, String> = cx
+            .pkg
+            .media
+            .iter()
+            .map(|part_name| (cx.export_name(part_name), part_name.clone()))
+            .collect();
+        for single_res in cx.res.pages.slides.values_mut() {
+            let dir = bundle::bundle_dir(single_res.page_no);
+            let mut images = Vec::new();
+            for image in &single_res.images {
+                // external images fetched with --fetch-external aren't in the
+                // package, and are left out
+                let part_name = match parts.get(image) {
+                    Some(part_name) => part_name,
+                    None => continue,
+                };
+                let name = bundle::image_name(image);
+                let mut file = match cx.archive.by_name(part_name) {
+                    Ok(file) => limits::Limited::new(file, cx.options.max_media_size),
+                    Err(e) => {
+                        cx.errors.error("failed to read media:", Some(part_name), e);
+                        continue;
+                    }
+                };
+                let filename = format!("{}/{}", dir, name);
+                match export_media(cx.output, &mut file, filename, cx.options) {
+                    Ok(_) => images.push(name.to_owned()),
+                    Err(e) => cx.errors.error("failed to bundle", Some(image), e),
+                }
+            }
+            let files = [
+                (bundle::TEXT_FILE, bundle::text(single_res, &images)),
+                (
+                    bundle::PREVIEW_FILE,
+                    bundle::preview(&cx.res.doc_title, single_res, &images),
+                ),
+            ];
+            for (name, data) in files {
+                cx.output
+                    .write(&format!("{}/{}", dir, name), data.as_bytes())?;
+            }
+            single_res.bundle = Some(dir);
+        }
+        Ok(())
+    }
+}
+
+// the text blocks of the slides read by the slides extractor, with where
+// they sit on the slide
+struct Layout;
+
+impl Extractor for Layout {
+    fn name(&self) -> &'static str {
+        "layout"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.layout.is_some()
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let format = cx.options.layout.unwrap_or(LayoutFormat::Json);
+        let size = cx.slide_size()?;
+        let pages: Vec<layout::Page> = cx
+            .layout_shapes
+            .iter()
+            .map(|(page_no, shapes)| layout::page(*page_no, shapes, size))
+            .collect();
+        let data = match format {
+            LayoutFormat::Json => layout::to_json(&cx.res.doc_title, size, &pages),
+            LayoutFormat::Hocr => layout::to_hocr(&cx.res.doc_title, size, &pages),
+        }
+        .into_bytes();
+        cx.res.layout = cx.write_file(format.file_name().to_owned(), data, "layout")?;
+        Ok(())
+    }
+}
+
+// the keywords of each slide read by the slides extractor, among the slides
+// of the deck
+struct Keywords;
+
+impl Extractor for Keywords {
+    fn name(&self) -> &'static str {
+        "keywords"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.keywords.is_some()
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let n = cx.options.keywords.unwrap_or_default();
+        let docs: Vec<Vec<String>> = cx
+            .res
+            .pages
+            .slides
+            .values()
+            .map(|single_res| keywords::terms(&single_res.texts))
+            .collect();
+        let slide_keywords = keywords::tf_idf(&docs, n);
+        for (single_res, keywords) in cx.res.pages.slides.values_mut().zip(slide_keywords) {
+            single_res.keywords = keywords;
+        }
+        Ok(())
+    }
+}
+
+// the text chunks of the slides read by the slides extractor
+struct Chunks;
+
+impl Extractor for Chunks {
+    fn name(&self) -> &'static str {
+        "chunks"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.chunks
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        let data = chunks::to_jsonl(&cx.chunks).into_bytes();
+        cx.res.chunks = cx.write_file(chunks::CHUNKS_FILE.to_owned(), data, "chunks")?;
+        Ok(())
+    }
+}
+
+// a montage of the images of the slides read by the slides extractor
+#[cfg(feature = "contact-sheet")]
+struct ContactSheet;
+
+#[cfg(feature = "contact-sheet")]
+impl Extractor for ContactSheet {
+    fn name(&self) -> &'static str {
+        "contact-sheet"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.contact_sheet
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        if cx.options.dry_run {
+            return Ok(());
+        }
+        // exported file name -> media part
+        let parts: HashMap<String, &String> = cx
+            .pkg
+            .media
+            .iter()
+            .map(|part_name| (cx.export_name(part_name), part_name))
+            .collect();
+        let mut thumbnails = HashMap::new();
+        let mut tiles = Vec::new();
+        for (slide_no, single_res) in &cx.res.pages.slides {
+            for part_name in single_res
+                .images
+                .iter()
+                .filter_map(|image| parts.get(image))
+            {
+                if !thumbnails.contains_key(part_name) {
+                    let mut data = Vec::new();
+                    let read = cx.archive.by_name(part_name).map(|file| {
+                        limits::Limited::new(file, cx.options.max_media_size).read_to_end(&mut data)
+                    });
+                    let thumbnail = match read {
+                        Ok(Ok(_)) => contact::thumbnail(&data),
+                        _ => None,
+                    };
+                    thumbnails.insert(*part_name, thumbnail);
+                }
+                tiles.push((*slide_no, *part_name));
+            }
+        }
+        let tiles: Vec<_> = tiles
+            .into_iter()
+            .map(|(slide_no, part_name)| (slide_no, thumbnails[part_name].as_ref()))
+            .collect();
+        if tiles.is_empty() {
+            return Ok(());
+        }
+        match contact::contact_sheet(&tiles) {
+            Ok(data) => {
+                cx.res.contact_sheet = cx.write_file(
+                    contact::CONTACT_SHEET_FILE.to_owned(),
+                    data,
+                    "contact sheet",
+                )?
+            }
+            Err(e) => cx.errors.error("failed to export contact sheet", None, e),
+        }
+        Ok(())
+    }
+}
+
+// groups of the exported images that are the same picture
+#[cfg(feature = "similar")]
+struct Similar;
+
+#[cfg(feature = "similar")]
+impl Extractor for Similar {
+    fn name(&self) -> &'static str {
+        "similar"
+    }
+
+    fn default_enabled(&self, options: &Options) -> bool {
+        options.dedupe_similar
+    }
+
+    fn extract(&self, cx: &mut Context) -> Result<(), ExportMediaError> {
+        if cx.options.dry_run {
+            return Ok(());
+        }
+        // exported file name -> hash and pixels
+        let mut hashes = BTreeMap::new();
+        for part_name in &cx.pkg.media {
+            if !selects(&cx.selection, part_name, true) {
+                continue;
+            }
+            let mut data = Vec::new();
+            let read = cx.archive.by_name(part_name).map(|file| {
+                limits::Limited::new(file, cx.options.max_media_size).read_to_end(&mut data)
+            });
+            // a read cut short by the size limit would hash part of the image
+            if let Err(e) = read.map_err(io::Error::from).and_then(|read| read) {
+                cx.errors.error("failed to read media:", Some(part_name), e);
+                continue;
+            }
+            if let Some(hash) = phash::phash(&data) {
+                hashes.insert(cx.export_name(part_name), hash);
+            }
+        }
+        cx.res.similar_images = phash::similar(&hashes);
+        debug!(
+            "{} images in {} groups of similar ones",
+            cx.res.similar_images.iter().map(Vec::len).sum::<usize>(),
+            cx.res.similar_images.len()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use log::trace;
+use std::{
+    collections::BTreeMap,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    errors::Errors,
+    output::Output,
+    package::Package,
+    xml::{self, NodeExt, NS_PRESENTATIONML as P, NS_RELATIONSHIPS},
+    ExportMediaError,
+};
+
+pub const DIR_FONTS: &str = "fonts";
+const EXT_OBFUSCATED_FONT: &str = "odttf";
+// only the first 32 bytes of an obfuscated font are scrambled
+const OBFUSCATED_LEN: usize = 32;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FontRes {
+    pub typeface: String,
+    // style (regular, bold, italic, boldItalic) -> exported file name
+    pub files: BTreeMap<String, String>,
+}
+
+// export the fonts listed in p:embeddedFontLst of the presentation into the
+// fonts directory of output,
+// without an output only the file names they would get are worked out
+pub fn export_fonts<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    pkg: &Package,
+    mut output: Option<&mut Output>,
+    errors: &mut Errors,
+) -> Result<Vec<FontRes>, ExportMediaError> {
+    let presentation = archive.by_name(&pkg.main_part).map_err(|e| e.to_string())?;
+    let text = xml::read(presentation)?;
+    let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, pkg.main_part.clone()))?;
+    let font_lst = match doc.root_element().child(P, "embeddedFontLst") {
+        Some(font_lst) => font_lst,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut res = Vec::new();
+    for embedded in font_lst.elements() {
+        let typeface = match embedded
+            .child(P, "font")
+            .and_then(|font| font.attribute("typeface"))
+        {
+            Some(typeface) => typeface.to_owned(),
+            None => continue,
+        };
+        let mut font_res = FontRes {
+            typeface,
+            files: BTreeMap::new(),
+        };
+        for style in embedded.elements() {
+            if style.has_tag_name((P, "font")) {
+                continue;
+            }
+            let part_name = match style
+                .attribute((NS_RELATIONSHIPS, "id"))
+                .and_then(|id| pkg.main_rel(id))
+            {
+                Some(rel) => &rel.target,
+                None => continue,
+            };
+            match export_font(archive, part_name, output.as_deref_mut()) {
+                Ok(outfilename) => {
+                    trace!("exported font {} as {}", part_name, outfilename);
+                    font_res
+                        .files
+                        .insert(style.tag_name().name().to_owned(), outfilename);
+                }
+                Err(e) => errors.error("failed to export font:", Some(part_name), e),
+            }
+        }
+        res.push(font_res);
+    }
+    Ok(res)
+}
+
+fn export_font<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+    output: Option<&mut Output>,
+) -> Result<String, ExportMediaError> {
+    let mut data = Vec::new();
+    archive
+        .by_name(part_name)
+        .map_err(|e| e.to_string())?
+        .read_to_end(&mut data)?;
+    let path = Path::new(part_name);
+    let mut outfilename = path.file_name().unwrap().to_string_lossy().into_owned();
+    if path.extension().and_then(|ext| ext.to_str()) == Some(EXT_OBFUSCATED_FONT) {
+        let stem = path.file_stem().unwrap().to_string_lossy();
+        let key = obfuscation_key(&stem)
+            .ok_or_else(|| format!("{} is not named after its obfuscation key", part_name))?;
+        deobfuscate(&mut data, &key);
+        outfilename = format!("{}.ttf", stem.trim_matches(|c| c == '{' || c == '}'));
+    }
+    if let Some(output) = output {
+        output.write(&format!("{}/{}", DIR_FONTS, outfilename), &data)?;
+    }
+    Ok(outfilename)
+}
+
+// obfuscated fonts are named after a GUID whose bytes, read from the end, form the key
+fn obfuscation_key(guid: &str) -> Option<[u8; 16]> {
+    let digits: String = guid.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    if digits.len() != 32 {
+        return None;
+    }
+    let mut key = [0u8; 16];
+    for (i, byte) in key.iter_mut().enumerate() {
+        let pos = (15 - i) * 2;
+        *byte = u8::from_str_radix(&digits[pos..pos + 2], 16).ok()?;
+    }
+    Some(key)
+}
+
+fn deobfuscate(data: &mut [u8], key: &[u8; 16]) {
+    for (i, byte) in data.iter_mut().take(OBFUSCATED_LEN).enumerate() {
+        *byte ^= key[i % key.len()];
+    }
+}
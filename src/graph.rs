@@ -0,0 +1,128 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    io::{Read, Seek},
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::Errors,
+    package::{self, CONTENT_TYPES_FILE},
+};
+
+// the package itself, the source of the package relationships
+pub const PACKAGE_NODE: &str = "/";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    // Graphviz
+    Dot,
+    // part -> its relationships
+    Json,
+}
+
+impl GraphFormat {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "graph.dot",
+            GraphFormat::Json => "graph.json",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Edge {
+    pub target: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub external: bool,
+}
+
+// every part of the package -> its relationships, parts without any and
+// parts nothing points to included
+pub fn graph<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    errors: &mut Errors,
+) -> BTreeMap<String, Vec<Edge>> {
+    let mut res = BTreeMap::new();
+    res.insert(PACKAGE_NODE.to_owned(), Vec::new());
+    let names: Vec<String> = archive.file_names().map(String::from).collect();
+    for name in names {
+        if name.ends_with('/') || name == CONTENT_TYPES_FILE {
+            continue;
+        }
+        let part_name = match source_part(&name) {
+            Some(part_name) => part_name,
+            None => {
+                res.entry(name).or_default();
+                continue;
+            }
+        };
+        let rels = match package::part_rels(archive, &part_name) {
+            Ok(rels) => rels,
+            Err(e) => {
+                errors.error("failed to get rels of", Some(&part_name), e);
+                continue;
+            }
+        };
+        let node = match part_name.is_empty() {
+            true => PACKAGE_NODE.to_owned(),
+            false => part_name,
+        };
+        let edges = res.entry(node).or_default();
+        edges.extend(rels.into_iter().map(|rel| Edge {
+            target: rel.target,
+            kind: rel.kind,
+            external: rel.external,
+        }));
+    }
+    res
+}
+
+// the part a rels file belongs to, ppt/slides/_rels/slide1.xml.rels ->
+// ppt/slides/slide1.xml, the package being the empty part name
+fn source_part(rels_name: &str) -> Option<String> {
+    let rels_name = rels_name.strip_suffix(".rels")?;
+    let (dir, file_name) = match rels_name.rsplit_once('/') {
+        Some((dir, file_name)) => (dir, file_name),
+        None => return None,
+    };
+    let parent = match dir.rsplit_once('/') {
+        Some((parent, "_rels")) => format!("{}/", parent),
+        None if dir == "_rels" => String::new(),
+        _ => return None,
+    };
+    Some(format!("{}{}", parent, file_name))
+}
+
+pub fn to_dot(graph: &BTreeMap<String, Vec<Edge>>) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut res = String::from("digraph package {\n  rankdir=LR;\n  node [shape=box];\n");
+    for node in graph.keys() {
+        let _ = writeln!(res, "  {};", quote(node));
+    }
+    for edges in graph.values() {
+        // external targets aren't parts, drawn dashed
+        for edge in edges.iter().filter(|edge| edge.external) {
+            let _ = writeln!(res, "  {} [style=dashed];", quote(&edge.target));
+        }
+    }
+    for (node, edges) in graph {
+        for edge in edges {
+            let style = if edge.external { ", style=dashed" } else { "" };
+            let _ = writeln!(
+                res,
+                "  {} -> {} [label={}{}];",
+                quote(node),
+                quote(&edge.target),
+                quote(&edge.kind),
+                style
+            );
+        }
+    }
+    res.push_str("}\n");
+    res
+}
@@ -0,0 +1,100 @@
+use log::{debug, error};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+};
+
+use crate::{ExportMediaError, ExtractArgs, ExtractVisitor, SingleRes};
+
+// the commands run for each exported media file and each slide, at most jobs
+// of them at once
+pub struct Hooks {
+    per_media: Option<String>,
+    per_slide: Option<String>,
+    jobs: usize,
+    // where the media are written, for the paths given to per_media
+    output_dir: PathBuf,
+    running: VecDeque<(String, Child)>,
+    failed: usize,
+}
+
+impl Hooks {
+    pub fn new(args: &ExtractArgs, output_dir: &Path) -> Self {
+        // a dry run writes nothing to run them on
+        let command = |command: &Option<String>| command.clone().filter(|_| !args.dry_run);
+        Hooks {
+            per_media: command(&args.exec_per_media),
+            per_slide: command(&args.exec_per_slide),
+            jobs: args.exec_jobs as usize,
+            output_dir: output_dir.to_path_buf(),
+            running: VecDeque::new(),
+            failed: 0,
+        }
+    }
+
+    // wait for every command to finish. Fails when any of them did.
+    pub fn finish(mut self) -> Result<(), ExportMediaError> {
+        while let Some((command, child)) = self.running.pop_front() {
+            self.wait(command, child);
+        }
+        if self.failed > 0 {
+            return Err(format!("{} hook commands failed", self.failed).into());
+        }
+        Ok(())
+    }
+
+    // start command in a shell, waiting for the oldest one first when jobs of
+    // them are running
+    fn run(&mut self, command: String) {
+        if self.running.len() >= self.jobs {
+            let (command, child) = self.running.pop_front().unwrap();
+            self.wait(command, child);
+        }
+        debug!("running {}", command);
+        match Command::new("sh").arg("-c").arg(&command).spawn() {
+            Ok(child) => self.running.push_back((command, child)),
+            Err(e) => {
+                error!("failed to run {}, error: {}", command, e);
+                self.failed += 1;
+            }
+        }
+    }
+
+    fn wait(&mut self, command: String, mut child: Child) {
+        match child.wait() {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                error!("hook {} exited with {}", command, status);
+                self.failed += 1;
+            }
+            Err(e) => {
+                error!("failed to wait for {}, error: {}", command, e);
+                self.failed += 1;
+            }
+        }
+    }
+}
+
+impl ExtractVisitor for Hooks {
+    fn on_slide(&mut self, slide: &SingleRes) {
+        if let Some(per_slide) = &self.per_slide {
+            let json = serde_json::to_string(slide).unwrap();
+            let command = per_slide.replace("{json}", &quote(&json));
+            self.run(command);
+        }
+    }
+
+    fn on_media_written(&mut self, _part_name: &str, filename: &str) {
+        if let Some(per_media) = &self.per_media {
+            let path = self.output_dir.join(filename);
+            let command = per_media.replace("{path}", &quote(&path.to_string_lossy()));
+            self.run(command);
+        }
+    }
+}
+
+// a single-quoted shell word of s
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
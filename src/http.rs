@@ -0,0 +1,65 @@
+use log::debug;
+use std::io::Read;
+
+use crate::{limits::Limited, ExportMediaError};
+
+pub const DEFAULT_MAX_DOWNLOAD_SIZE: u64 = 1024 * 1024 * 1024;
+
+// the url without its query and fragment, which may carry credentials, for
+// logs and the index
+pub fn display(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+// download url into memory, following redirects, with headers given as
+// "Name: value". Authorization is only kept on redirects to the same host.
+pub fn download(url: &str, headers: &[String], max_size: u64) -> Result<Vec<u8>, ExportMediaError> {
+    let agent = ureq::AgentBuilder::new()
+        .redirect_auth_headers(ureq::RedirectAuthHeaders::SameHost)
+        .build();
+    let mut request = agent.get(url);
+    for header in headers {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("invalid header {}, expected Name: value", header))?;
+        request = request.set(name.trim(), value.trim());
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, _)) => {
+            return Err(format!("failed to download {}: status {}", display(url), status).into())
+        }
+        Err(ureq::Error::Transport(transport)) => {
+            return Err(format!("failed to download {}: {}", display(url), transport.kind()).into())
+        }
+    };
+    let length = response
+        .header("content-length")
+        .and_then(|length| length.parse::<u64>().ok());
+    if length.is_some_and(|length| length > max_size) {
+        return Err(format!(
+            "{} is larger than the limit of {} bytes",
+            display(url),
+            max_size
+        )
+        .into());
+    }
+    let mut data = Vec::with_capacity(length.unwrap_or_default() as usize);
+    Limited::new(response.into_reader(), max_size).read_to_end(&mut data)?;
+    debug!("downloaded {}, {} bytes", display(url), data.len());
+    Ok(data)
+}
+
+// percent-encode all but the unreserved characters and /
+pub fn uri_encode(s: &str) -> String {
+    let mut res = String::new();
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                res.push(b as char)
+            }
+            b => res.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    res
+}
@@ -0,0 +1,59 @@
+use std::fmt::Write;
+
+use ppt_img_extract::{output::Output, ExportMediaError, Res, SingleRes};
+
+const HEADER: &str = "deck,slide_no,is_master,image_file,width,height,hash\n";
+
+// write a CSV row for each image of each slide, then of each master, to path
+// in the output. The width and height are left empty for images whose header
+// doesn't tell, EMF and WMF drawings among them, and the hash is the SHA-256
+// of the exported file.
+pub fn write(output: &mut Output, res: &Res, path: &str) -> Result<(), ExportMediaError> {
+    let mut csv = HEADER.to_owned();
+    let masters = res
+        .pages
+        .masters
+        .iter()
+        .flat_map(|masters| masters.values());
+    let pages = res
+        .pages
+        .slides
+        .values()
+        .map(|single_res| (single_res, false))
+        .chain(masters.map(|single_res| (single_res, true)));
+    for (single_res, is_master) in pages {
+        rows(&mut csv, res, single_res, is_master);
+    }
+    output.write(path, csv.as_bytes())?;
+    Ok(())
+}
+
+fn rows(csv: &mut String, res: &Res, single_res: &SingleRes, is_master: bool) {
+    for image in &single_res.images {
+        let dimensions = res.dimensions.get(image);
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            quote(&res.doc_title),
+            single_res.page_no,
+            is_master,
+            quote(image),
+            dimensions.map(|d| d.width.to_string()).unwrap_or_default(),
+            dimensions.map(|d| d.height.to_string()).unwrap_or_default(),
+            res.media
+                .get(image)
+                .map(|checksum| checksum.sha256.as_str())
+                .unwrap_or_default(),
+        )
+        .unwrap();
+    }
+}
+
+// a field quoted when it has a comma, quote or line break, quotes doubled
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
@@ -0,0 +1,97 @@
+use log::{debug, info};
+use std::{env, fs, path::Path, time::UNIX_EPOCH};
+
+use ppt_img_extract::output::write_atomically;
+use serde::{Deserialize, Serialize};
+
+use crate::{checksum::Checksum, unpacked, ExportMediaError};
+
+// kept next to index.json, records what the extraction was made from
+pub const MANIFEST_FILE: &str = ".ppt-img-extract-manifest.json";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    source: String,
+    size: u64,
+    // modification time of the source in seconds since the epoch
+    mtime: u64,
+    sha256: String,
+    tool_version: String,
+    // the command line, a different one may extract something else
+    args: Vec<String>,
+}
+
+impl Manifest {
+    // describe the source as it is now, without its hash, a package stored
+    // unzipped by the total size of its files and the latest mtime of them
+    pub fn new(input_file: &str) -> Result<Self, ExportMediaError> {
+        let metadata = fs::metadata(input_file)?;
+        let (size, modified) = match metadata.is_dir() {
+            true => {
+                let mut size = 0;
+                let mut modified = UNIX_EPOCH;
+                for (_, path) in unpacked::files(Path::new(input_file))? {
+                    let metadata = fs::metadata(path)?;
+                    size += metadata.len();
+                    modified = modified.max(metadata.modified()?);
+                }
+                (size, modified)
+            }
+            false => (metadata.len(), metadata.modified()?),
+        };
+        Ok(Manifest {
+            source: input_file.to_owned(),
+            size,
+            mtime: modified
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default(),
+            sha256: String::new(),
+            tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+            args: env::args().skip(1).collect(),
+        })
+    }
+
+    pub fn load(output_dir: &Path) -> Option<Self> {
+        let content = fs::read_to_string(output_dir.join(MANIFEST_FILE)).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                debug!("ignoring unreadable manifest, error: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, output_dir: &Path) -> Result<(), ExportMediaError> {
+        let j = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_atomically(&output_dir.join(MANIFEST_FILE), j.as_bytes())?;
+        Ok(())
+    }
+
+    // the same source, tool and command line, judged by size and mtime
+    pub fn unchanged(&self, previous: &Manifest) -> bool {
+        self.source == previous.source
+            && self.size == previous.size
+            && self.mtime == previous.mtime
+            && self.tool_version == previous.tool_version
+            && self.args == previous.args
+    }
+
+    pub fn set_checksum(&mut self, checksum: &Checksum) {
+        self.sha256 = checksum.sha256.clone();
+    }
+
+    // the same content as previously, the mtime may have changed when the
+    // source was merely touched or copied
+    pub fn same_content(&self, previous: &Manifest) -> bool {
+        let same = self.source == previous.source
+            && self.sha256 == previous.sha256
+            && self.tool_version == previous.tool_version
+            && self.args == previous.args;
+        if same {
+            info!("{} has the same content as extracted before", self.source);
+        }
+        same
+    }
+}
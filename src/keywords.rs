@@ -0,0 +1,76 @@
+use std::collections::{HashMap, HashSet};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+// words too common in English to tell slides apart, which a deck of a few
+// slides has too few slides to weigh down
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been",
+    "but", "by", "can", "could", "do", "does", "for", "from", "had", "has", "have", "he", "her",
+    "his", "how", "i", "if", "in", "into", "is", "it", "its", "may", "more", "most", "no", "not",
+    "of", "on", "or", "other", "our", "out", "over", "she", "so", "some", "such", "than", "that",
+    "the", "their", "them", "then", "there", "these", "they", "this", "to", "up", "us", "was",
+    "we", "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Keyword {
+    pub term: String,
+    // TF-IDF, the share of the slide's words that are the term weighed by
+    // how few of the slides have it
+    pub score: f64,
+}
+
+// the words of texts that can be keywords, lowercased, numbers and
+// stopwords left out
+pub fn terms<S: AsRef<str>>(texts: &[S]) -> Vec<String> {
+    texts
+        .iter()
+        .flat_map(|text| text.as_ref().split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| word.chars().count() > 1 && !word.chars().all(|c| c.is_numeric()))
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+// the top n keywords of each document of terms by TF-IDF among the
+// documents, highest first and ties in alphabetical order. A term every
+// document has still scores, idf being smoothed.
+pub fn tf_idf(docs: &[Vec<String>], n: usize) -> Vec<Vec<Keyword>> {
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    for doc in docs {
+        let unique: HashSet<&str> = doc.iter().map(String::as_str).collect();
+        for term in unique {
+            *df.entry(term).or_default() += 1;
+        }
+    }
+    let total = docs.len() as f64;
+    docs.iter()
+        .map(|doc| {
+            let mut tf: HashMap<&str, usize> = HashMap::new();
+            for term in doc {
+                *tf.entry(term).or_default() += 1;
+            }
+            let mut keywords: Vec<Keyword> = tf
+                .into_iter()
+                .map(|(term, count)| {
+                    let idf = ((1.0 + total) / (1.0 + df[term] as f64)).ln() + 1.0;
+                    let score = count as f64 / doc.len() as f64 * idf;
+                    Keyword {
+                        term: term.to_owned(),
+                        // rounded, the digits beyond telling nothing
+                        score: (score * 10000.0).round() / 10000.0,
+                    }
+                })
+                .collect();
+            keywords.sort_by(|a, b| {
+                b.score
+                    .total_cmp(&a.score)
+                    .then_with(|| a.term.cmp(&b.term))
+            });
+            keywords.truncate(n);
+            keywords
+        })
+        .collect()
+}
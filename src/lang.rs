@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+// share of the detected characters a language needs to count as dominant
+const MIN_SHARE: f64 = 0.2;
+
+// detect languages text by text and keep those covering a significant share
+// of the slide, weighted by text length, most common first
+pub fn detect(texts: &[String]) -> Vec<String> {
+    let mut weights: HashMap<&'static str, usize> = HashMap::new();
+    let mut total = 0;
+    for text in texts {
+        let info = match whatlang::detect(text) {
+            Some(info) if info.is_reliable() => info,
+            _ => continue,
+        };
+        let len = text.chars().count();
+        *weights.entry(info.lang().code()).or_insert(0) += len;
+        total += len;
+    }
+    let mut langs: Vec<(&str, usize)> = weights
+        .into_iter()
+        .filter(|(_, weight)| *weight as f64 >= total as f64 * MIN_SHARE)
+        .collect();
+    langs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    langs.into_iter().map(|(lang, _)| lang.to_owned()).collect()
+}
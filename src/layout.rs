@@ -0,0 +1,223 @@
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{reveal::escape, shapes::Shape};
+
+// EMU per pixel at 96 dpi, hOCR giving boxes in pixels
+const EMU_PER_PX: f64 = 9525.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutFormat {
+    // pages of blocks of lines, boxes relative to the slide size
+    Json,
+    // an hOCR page a slide, boxes in pixels
+    Hocr,
+}
+
+impl LayoutFormat {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            LayoutFormat::Json => "layout.json",
+            LayoutFormat::Hocr => "layout.hocr",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Layout<'a> {
+    doc_title: &'a str,
+    // the slide size in EMU
+    width: i64,
+    height: i64,
+    pages: &'a [Page],
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page {
+    pub page_no: u32,
+    pub blocks: Vec<Block>,
+}
+
+// the text of a shape and where it sits
+#[derive(Debug, Serialize)]
+pub struct Block {
+    pub shape_id: u32,
+    pub shape: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    // left, top, right and bottom as fractions of the slide width and height
+    pub bbox: [f64; 4],
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Line {
+    // bullet indent level, 0 being the outermost
+    pub level: u32,
+    pub text: String,
+}
+
+// the blocks of the shapes of a slide in reading order, top to bottom and
+// then left to right. Shapes without a position, which inherit theirs from
+// the layout, are left out.
+pub fn page(page_no: u32, shapes: &[Shape], size: (i64, i64)) -> Page {
+    let (width, height) = (size.0 as f64, size.1 as f64);
+    // rounded to a hundredth of a percent, finer than any layout
+    let fraction = |v: i64, of: f64| (v as f64 / of * 10000.0).round() / 10000.0;
+    let mut blocks: Vec<Block> = shapes
+        .iter()
+        .filter_map(|shape| {
+            let position = shape.position?;
+            Some(Block {
+                shape_id: shape.id,
+                shape: shape.name.clone(),
+                placeholder: shape.placeholder.clone(),
+                bbox: [
+                    fraction(position.x, width),
+                    fraction(position.y, height),
+                    fraction(position.x + position.width, width),
+                    fraction(position.y + position.height, height),
+                ],
+                // a line a paragraph, and another for each a:br in it
+                lines: shape
+                    .paragraphs
+                    .iter()
+                    .flat_map(|paragraph| {
+                        paragraph.text.split('\n').map(|text| Line {
+                            level: paragraph.level,
+                            text: text.to_owned(),
+                        })
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+    blocks.sort_by(|a, b| {
+        a.bbox[1]
+            .total_cmp(&b.bbox[1])
+            .then(a.bbox[0].total_cmp(&b.bbox[0]))
+    });
+    Page { page_no, blocks }
+}
+
+pub fn to_json(doc_title: &str, size: (i64, i64), pages: &[Page]) -> String {
+    let layout = Layout {
+        doc_title,
+        width: size.0,
+        height: size.1,
+        pages,
+    };
+    serde_json::to_string_pretty(&layout).unwrap()
+}
+
+// an hOCR document of a page a slide, in XHTML as Tesseract writes it, a block
+// being a carea holding a par of its lines. The lines of a block share its
+// height evenly, the shape not telling where each is.
+pub fn to_hocr(doc_title: &str, size: (i64, i64), pages: &[Page]) -> String {
+    let (width, height) = (size.0 as f64 / EMU_PER_PX, size.1 as f64 / EMU_PER_PX);
+    let px = |bbox: &[f64; 4]| {
+        [
+            bbox[0] * width,
+            bbox[1] * height,
+            bbox[2] * width,
+            bbox[3] * height,
+        ]
+        .map(|v| v.round() as i64)
+    };
+    let mut res = String::new();
+    res.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    res.push_str("<html xmlns=\"http://www.w3.org/1999/xhtml\">\n<head>\n");
+    writeln!(res, "<title>{}</title>", escape(doc_title)).unwrap();
+    res.push_str("<meta http-equiv=\"Content-Type\" content=\"text/html;charset=utf-8\"/>\n");
+    res.push_str("<meta name=\"ocr-system\" content=\"ppt-img-extract\"/>\n");
+    res.push_str(
+        "<meta name=\"ocr-capabilities\" content=\"ocr_page ocr_carea ocr_par ocr_line\"/>\n",
+    );
+    res.push_str("</head>\n<body>\n");
+    for page in pages {
+        writeln!(
+            res,
+            "<div class=\"ocr_page\" id=\"page_{}\" title=\"bbox 0 0 {} {}; ppageno {}\">",
+            page.page_no,
+            width.round() as i64,
+            height.round() as i64,
+            page.page_no - 1
+        )
+        .unwrap();
+        for block in &page.blocks {
+            let [x0, y0, x1, y1] = px(&block.bbox);
+            let id = format!("{}_{}", page.page_no, block.shape_id);
+            writeln!(
+                res,
+                "<div class=\"ocr_carea\" id=\"block_{}\" title=\"bbox {} {} {} {}\">",
+                id, x0, y0, x1, y1
+            )
+            .unwrap();
+            writeln!(
+                res,
+                "<p class=\"ocr_par\" id=\"par_{}\" title=\"bbox {} {} {} {}\">",
+                id, x0, y0, x1, y1
+            )
+            .unwrap();
+            let line_height = (y1 - y0) as f64 / block.lines.len().max(1) as f64;
+            for (i, line) in block.lines.iter().enumerate() {
+                let top = y0 + (line_height * i as f64).round() as i64;
+                let bottom = y0 + (line_height * (i + 1) as f64).round() as i64;
+                writeln!(
+                    res,
+                    "<span class=\"ocr_line\" id=\"line_{}_{}\" title=\"bbox {} {} {} {}\">{}</span>",
+                    id,
+                    i + 1,
+                    x0,
+                    top,
+                    x1,
+                    bottom,
+                    escape(&line.text)
+                )
+                .unwrap();
+            }
+            res.push_str("</p>\n</div>\n");
+        }
+        res.push_str("</div>\n");
+    }
+    res.push_str("</body>\n</html>\n");
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::page;
+    use crate::shapes::{Position, Shape};
+
+    fn shape(id: u32, x: i64, y: i64) -> Shape {
+        Shape {
+            id,
+            name: format!("Shape {}", id),
+            placeholder: None,
+            position: Some(Position {
+                x,
+                y,
+                width: 100,
+                height: 100,
+            }),
+            paragraphs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn blocks_in_reading_order() {
+        let shapes = [shape(1, 500, 500), shape(2, 500, 0), shape(3, 0, 0)];
+        let page = page(1, &shapes, (1000, 1000));
+        let ids: Vec<u32> = page.blocks.iter().map(|block| block.shape_id).collect();
+        assert_eq!(ids, [3, 2, 1]);
+    }
+
+    #[test]
+    fn slide_without_a_size_does_not_panic() {
+        let shapes = [shape(1, 0, 0), shape(2, 500, 0)];
+        assert_eq!(page(1, &shapes, (0, 0)).blocks.len(), 2);
+    }
+}
@@ -0,0 +1,1239 @@
+use log::{debug, error, trace, warn};
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, fs,
+    io::{self, Read, Seek},
+    path::{Component, Path},
+    time::Instant,
+};
+
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use zip::{self, read::ZipFile};
+
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+pub mod bundle;
+pub mod chart_data;
+pub mod checksum;
+pub mod chunks;
+#[cfg(feature = "contact-sheet")]
+pub mod contact;
+pub mod dimensions;
+pub mod errors;
+pub mod exif;
+pub mod external;
+pub mod extractor;
+pub mod fonts;
+pub mod graph;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod keywords;
+#[cfg(feature = "lang-detect")]
+pub mod lang;
+pub mod layout;
+pub mod limits;
+pub mod logging;
+pub mod math;
+pub mod output;
+pub mod package;
+#[cfg(feature = "similar")]
+pub mod phash;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+#[cfg(feature = "media-probe")]
+pub mod probe;
+pub mod profile;
+pub mod redact;
+#[cfg(any(feature = "s3", feature = "gcs", feature = "azure"))]
+pub mod remote;
+#[cfg(feature = "resize")]
+pub mod resize;
+pub mod reveal;
+#[cfg(feature = "s3")]
+pub mod s3;
+pub mod salvage;
+pub mod sanitize;
+pub mod shapes;
+pub mod signatures;
+pub mod sniff;
+pub mod srt;
+pub mod stats;
+pub mod strip;
+pub mod summary;
+pub mod svg;
+pub mod template;
+pub mod text;
+pub mod theme;
+pub mod unpacked;
+pub mod vba;
+pub mod writer;
+pub mod xml;
+
+use chart_data::{ChartDataFormat, ChartSheet};
+use checksum::Checksum;
+use dimensions::Dimensions;
+use errors::{Errors, PartError};
+use exif::Exif;
+use external::ExternalImage;
+use extractor::Context;
+use fonts::FontRes;
+use graph::GraphFormat;
+use keywords::Keyword;
+use layout::LayoutFormat;
+use logging::Phases;
+use math::Equation;
+use output::Output;
+use package::{Package, PackageType};
+use profile::Profile;
+use salvage::Partial;
+use shapes::{Background, Paragraph, Picture, Shape, Video};
+use signatures::SignatureRes;
+use stats::Stats;
+use summary::Summary;
+use template::NameTemplate;
+use text::TextOptions;
+use theme::ThemeRes;
+use vba::VbaRes;
+use writer::MediaWriter;
+use xml::NodeExt;
+
+static RE_TEXT: Lazy<Regex> = Lazy::new(|| Regex::new(r"<a:t>([\s\S]+?)</a:t>").unwrap());
+static RE_PARAGRAPH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<a:p>([\s\S]*?)</a:p>|<a:p\s[^>]*[^/]>([\s\S]*?)</a:p>").unwrap());
+static RE_PAGE_NO: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(slide|slideMaster|theme)(\d+).xml").unwrap());
+
+pub const INDEX_FILE: &str = "index.json";
+// written last into the output directory once everything else is in place
+pub const DONE_FILE: &str = ".done";
+// version of the index.json format, as major.minor. The minor version goes up
+// when fields or values are added, which consumers should ignore when they
+// don't know them. The major version goes up when a field is removed, renamed
+// or changes its type or meaning, consumers should refuse a major version they
+// don't know.
+pub const SCHEMA_VERSION: &str = "1.30";
+const ATTR_REL_TYPE_IMAGE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image";
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct SingleRes {
+    pub page_no: u32,
+    pub part_name: String,
+    pub slide_master: bool,
+    // the layout of a slide by its place among the layouts, and its master,
+    // the key in pages.masters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout_no: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub master_no: Option<u32>,
+    pub images: Vec<String>,
+    // the images plus those shown from the layout and master, with
+    // resolve_inherited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_images: Option<Vec<EffectiveImage>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub external_images: Vec<ExternalImage>,
+    pub texts: Vec<String>,
+    // the text of the slide's notes, with notes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    // dominant languages of the texts as ISO 639-3 codes, most common first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Stats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paragraphs: Option<Vec<Paragraph>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shapes: Option<Vec<Shape>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equations: Option<Vec<Equation>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pictures: Vec<Picture>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub videos: Vec<Video>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<Background>,
+    // the terms telling the slide apart from the others of the deck, with
+    // keywords
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<Keyword>,
+    // the sheets of the workbooks of the slide's charts, with chart_data
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chart_data: Vec<ChartSheet>,
+    // exported file name of the SVG of the shapes drawn on the slide, with
+    // shapes_svg
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shapes_svg: Option<String>,
+    // the folder of the slide's images, text and preview, with bundle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<String>,
+    // fields added by plugins
+    #[serde(flatten)]
+    pub fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl SingleRes {
+    fn new(page_no: u32, part_name: String, slide_master: bool) -> Self {
+        SingleRes {
+            page_no,
+            part_name,
+            slide_master,
+            layout_no: None,
+            master_no: None,
+            images: Vec::new(),
+            effective_images: None,
+            external_images: Vec::new(),
+            texts: Vec::new(),
+            notes: Vec::new(),
+            languages: Vec::new(),
+            stats: None,
+            paragraphs: None,
+            shapes: None,
+            equations: None,
+            pictures: Vec::new(),
+            videos: Vec::new(),
+            background: None,
+            keywords: Vec::new(),
+            chart_data: Vec::new(),
+            shapes_svg: None,
+            bundle: None,
+            fields: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EffectiveImage {
+    pub image: String,
+    pub inherited: bool,
+    // layout or master, for inherited images
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<&'static str>,
+}
+
+// ordered maps throughout the index, so that the same input always gives the
+// same output
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PageRes {
+    pub slides: BTreeMap<u32, SingleRes>,
+    // left out with masters off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub masters: Option<BTreeMap<u32, SingleRes>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Res {
+    pub schema_version: &'static str,
+    // version of the ppt-img-extract that wrote the index
+    pub tool_version: &'static str,
+    pub doc_title: String,
+    // detected from the package contents rather than the file extension
+    pub package_type: Option<PackageType>,
+    // checksum of the input file itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Checksum>,
+    // present when the archive was damaged and only partly recovered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial: Option<Partial>,
+    // exported file name -> checksum of its contents
+    pub media: BTreeMap<String, Checksum>,
+    // media part -> the name it was exported as, of the names sanitizing
+    // changed
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub sanitized_names: BTreeMap<String, String>,
+    // media part -> the name it was exported as, of the media whose extension
+    // didn't match their content, with fix_extensions
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub corrected_names: BTreeMap<String, String>,
+    // exported file name -> EXIF, of the images that have any
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub exif: BTreeMap<String, Exif>,
+    // exported file name -> width and height in pixels, of the images whose
+    // header tells
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub dimensions: BTreeMap<String, Dimensions>,
+    // pattern name -> the matches replaced in the text and notes, with redact
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub redactions: BTreeMap<String, u64>,
+    // exported file name -> the fields plugins added, of the media they added
+    // any to
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub media_fields: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+    // exported file name of the preview the package carries of its first slide
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    // exported file name of the montage of the slide images
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_sheet: Option<String>,
+    // groups of near-duplicate images by exported file name, the same picture
+    // at different sizes or crops, each starting with the one of the most
+    // pixels, with dedupe_similar
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub similar_images: Vec<Vec<String>>,
+    // exported file name of the subtitles made of the slide notes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub srt: Option<String>,
+    // exported file name of the graph of the parts and their relationships
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph: Option<String>,
+    // exported file name of the reveal.js deck of the slides
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reveal: Option<String>,
+    // exported file name of the text blocks of the slides with their boxes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+    // exported file name of the text chunks of the slides, a JSON object a
+    // line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<String>,
+    pub pages: PageRes,
+    pub themes: BTreeMap<u32, ThemeRes>,
+    pub fonts: Vec<FontRes>,
+    // present when the presentation carries macros
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vba: Option<VbaRes>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<SignatureRes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Stats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Summary>,
+}
+
+impl Res {
+    fn new(doc_title: &str) -> Self {
+        Res {
+            schema_version: SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            doc_title: doc_title.to_owned(),
+            package_type: None,
+            source: None,
+            partial: None,
+            media: BTreeMap::new(),
+            sanitized_names: BTreeMap::new(),
+            corrected_names: BTreeMap::new(),
+            exif: BTreeMap::new(),
+            dimensions: BTreeMap::new(),
+            redactions: BTreeMap::new(),
+            media_fields: BTreeMap::new(),
+            thumbnail: None,
+            contact_sheet: None,
+            similar_images: Vec::new(),
+            srt: None,
+            graph: None,
+            reveal: None,
+            layout: None,
+            chunks: None,
+            pages: PageRes {
+                slides: BTreeMap::new(),
+                masters: Some(BTreeMap::new()),
+            },
+            themes: BTreeMap::new(),
+            fonts: Vec::new(),
+            vba: None,
+            signatures: Vec::new(),
+            stats: None,
+            profile: None,
+            summary: None,
+        }
+    }
+}
+
+// what to extract and how
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub text: TextOptions,
+    pub fonts: bool,
+    pub vba: bool,
+    pub stats: bool,
+    pub paragraphs: bool,
+    pub shapes: bool,
+    // equations as LaTeX, and as MathML too with mathml
+    pub equations: bool,
+    pub mathml: bool,
+    // list the layout and master images shown on each slide
+    pub resolve_inherited: bool,
+    // export media under their package paths, ppt/media/image1.png, rather
+    // than their file names
+    pub keep_paths: bool,
+    // make media names ASCII, valid on Windows and as S3 keys, and unique
+    pub sanitize_names: bool,
+    // export media under the extension of what their content is, image1.bin
+    // holding a PNG as image1.png
+    pub fix_extensions: bool,
+    // remove EXIF, XMP and other metadata from exported JPEG, PNG and TIFF
+    // images
+    pub strip_metadata: bool,
+    // record the capture date, camera and GPS presence of exported images
+    pub exif_report: bool,
+    // record the width and height of exported images
+    pub dimensions: bool,
+    // leave out the names of the people who signed the package and the
+    // targets of linked images, paths on the author's machine
+    pub anonymize: bool,
+    // add the text of each slide's notes to the index
+    pub notes: bool,
+    // export subtitles of the slide notes, or else text, timed by how long
+    // each slide shows
+    pub srt: bool,
+    // extract the slide masters, listed under pages.masters
+    pub masters: bool,
+    // leave out slide placeholders holding their layout's prompt text
+    pub exclude_prompt_text: bool,
+    // export the graph of the parts and their relationships
+    pub graph: Option<GraphFormat>,
+    // export a reveal.js deck of the slide text and images
+    pub reveal: bool,
+    // export the text blocks of the slides with their boxes
+    pub layout: Option<LayoutFormat>,
+    // the number of keywords to find for each slide
+    pub keywords: Option<usize>,
+    // export the sheets of the workbooks embedded with the charts
+    pub chart_data: Option<ChartDataFormat>,
+    // export an SVG of the shapes drawn on each slide
+    pub shapes_svg: bool,
+    // export a folder of each slide's images and text, previewed by a page
+    // linking only to them
+    pub bundle: bool,
+    // export the text of the shapes in chunks of at most max_tokens, for
+    // embedding
+    pub chunks: bool,
+    pub max_tokens: u32,
+    // downscale exported images larger than this many pixels wide or high
+    #[cfg(feature = "resize")]
+    pub max_dimension: Option<u32>,
+    // export a montage of the slide images labeled with their slide numbers
+    #[cfg(feature = "contact-sheet")]
+    pub contact_sheet: bool,
+    // group the images that are the same picture by their perceptual hashes
+    #[cfg(feature = "similar")]
+    pub dedupe_similar: bool,
+    // download the externally linked images
+    #[cfg(feature = "http")]
+    pub fetch_external: bool,
+    // read the duration and codecs of videos from their containers
+    #[cfg(feature = "media-probe")]
+    pub probe_media: bool,
+    // programs adding fields to the slides and media in the index
+    #[cfg(feature = "plugins")]
+    pub plugins: Vec<plugin::Plugin>,
+    // extract only the slides whose text matches, and the media they show
+    pub grep: Option<Regex>,
+    // replace the matches of these patterns in the text and notes
+    pub redact: Option<redact::Redactor>,
+    // placeholder types whose text is left out
+    pub exclude_placeholders: Vec<String>,
+    #[cfg(feature = "lang-detect")]
+    pub detect_lang: bool,
+    pub md5: bool,
+    pub name_template: Option<NameTemplate>,
+    pub max_uncompressed_size: u64,
+    pub max_entries: usize,
+    pub max_media_size: u64,
+    // reading the presentation fails once it's past
+    pub deadline: limits::Deadline,
+    pub salvage: bool,
+    pub profile: bool,
+    // read everything but write nothing
+    pub dry_run: bool,
+    // extractors to run or not to run regardless of the other options
+    pub enable: Vec<String>,
+    pub disable: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            text: TextOptions::default(),
+            fonts: false,
+            vba: false,
+            stats: false,
+            paragraphs: false,
+            shapes: false,
+            equations: false,
+            mathml: false,
+            resolve_inherited: false,
+            keep_paths: false,
+            sanitize_names: false,
+            fix_extensions: false,
+            strip_metadata: false,
+            exif_report: false,
+            dimensions: false,
+            anonymize: false,
+            notes: false,
+            srt: false,
+            masters: true,
+            exclude_prompt_text: false,
+            graph: None,
+            reveal: false,
+            shapes_svg: false,
+            chart_data: None,
+            keywords: None,
+            layout: None,
+            bundle: false,
+            chunks: false,
+            max_tokens: chunks::DEFAULT_MAX_TOKENS,
+            #[cfg(feature = "resize")]
+            max_dimension: None,
+            #[cfg(feature = "contact-sheet")]
+            contact_sheet: false,
+            #[cfg(feature = "similar")]
+            dedupe_similar: false,
+            #[cfg(feature = "http")]
+            fetch_external: false,
+            #[cfg(feature = "media-probe")]
+            probe_media: false,
+            #[cfg(feature = "plugins")]
+            plugins: Vec::new(),
+            grep: None,
+            redact: None,
+            exclude_placeholders: Vec::new(),
+            #[cfg(feature = "lang-detect")]
+            detect_lang: false,
+            md5: false,
+            name_template: None,
+            max_uncompressed_size: limits::DEFAULT_MAX_UNCOMPRESSED_SIZE,
+            max_entries: limits::DEFAULT_MAX_ENTRIES,
+            max_media_size: limits::DEFAULT_MAX_MEDIA_SIZE,
+            deadline: limits::Deadline::default(),
+            salvage: false,
+            profile: false,
+            dry_run: false,
+            enable: Vec::new(),
+            disable: Vec::new(),
+        }
+    }
+}
+
+// the index of an extraction, and for a dry run the media file names and
+// sizes it would have exported
+pub struct Extracted {
+    pub res: Res,
+    pub dry_media: Vec<(String, u64)>,
+}
+
+// called with the slides, masters and media as soon as they're read, for
+// consumers streaming them somewhere of their own
+pub trait ExtractVisitor {
+    // the parts found in the package, before any is read
+    fn on_start(&mut self, _pkg: &Package) {}
+    fn on_slide(&mut self, _slide: &SingleRes) {}
+    fn on_master(&mut self, _master: &SingleRes) {}
+    // each media part, as decompressed by the media writer to be exported
+    // as filename, on the extracting thread while the writer goes on. Not
+    // called for a dry run, which reads none.
+    fn on_media(&mut self, _part_name: &str, _filename: &str, _data: &[u8]) {}
+    // each media part once the media writer put it in the output as
+    // filename, reported as on_media is
+    fn on_media_written(&mut self, _part_name: &str, _filename: &str) {}
+    // each part that failed, which the extraction goes on without
+    fn on_error(&mut self, _error: &PartError) {}
+}
+
+// visits nothing
+impl ExtractVisitor for () {}
+
+impl<T: ExtractVisitor + ?Sized> ExtractVisitor for &mut T {
+    fn on_start(&mut self, pkg: &Package) {
+        (**self).on_start(pkg)
+    }
+    fn on_slide(&mut self, slide: &SingleRes) {
+        (**self).on_slide(slide)
+    }
+    fn on_master(&mut self, master: &SingleRes) {
+        (**self).on_master(master)
+    }
+    fn on_media(&mut self, part_name: &str, filename: &str, data: &[u8]) {
+        (**self).on_media(part_name, filename, data)
+    }
+    fn on_media_written(&mut self, part_name: &str, filename: &str) {
+        (**self).on_media_written(part_name, filename)
+    }
+    fn on_error(&mut self, error: &PartError) {
+        (**self).on_error(error)
+    }
+}
+
+// visits with the visitor when there is one
+impl<T: ExtractVisitor> ExtractVisitor for Option<T> {
+    fn on_start(&mut self, pkg: &Package) {
+        if let Some(visitor) = self {
+            visitor.on_start(pkg)
+        }
+    }
+    fn on_slide(&mut self, slide: &SingleRes) {
+        if let Some(visitor) = self {
+            visitor.on_slide(slide)
+        }
+    }
+    fn on_master(&mut self, master: &SingleRes) {
+        if let Some(visitor) = self {
+            visitor.on_master(master)
+        }
+    }
+    fn on_media(&mut self, part_name: &str, filename: &str, data: &[u8]) {
+        if let Some(visitor) = self {
+            visitor.on_media(part_name, filename, data)
+        }
+    }
+    fn on_media_written(&mut self, part_name: &str, filename: &str) {
+        if let Some(visitor) = self {
+            visitor.on_media_written(part_name, filename)
+        }
+    }
+    fn on_error(&mut self, error: &PartError) {
+        if let Some(visitor) = self {
+            visitor.on_error(error)
+        }
+    }
+}
+
+// visits with both, the first first
+impl<A: ExtractVisitor, B: ExtractVisitor> ExtractVisitor for (A, B) {
+    fn on_start(&mut self, pkg: &Package) {
+        self.0.on_start(pkg);
+        self.1.on_start(pkg);
+    }
+    fn on_slide(&mut self, slide: &SingleRes) {
+        self.0.on_slide(slide);
+        self.1.on_slide(slide);
+    }
+    fn on_master(&mut self, master: &SingleRes) {
+        self.0.on_master(master);
+        self.1.on_master(master);
+    }
+    fn on_media(&mut self, part_name: &str, filename: &str, data: &[u8]) {
+        self.0.on_media(part_name, filename, data);
+        self.1.on_media(part_name, filename, data);
+    }
+    fn on_media_written(&mut self, part_name: &str, filename: &str) {
+        self.0.on_media_written(part_name, filename);
+        self.1.on_media_written(part_name, filename);
+    }
+    fn on_error(&mut self, error: &PartError) {
+        self.0.on_error(error);
+        self.1.on_error(error);
+    }
+}
+
+// extract the presentation read from reader into output, doc_title being the
+// file name of the presentation. visitor is called with each slide, master
+// and media part as soon as it's read, the index itself is left to the caller.
+pub fn extract_archive<R: Read + Seek>(
+    reader: R,
+    doc_title: &str,
+    options: &Options,
+    output: &mut Output,
+    phases: &mut Phases,
+    visitor: &mut dyn ExtractVisitor,
+) -> Result<Extracted, ExportMediaError> {
+    let start = Instant::now();
+    let warnings = logging::warnings();
+    let mut res = Res::new(doc_title);
+    let deadline = options.deadline;
+    let mut reader = limits::Timed::new(reader, deadline);
+
+    phases.next("zip");
+    // salvaging needs the whole archive at hand should it fail to open
+    let mut data = Vec::new();
+    let reader: Box<dyn ReadSeek + '_> = if options.salvage {
+        reader.read_to_end(&mut data)?;
+        Box::new(io::Cursor::new(data.as_slice()))
+    } else {
+        Box::new(reader)
+    };
+    let mut archive = match zip::ZipArchive::new(reader) {
+        Ok(archive) => archive,
+        Err(e) if options.salvage => {
+            warn!("failed to open archive, salvaging, error: {}", e);
+            let (salvaged, partial) =
+                salvage::salvage(&data, e.to_string(), options.max_uncompressed_size)?;
+            res.partial = Some(partial);
+            zip::ZipArchive::new(Box::new(io::Cursor::new(salvaged)) as Box<dyn ReadSeek>)
+                .map_err(|e| e.to_string())?
+        }
+        Err(e) => return Err(e.to_string().into()),
+    };
+    limits::check(
+        &mut archive,
+        options.max_uncompressed_size,
+        options.max_entries,
+    )?;
+
+    match package::package_type(&mut archive) {
+        Ok(package_type) => {
+            debug!("package type: {:?}", package_type);
+            res.package_type = Some(package_type);
+        }
+        Err(e) => {
+            error!("failed to detect package type, error: {}", e)
+        }
+    }
+
+    let mut errors = Errors::new("discover");
+    let pkg = package::discover(&mut archive, &mut errors)?;
+    visitor.on_start(&pkg);
+
+    let mut cx = Context::new(&mut archive, &pkg, doc_title, options, output, res, visitor)?;
+    cx.errors.extend(errors.take());
+    cx.report_errors();
+    if !cx.is_enabled("masters") {
+        cx.res.pages.masters = None;
+    }
+    for extractor in extractor::pipeline() {
+        if !cx.is_enabled(extractor.name()) {
+            continue;
+        }
+        deadline.check()?;
+        phases.next(extractor.name());
+        cx.errors.phase = Some(extractor.name());
+        let extracted = extractor.extract(&mut cx);
+        // the media written while it ran, for the visitor to hear of them
+        // before the writer finishes
+        cx.visit_media();
+        cx.report_errors();
+        if let Err(e) = extracted {
+            // the output is the caller's to clean up, unless the writer
+            // panicked with it
+            if let Err(finish_error) = cx.finish_media() {
+                error!("failed to finish writing media, error: {}", finish_error);
+            }
+            cx.report_errors();
+            // a read cut short by the timeout fails however the reader of it
+            // makes of the error
+            deadline.check()?;
+            return Err(e);
+        }
+    }
+    // the writer holds the output until the media are written
+    cx.finish_media()?;
+    cx.report_errors();
+    let Context {
+        mut res,
+        dry_media,
+        mut profile,
+        ..
+    } = cx;
+    debug!("res: {:?}", res);
+    // which the caller writes next
+    if !options.dry_run {
+        phases.next("index");
+    }
+    if options.profile {
+        profile.phases(phases.finished());
+        res.profile = Some(profile);
+    }
+    res.summary = Some(Summary::new(
+        &res,
+        logging::warnings() - warnings,
+        start.elapsed(),
+    ));
+    Ok(Extracted { res, dry_media })
+}
+
+// the index and the exported files of an extraction done in memory
+pub struct Extraction {
+    pub res: Res,
+    // exported file name -> contents, index.json aside
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+// extract the presentation in data without touching the filesystem, the
+// index's doc_title is left empty
+pub fn extract_from_bytes(data: &[u8], options: Options) -> Result<Extraction, ExportMediaError> {
+    extract_in_memory(data, options, &mut ())
+}
+
+// extract the presentation at path, a file or a package stored unzipped,
+// without writing anything, visitor being called with each slide, master and
+// media part as it's read
+pub fn extract_with(
+    path: impl AsRef<Path>,
+    options: Options,
+    mut visitor: impl ExtractVisitor,
+) -> Result<Res, ExportMediaError> {
+    let path = path.as_ref();
+    let reader: Box<dyn ReadSeek> = match path.is_dir() {
+        true => Box::new(io::Cursor::new(unpacked::pack(
+            path,
+            options.max_uncompressed_size,
+        )?)),
+        false => Box::new(io::BufReader::new(fs::File::open(path)?)),
+    };
+    let doc_title = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut output = Output::null();
+    let mut phases = Phases::start("open");
+    let Extracted { res, .. } = extract_archive(
+        reader,
+        &doc_title,
+        &options,
+        &mut output,
+        &mut phases,
+        &mut visitor,
+    )?;
+    Ok(res)
+}
+
+fn extract_in_memory(
+    data: &[u8],
+    options: Options,
+    visitor: &mut dyn ExtractVisitor,
+) -> Result<Extraction, ExportMediaError> {
+    let mut output = Output::memory();
+    let mut phases = Phases::start("open");
+    let source = checksum::checksum(&mut &data[..], options.md5)?;
+    let Extracted { mut res, .. } = extract_archive(
+        io::Cursor::new(data),
+        "",
+        &options,
+        &mut output,
+        &mut phases,
+        visitor,
+    )?;
+    res.source = Some(source);
+    Ok(Extraction {
+        res,
+        files: output.into_files(),
+    })
+}
+
+// an input archive, a file, a download or what was salvaged from it
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+#[derive(Debug)]
+pub enum ExportMediaError {
+    Io(std::io::Error),
+    Parse(roxmltree::Error, String),
+    Custom(String),
+}
+
+impl fmt::Display for ExportMediaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportMediaError::Io(err) => write!(f, "IO error: {}", err),
+            ExportMediaError::Parse(err, fname) => write!(f, "Parse error: {} in {}", err, fname),
+            ExportMediaError::Custom(err) => write!(f, "Custom error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ExportMediaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExportMediaError::Io(err) => Some(err),
+            ExportMediaError::Parse(err, _) => Some(err),
+            ExportMediaError::Custom(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ExportMediaError {
+    fn from(error: std::io::Error) -> Self {
+        ExportMediaError::Io(error)
+    }
+}
+
+impl From<String> for ExportMediaError {
+    fn from(error: String) -> Self {
+        ExportMediaError::Custom(error)
+    }
+}
+
+// a media file that was exported
+pub(crate) struct Exported {
+    pub filename: String,
+    // that of the media in the archive when an existing file is kept
+    pub checksum: Checksum,
+    // of images with exif_report
+    pub exif: Option<Exif>,
+    // of images with dimensions, as exported
+    pub dimensions: Option<Dimensions>,
+}
+
+pub(crate) fn export_media<R: Read>(
+    output: &mut Output,
+    f: &mut R,
+    filename: String,
+    options: &Options,
+) -> Result<Exported, ExportMediaError> {
+    trace!("out filename: {:?}", filename);
+    #[cfg(feature = "resize")]
+    let resize = options.max_dimension.is_some();
+    #[cfg(not(feature = "resize"))]
+    let resize = false;
+    if !options.strip_metadata && !options.exif_report && !options.dimensions && !resize {
+        let (filename, checksum) = write_media(output, f, filename, options.md5)?;
+        return Ok(Exported {
+            filename,
+            checksum,
+            exif: None,
+            dimensions: None,
+        });
+    }
+    let mut data = Vec::new();
+    f.read_to_end(&mut data)?;
+    // reported before stripping, the provenance of the original
+    let exif = match options.exif_report {
+        true => exif::exif(&data).unwrap_or_else(|e| {
+            error!("failed to read exif of {}, error: {}", filename, e);
+            None
+        }),
+        false => None,
+    };
+    #[cfg(feature = "resize")]
+    if let Some(max_dimension) = options.max_dimension {
+        // exported as it is when it can't be decoded
+        match resize::downscale(&data, max_dimension) {
+            Ok(Some(downscaled)) => data = downscaled,
+            Ok(None) => {}
+            Err(e) => error!("failed to downscale {}, error: {}", filename, e),
+        }
+    }
+    if options.strip_metadata {
+        data = strip::strip(data)
+            .map_err(|e| format!("failed to strip metadata of {}: {}", filename, e))?;
+    }
+    let dimensions = options
+        .dimensions
+        .then(|| dimensions::dimensions(&data))
+        .flatten();
+    let (filename, checksum) = write_media(output, &mut data.as_slice(), filename, options.md5)?;
+    Ok(Exported {
+        filename,
+        checksum,
+        exif,
+        dimensions,
+    })
+}
+
+fn write_media<R: Read>(
+    output: &mut Output,
+    f: &mut R,
+    filename: String,
+    md5: bool,
+) -> Result<(String, Checksum), ExportMediaError> {
+    // write contents of f to filename, hashing on the way
+    let outfile = match output.create(&filename)? {
+        Some(outfile) => outfile,
+        None => return Ok((filename, checksum::checksum(f, md5)?)),
+    };
+    let mut writer = checksum::HashingWriter::new(outfile, md5);
+
+    match io::copy(f, &mut writer) {
+        Ok(_) => Ok((filename, writer.finish())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Debug)]
+pub struct SlideContent {
+    pub texts: Vec<String>,
+    // paragraphs with text that aren't explicitly unbulleted
+    pub bullets: u32,
+    pub paragraphs: Vec<Paragraph>,
+    pub shapes: Vec<Shape>,
+    pub equations: Vec<Equation>,
+    pub pictures: Vec<Picture>,
+    pub videos: Vec<Video>,
+    pub background: Option<Background>,
+    // p:sld@showMasterSp, whether the layout and master shapes show through
+    pub show_master_shapes: bool,
+    pub advance_ms: Option<u64>,
+    pub hidden: bool,
+}
+
+// exclude lists placeholder types whose text is left out
+// prompts are the placeholder texts of the slide's layout and master, whose
+// placeholders are left out when they hold one
+pub fn slide(
+    mut f: ZipFile,
+    exclude: &[String],
+    prompts: &HashSet<String>,
+) -> Result<SlideContent, String> {
+    let mut res = SlideContent {
+        texts: Vec::new(),
+        bullets: 0,
+        paragraphs: Vec::new(),
+        shapes: Vec::new(),
+        equations: Vec::new(),
+        pictures: Vec::new(),
+        videos: Vec::new(),
+        background: None,
+        show_master_shapes: true,
+        advance_ms: None,
+        hidden: false,
+    };
+    let content = xml::read(&mut f).map_err(|e| e.to_string())?;
+    match xml::parse(&content) {
+        Ok(doc) => {
+            res.bullets = shapes::bullets(doc.root_element());
+            // the slide without the prompts, parsed again
+            let without_prompts = shapes::remove_prompts(&doc, prompts);
+            let reparsed = without_prompts
+                .as_deref()
+                .and_then(|text| xml::parse(text).ok());
+            let el = reparsed.as_ref().unwrap_or(&doc).root_element();
+            res.texts = shapes::run_texts(el, exclude);
+            res.paragraphs = shapes::paragraphs(el, exclude);
+            res.shapes = shapes::shapes(el, exclude);
+            res.equations = math::equations(el);
+            res.pictures = shapes::pictures(el);
+            res.videos = shapes::videos(el);
+            res.background = shapes::background(el);
+            res.show_master_shapes = show_master_shapes(el);
+            res.advance_ms = srt::advance_ms(el);
+            res.hidden = srt::hidden(el);
+        }
+        Err(e) => {
+            warn!("failed to parse {}, error: {}", f.name(), e);
+            // still get what text there is out of the broken XML
+            for cap in RE_TEXT.captures_iter(&content) {
+                if let Some(text) = cap.get(1) {
+                    res.texts.push(text::decode_entities(text.as_str()));
+                }
+            }
+            for cap in RE_PARAGRAPH.captures_iter(&content) {
+                if let Some(paragraph) = cap.get(1).or_else(|| cap.get(2)) {
+                    let paragraph = paragraph.as_str();
+                    if paragraph.contains("<a:t>") && !paragraph.contains("<a:buNone/>") {
+                        res.bullets += 1;
+                    }
+                }
+            }
+        }
+    }
+    debug!("slide {} content: {:?}", f.name(), res);
+    Ok(res)
+}
+
+// get the image relationships of part_name from its rels file f, id -> image
+// part name
+fn rels(
+    f: zip::read::ZipFile,
+    part_name: &str,
+) -> Result<BTreeMap<String, String>, ExportMediaError> {
+    let fname = f.name().to_owned();
+    let base_dir = Path::new(part_name)
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let text = xml::read(f)?;
+    let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, fname.clone()))?;
+    let image_rel_els = doc.root_element().elements().filter(|el| {
+        // linked images are listed under external_images instead
+        el.has_tag_name((xml::NS_PACKAGE_RELATIONSHIPS, "Relationship"))
+            && el.attribute("Type") == Some(ATTR_REL_TYPE_IMAGE)
+            && el.attribute("TargetMode") != Some("External")
+    });
+    let mut res = BTreeMap::new();
+    for image_rel_el in image_rel_els {
+        let (id, rel_image_path) = match (
+            image_rel_el.attribute("Id"),
+            image_rel_el.attribute("Target"),
+        ) {
+            (Some(id), Some(target)) => (id, target),
+            _ => continue,
+        };
+        res.insert(
+            id.to_owned(),
+            package::resolve_target(&base_dir, rel_image_path),
+        );
+    }
+    Ok(res)
+}
+
+// the placeholder texts of a layout or master
+fn part_prompts<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+) -> HashSet<String> {
+    match archive.by_name(part_name) {
+        Ok(file) => match xml::read(file).map_err(|e| e.to_string()).and_then(|text| {
+            let doc = xml::parse(&text).map_err(|e| e.to_string())?;
+            Ok(shapes::prompt_texts(doc.root_element()))
+        }) {
+            Ok(prompts) => prompts,
+            Err(e) => {
+                error!("failed to parse {}, error: {}", part_name, e);
+                HashSet::new()
+            }
+        },
+        Err(_) => HashSet::new(),
+    }
+}
+
+// the images of a layout or master, and whether it shows the master shapes
+fn part_images<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+) -> (Vec<String>, bool) {
+    let images = match archive.by_name(&package::rels_part(part_name)) {
+        Ok(file) => match rels(file, part_name) {
+            Ok(rels) => rels.into_values().collect(),
+            Err(e) => {
+                error!("failed to get rels, error: {}", e);
+                Vec::new()
+            }
+        },
+        Err(_) => Vec::new(),
+    };
+    let show = match archive.by_name(part_name) {
+        Ok(file) => xml::read(file)
+            .ok()
+            .and_then(|text| {
+                xml::parse(&text)
+                    .ok()
+                    .map(|doc| show_master_shapes(doc.root_element()))
+            })
+            .unwrap_or(true),
+        Err(_) => true,
+    };
+    (images, show)
+}
+
+// the name a media part is exported as by default, the part name itself with
+// keep_paths, or else the part's file name. A kept path is only its normal
+// components, so that no '..' or root takes it out of the output
+fn default_name(part_name: &str, keep_paths: bool) -> String {
+    match keep_paths {
+        true => Path::new(part_name)
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/"),
+        false => Path::new(part_name)
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+    }
+}
+
+fn show_master_shapes(el: roxmltree::Node) -> bool {
+    el.attribute("showMasterSp")
+        .is_none_or(|show| show != "0" && show != "false")
+}
+
+// get page no from filename
+fn page_no(fname: &str) -> Result<u32, String> {
+    if let Some(matched) = RE_PAGE_NO.captures(fname) {
+        if let Some(page_no) = matched.get(2) {
+            page_no.as_str().parse::<u32>().map_err(|e| e.to_string())
+        } else {
+            Err("Can't find valid page no".into())
+        }
+    } else {
+        Err("Can't find valid page no".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::{default_name, errors, extract_in_memory, page_no, Options, PartError};
+
+    const RELS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+    const TYPES: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+    // a presentation of the given slides, by part name and contents
+    fn presentation(slides: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut add = |name: &str, data: &[u8]| {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        };
+        add(
+            "_rels/.rels",
+            format!(
+                r#"<Relationships xmlns="{}"><Relationship Id="rId1" Type="{}/officeDocument" Target="ppt/presentation.xml"/></Relationships>"#,
+                RELS, TYPES
+            )
+            .as_bytes(),
+        );
+        let (mut ids, mut rels) = (String::new(), String::new());
+        for (i, (name, _)) in slides.iter().enumerate() {
+            ids += &format!(r#"<p:sldId id="{}" r:id="rId{}"/>"#, 256 + i, i + 1);
+            rels += &format!(
+                r#"<Relationship Id="rId{}" Type="{}/slide" Target="/{}"/>"#,
+                i + 1,
+                TYPES,
+                name
+            );
+        }
+        add(
+            "ppt/presentation.xml",
+            format!(
+                r#"<p:presentation xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main" xmlns:r="{}"><p:sldIdLst>{}</p:sldIdLst></p:presentation>"#,
+                TYPES, ids
+            )
+            .as_bytes(),
+        );
+        add(
+            "ppt/_rels/presentation.xml.rels",
+            format!(
+                r#"<Relationships xmlns="{}">{}</Relationships>"#,
+                RELS, rels
+            )
+            .as_bytes(),
+        );
+        for (name, data) in slides {
+            add(name, data);
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn kept_paths_stay_inside_the_output() {
+        assert_eq!(default_name("ppt/media/image1.png", false), "image1.png");
+        assert_eq!(
+            default_name("ppt/media/image1.png", true),
+            "ppt/media/image1.png"
+        );
+        assert_eq!(default_name("../../escaped.png", true), "escaped.png");
+        assert_eq!(default_name("/ppt/media/a.png", true), "ppt/media/a.png");
+    }
+
+    #[test]
+    fn page_no_of_a_number_too_large() {
+        assert_eq!(page_no("ppt/theme/theme2.xml"), Ok(2));
+        assert!(page_no("ppt/theme/theme99999999999.xml").is_err());
+    }
+
+    #[test]
+    fn errors_name_a_slide_that_does_not_parse() {
+        // XML that is merely malformed has its text salvaged, text that isn't
+        // UTF-8 has none to salvage
+        let data = presentation(&[
+            ("ppt/slides/slide1.xml", b"<p:sld>\xe9</p:sld>"),
+            (
+                "ppt/slides/slide2.xml",
+                br#"<p:sld xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"/>"#,
+            ),
+        ]);
+        let mut part_errors: Vec<PartError> = Vec::new();
+        let extraction = extract_in_memory(&data, Options::default(), &mut part_errors).unwrap();
+        assert_eq!(extraction.res.pages.slides.len(), 1);
+        let report: serde_json::Value =
+            serde_json::from_str(&errors::report("deck.pptx", &part_errors, None)).unwrap();
+        let errors = report["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["part"], "ppt/slides/slide1.xml");
+        assert_eq!(errors[0]["slide"], 1);
+    }
+}
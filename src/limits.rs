@@ -0,0 +1,141 @@
+use log::debug;
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    time::{Duration, Instant},
+};
+
+use crate::ExportMediaError;
+
+pub const DEFAULT_MAX_UNCOMPRESSED_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+pub const DEFAULT_MAX_MEDIA_SIZE: u64 = 1024 * 1024 * 1024;
+
+// refuse archives whose entries add up to more than the limits, going by the
+// sizes the archive claims for them
+pub fn check<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    max_uncompressed_size: u64,
+    max_entries: usize,
+) -> Result<(), ExportMediaError> {
+    if archive.len() > max_entries {
+        return Err(format!(
+            "archive has {} entries, more than the limit of {}",
+            archive.len(),
+            max_entries
+        )
+        .into());
+    }
+    let mut total: u64 = 0;
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i).map_err(|e| e.to_string())?;
+        total = total.saturating_add(file.size());
+        if total > max_uncompressed_size {
+            return Err(format!(
+                "archive expands to more than the limit of {} bytes",
+                max_uncompressed_size
+            )
+            .into());
+        }
+    }
+    debug!("archive has {} entries, {} bytes", archive.len(), total);
+    Ok(())
+}
+
+// a reader failing once more than max_size bytes come out of it, as the
+// sizes an archive claims can't be trusted
+pub struct Limited<R> {
+    inner: R,
+    remaining: u64,
+    max_size: u64,
+}
+
+impl<R: Read> Limited<R> {
+    pub fn new(inner: R, max_size: u64) -> Self {
+        Limited {
+            inner,
+            remaining: max_size,
+            max_size,
+        }
+    }
+}
+
+impl<R: Read> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // one byte more than allowed is enough to tell it's too large
+        let len = buf.len().min(self.remaining.saturating_add(1) as usize);
+        let n = self.inner.read(&mut buf[..len])?;
+        if n as u64 > self.remaining {
+            return Err(io::Error::other(format!(
+                "larger than the limit of {} bytes",
+                self.max_size
+            )));
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+// when an extraction is given up, none by default
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Deadline {
+    at: Option<(Instant, Duration)>,
+}
+
+impl Deadline {
+    // timeout from now
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Deadline {
+            at: timeout.map(|timeout| (Instant::now() + timeout, timeout)),
+        }
+    }
+
+    pub fn check(&self) -> io::Result<()> {
+        match self.at {
+            Some((at, timeout)) if Instant::now() >= at => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out after {:?}", timeout),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// a reader failing once the deadline passes, every part of a package being
+// read through it, so a decompression bomb or a huge part gives up midway
+pub struct Timed<R> {
+    inner: R,
+    deadline: Deadline,
+}
+
+impl<R> Timed<R> {
+    pub fn new(inner: R, deadline: Deadline) -> Self {
+        Timed { inner, deadline }
+    }
+}
+
+impl<R: Read> Read for Timed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.deadline.check()?;
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for Timed<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+// a duration as seconds, 120 or 120s, or else in ms, m or h, e.g. 2m or 1.5h
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration {}, expected e.g. 120s, 2m or 1h", s);
+    let (n, unit) = [("ms", 0.001), ("s", 1.0), ("m", 60.0), ("h", 3600.0)]
+        .into_iter()
+        .find_map(|(suffix, unit)| s.strip_suffix(suffix).map(|n| (n, unit)))
+        .unwrap_or((s, 1.0));
+    let n: f64 = n.trim().parse().map_err(|_| invalid())?;
+    Duration::try_from_secs_f64(n * unit)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(invalid)
+}
@@ -0,0 +1,54 @@
+use std::{fs, io, path::Path};
+
+use clap::Args;
+
+use crate::{errors::Errors, package, ExportMediaError};
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Input file
+    input_file: String,
+}
+
+// print the slides and media of a presentation without extracting anything
+pub fn run(args: &ListArgs) -> Result<(), ExportMediaError> {
+    let archivef = fs::File::open(Path::new(&args.input_file))?;
+    let mut archive =
+        zip::ZipArchive::new(io::BufReader::new(archivef)).map_err(|e| e.to_string())?;
+    let pkg = package::discover(&mut archive, &mut Errors::default())?;
+
+    println!("{:>5}  {:>6}  Part", "Slide", "Images");
+    println!("{:->5}  {:->6}  ----", "", "");
+    let mut total_images = 0;
+    for (i, part_name) in pkg.slides.iter().enumerate() {
+        let images = package::part_rels(&mut archive, part_name)?
+            .iter()
+            .filter(|rel| rel.kind == package::REL_IMAGE)
+            .count();
+        total_images += images;
+        println!("{:>5}  {:>6}  {}", i + 1, images, part_name);
+    }
+    println!("{:->5}  {:->6}  ----", "", "");
+    println!(
+        "{:>5}  {:>6}  {} slides",
+        "",
+        total_images,
+        pkg.slides.len()
+    );
+    println!();
+
+    println!("{:>10}  Media", "Length");
+    println!("{:->10}  -----", "");
+    let mut total_size = 0;
+    for part_name in &pkg.media {
+        let size = archive
+            .by_name(part_name)
+            .map_err(|e| e.to_string())?
+            .size();
+        total_size += size;
+        println!("{:>10}  {}", size, part_name);
+    }
+    println!("{:->10}  -----", "");
+    println!("{:>10}  {} files", total_size, pkg.media.len());
+    Ok(())
+}
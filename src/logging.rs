@@ -0,0 +1,248 @@
+use log::{info, Level, LevelFilter, Log, Metadata, Record};
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+
+use crate::ExportMediaError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    // one JSON object per event, with the file, slide and phase being worked on
+    Json,
+}
+
+// what the extraction is working on, attached to JSON log events
+#[derive(Default)]
+struct Context {
+    file: Option<String>,
+    slide: Option<u32>,
+    phase: Option<&'static str>,
+    duration: Option<Duration>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context::default());
+}
+
+// the target of the summaries of extractions, logged even when the rest
+// of the info events are left out
+pub const SUMMARY: &str = "summary";
+
+// warnings and errors logged so far, those filtered out included
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn warnings() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+pub fn set_file(file: Option<&str>) {
+    CONTEXT.with(|context| context.borrow_mut().file = file.map(String::from));
+}
+
+pub fn set_slide(slide: Option<u32>) {
+    CONTEXT.with(|context| context.borrow_mut().slide = slide);
+}
+
+// times the phases of an extraction one after the other, logging how long
+// each one took as it ends
+pub struct Phases {
+    current: Option<(&'static str, Instant)>,
+    finished: Vec<(&'static str, Duration)>,
+}
+
+impl Phases {
+    pub fn start(phase: &'static str) -> Self {
+        let mut res = Phases {
+            current: None,
+            finished: Vec::new(),
+        };
+        res.next(phase);
+        res
+    }
+
+    pub fn next(&mut self, phase: &'static str) {
+        self.finish();
+        CONTEXT.with(|context| context.borrow_mut().phase = Some(phase));
+        self.current = Some((phase, Instant::now()));
+    }
+
+    // the phases that ended so far and how long they took
+    pub fn finished(&self) -> &[(&'static str, Duration)] {
+        &self.finished
+    }
+
+    fn finish(&mut self) {
+        if let Some((phase, start)) = self.current.take() {
+            let duration = start.elapsed();
+            self.finished.push((phase, duration));
+            CONTEXT.with(|context| context.borrow_mut().duration = Some(duration));
+            info!("finished {} in {:?}", phase, duration);
+            CONTEXT.with(|context| {
+                let mut context = context.borrow_mut();
+                context.phase = None;
+                context.slide = None;
+                context.duration = None;
+            });
+        }
+    }
+}
+
+impl Drop for Phases {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+// log to stderr, or to a file rotated once it grows past max_size with keep
+// old files as path.1, path.2, ...
+pub fn init(
+    log_file: Option<&str>,
+    max_size: u64,
+    keep: usize,
+    format: LogFormat,
+) -> Result<(), ExportMediaError> {
+    // events other than errors and summaries are what JSON logs are for
+    let default_filter = match format {
+        LogFormat::Text => "error,summary=info",
+        LogFormat::Json => "info",
+    };
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter));
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let message = record.args().to_string();
+            let mut event = serde_json::json!({
+                "ts": buf.timestamp_millis().to_string(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": message,
+            });
+            // errors are logged as "failed to ..., error: ..."
+            if let Some((_, error)) = message.split_once(", error: ") {
+                event["error"] = error.into();
+            }
+            CONTEXT.with(|context| {
+                let context = context.borrow();
+                if let Some(file) = &context.file {
+                    event["file"] = file.as_str().into();
+                }
+                if let Some(slide) = context.slide {
+                    event["slide"] = slide.into();
+                }
+                if let Some(phase) = context.phase {
+                    event["phase"] = phase.into();
+                }
+                if let Some(duration) = context.duration {
+                    event["duration_ms"] = (duration.as_secs_f64() * 1000.0).into();
+                }
+            });
+            writeln!(buf, "{}", event)
+        });
+    }
+    if let Some(log_file) = log_file {
+        let file = RotatingFile::open(Path::new(log_file), max_size, keep)?;
+        builder
+            .target(env_logger::Target::Pipe(Box::new(file)))
+            .write_style(env_logger::WriteStyle::Never);
+    }
+    let inner = builder.build();
+    let max_level = inner.filter().max(LevelFilter::Warn);
+    log::set_boxed_logger(Box::new(CountingLogger { inner })).map_err(|e| e.to_string())?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+// counts the warnings and errors before filtering them as the logger would
+struct CountingLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for CountingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= Level::Warn {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: fs::File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_size: u64, keep: usize) -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(RotatingFile {
+            path: path.to_owned(),
+            size: file.metadata()?.len(),
+            file,
+            max_size,
+            keep,
+        })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            self.file = fs::File::create(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated(self.keep));
+            for n in (1..self.keep).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    fs::rename(from, self.rotated(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+            self.file = fs::File::create(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
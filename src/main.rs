@@ -1,276 +1,1232 @@
-use log::{debug, error, trace};
-use regex::Regex;
+use log::{error, info, warn};
 use std::{
-    collections::HashMap,
-    fmt, fs,
-    io::{self, Read},
+    collections::HashSet,
+    ffi::OsStr,
+    fs,
+    io::{self, Read, Seek},
     path::Path,
+    process,
+    time::{Duration, Instant},
 };
 
-use clap::Parser;
-use env_logger;
-use once_cell::sync::Lazy;
-use serde::Serialize;
-use zip::{self, read::ZipFile};
+use clap::{
+    builder::PossibleValuesParser, error::ErrorKind, parser::ValueSource, ArgAction, ArgMatches,
+    Args, CommandFactory, FromArgMatches, Parser, Subcommand,
+};
+use regex::Regex;
 
-static RE_TEXT: Lazy<Regex> = Lazy::new(|| Regex::new(r"<a:t>([\s\S]+?)</a:t>").unwrap());
-static RE_PAGE_NO: Lazy<Regex> = Lazy::new(|| Regex::new(r"(slide|slideMaster)(\d+).xml").unwrap());
+use ppt_img_extract::{
+    bundle, checksum, chunks, extractor, fonts, keywords, limits, logging, package,
+    redact::Redactor, slide, text, unpacked, xml, ExportMediaError, ExtractVisitor, Extracted,
+    Options, ReadSeek, Res, SingleRes, DONE_FILE, INDEX_FILE, SCHEMA_VERSION,
+};
+
+mod bench;
+mod bulk;
+mod catalog;
+mod config;
+mod container;
+mod diff;
+mod duplicates;
+mod events;
+mod hooks;
+mod image_map;
+mod incremental;
+mod list;
+mod merge;
+mod meta;
+mod schema;
+mod search;
+mod serve;
+mod split;
+mod validate;
+mod verify;
+
+use bulk::IndexFormat;
+use container::Container;
+use events::Events;
+use hooks::Hooks;
+use incremental::Manifest;
+use logging::LogFormat;
+use ppt_img_extract::chart_data::ChartDataFormat;
+use ppt_img_extract::errors::{self, PartError};
+use ppt_img_extract::graph::GraphFormat;
+#[cfg(feature = "http")]
+use ppt_img_extract::http;
+use ppt_img_extract::layout::LayoutFormat;
+use ppt_img_extract::output::{self, OnFailure, Output, Overwrite};
+#[cfg(feature = "plugins")]
+use ppt_img_extract::plugin::Plugin;
+#[cfg(any(feature = "s3", feature = "gcs", feature = "azure"))]
+use ppt_img_extract::remote;
+use ppt_img_extract::template::NameTemplate;
 
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
-struct Args {
-    /// Input file
-    #[arg(short, long)]
-    input_file: String,
+#[command(
+    version,
+    about,
+    long_about = None,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    // the extract flags without the subcommand, as before there were subcommands
+    #[command(flatten)]
+    extract: ExtractArgs,
+}
+
+// flags shared by all subcommands
+#[derive(Args)]
+struct GlobalArgs {
+    /// Config file with defaults for the extract flags [default: ppt-img-extract.toml if present]
+    #[arg(long, global = true)]
+    config: Option<String>,
+
+    /// Write the log to this file instead of stderr
+    #[arg(long, global = true)]
+    log_file: Option<String>,
+
+    /// Size in bytes at which the log file is rotated
+    #[arg(long, global = true, default_value_t = 10 * 1024 * 1024)]
+    log_max_size: u64,
+
+    /// Number of rotated log files to keep
+    #[arg(long, global = true, default_value_t = 5)]
+    log_keep: usize,
+
+    /// Log as plain text or as one JSON object per event
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+}
+
+#[derive(Args)]
+struct ExtractArgs {
+    /// Input files, any presentation package (.pptx, .pptm, .ppsx, .potx, ...) or a directory
+    /// it was unzipped into, several are extracted into a directory each under the output
+    /// directory, next to a catalog.json
+    #[arg(short, long, num_args = 1..)]
+    #[cfg_attr(not(feature = "http"), arg(required_unless_present = "files_from"))]
+    #[cfg_attr(
+        feature = "http",
+        arg(required_unless_present_any = ["input_url", "files_from"])
+    )]
+    input_file: Vec<String>,
+
+    /// Read more input files from this file, - for stdin, a path a line, e.g.
+    /// "find decks -name '*.pptx' | ppt-img-extract --files-from - -o out"
+    #[arg(long, value_name = "FILE")]
+    files_from: Option<String>,
+
+    /// Separate the paths of --files-from by NUL instead of newline, as find -print0 does
+    #[arg(short = '0', long, requires = "files_from")]
+    null: bool,
+
+    /// Download the presentation from this http(s) url instead of reading a file, or read it
+    /// with ranged requests from s3://bucket/key, gs://bucket/object or az://container/blob when
+    /// built with the s3, gcs or azure feature
+    #[cfg(feature = "http")]
+    #[arg(long, conflicts_with_all = ["input_file", "files_from", "incremental"])]
+    input_url: Option<String>,
+
+    /// Header for the --input-url request as "Name: value", e.g. "Authorization: Bearer ..."
+    #[cfg(feature = "http")]
+    #[arg(long, requires = "input_url")]
+    input_header: Vec<String>,
+
+    /// Read the input files as zip archives of presentations, extracting each .pptx, .pptm,
+    /// .ppsx, ... in them into a directory of its own
+    #[arg(long, conflicts_with = "incremental")]
+    #[cfg_attr(feature = "http", arg(conflicts_with = "input_url"))]
+    container: bool,
+
+    /// Group the slides that are the same, or nearly by their words, with the same images
+    /// across the presentations into duplicate_slides.json next to the catalog.json, extracting
+    /// even a single input file into a directory of its own
+    #[arg(long)]
+    duplicate_slides: bool,
+
+    /// Refuse --input-url downloads larger than this many bytes
+    #[cfg(feature = "http")]
+    #[arg(long, default_value_t = http::DEFAULT_MAX_DOWNLOAD_SIZE)]
+    max_download_size: u64,
 
     /// Output directory
     #[arg(short, long, default_value_t = String::from("./output"))]
     output_dir: String,
-}
 
-const DIR_MEDIA: &str = "ppt/media";
-const DIR_SLIDES_RELS: &str = "ppt/slides/_rels";
-const MASTER_RELS_DIR: &str = "ppt/slideMasters/_rels";
-const DIR_SLIDES: &str = "ppt/slides";
-const INDEX_FILE: &str = "index.json";
-const ATTR_REL_TYPE_IMAGE: &str =
-    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image";
-
-#[derive(Debug, Serialize)]
-struct SingleRes {
-    page_no: u32,
-    slide_master: bool,
-    images: Vec<String>,
-    texts: Vec<String>,
-}
+    /// Write the exported files and index.json into this zip archive instead of a directory
+    #[arg(long, conflicts_with = "output_dir")]
+    output_zip: Option<String>,
+
+    /// Write the exported files and index.json as a tar archive to this file, - for stdout
+    #[arg(long, conflicts_with_all = ["output_dir", "output_zip"])]
+    output_tar: Option<String>,
+
+    /// Create the output directory, or the one of --output-zip or --output-tar, and any missing
+    /// above it, failing when it's missing otherwise
+    #[arg(long, default_value_t = true, action = ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    create_dirs: bool,
+
+    /// Upload the exported files and index.json to s3://bucket/prefix/, with credentials,
+    /// region and endpoint from the AWS_* environment variables
+    #[cfg(feature = "s3")]
+    #[arg(long, conflicts_with_all = ["output_dir", "output_zip", "output_tar", "incremental"])]
+    output_url: Option<String>,
+
+    /// Number of parallel uploads to --output-url
+    #[cfg(feature = "s3")]
+    #[arg(long, default_value_t = 4)]
+    upload_concurrency: usize,
+
+    /// Export embedded fonts into {output_dir}/fonts
+    #[arg(long)]
+    fonts: bool,
+
+    /// Unicode normalization form applied to extracted text
+    #[arg(long, value_enum)]
+    normalize: Option<text::Normalization>,
+
+    /// Collapse whitespace in extracted text and drop empty entries
+    #[arg(long)]
+    trim_text: bool,
+
+    /// Detect the dominant languages of each slide
+    #[cfg(feature = "lang-detect")]
+    #[arg(long)]
+    detect_lang: bool,
+
+    /// Add word, character, bullet and image counts per slide and for the deck
+    #[arg(long)]
+    stats: bool,
+
+    /// Add the paragraphs of each slide with their bullet indent levels
+    #[arg(long)]
+    paragraphs: bool,
+
+    /// Add the text of each slide grouped by shape, with shape names and ids
+    #[arg(long)]
+    shapes: bool,
+
+    /// Add the equations of each slide as LaTeX
+    #[arg(long)]
+    equations: bool,
+
+    /// Add the equations as MathML too
+    #[arg(long, requires = "equations")]
+    mathml: bool,
+
+    /// Add the images each slide shows from its layout and master
+    #[arg(long)]
+    resolve_inherited: bool,
+
+    /// Export media under their paths in the package, e.g. ppt/media/image1.png
+    #[arg(long, conflicts_with = "name_template")]
+    keep_paths: bool,
+
+    /// Make media names ASCII, replacing characters invalid on Windows or in S3 keys, and
+    /// unique, listing the names that changed in the index
+    #[arg(long)]
+    sanitize_names: bool,
+
+    /// Export media whose extension doesn't match their content, e.g. image1.bin holding a PNG,
+    /// under the right one, by their first bytes or else their content type, listing them
+    /// under corrected_names in the index
+    #[arg(long)]
+    fix_extensions: bool,
+
+    /// Remove EXIF, XMP and other metadata such as GPS coordinates, camera serials and authors from exported JPEG, PNG and TIFF images
+    #[arg(long)]
+    strip_metadata: bool,
+
+    /// Record the capture date, camera make and model and whether there are GPS coordinates of each exported image with EXIF
+    #[arg(long)]
+    exif_report: bool,
+
+    /// Leave the signer, subject and issuer names out of the digital signatures in the index and
+    /// blank the targets of externally linked images, so that the output can be shared outside
+    /// the organization
+    #[arg(long)]
+    anonymize: bool,
+
+    /// Export subtitles of the slide notes, or the slide text without notes, as slides.srt,
+    /// timed by when each slide advances or else 5 seconds a slide
+    #[arg(long)]
+    srt: bool,
+
+    /// Export a reveal.js deck of the slide text, images and videos as reveal.html,
+    /// a lossy copy to present on the web
+    #[arg(long)]
+    reveal: bool,
+
+    /// Export the shapes drawn on each slide, preset geometries, lines and freeform paths with
+    /// their fills, as shapes/slide_007.svg
+    #[arg(long)]
+    shapes_svg: bool,
+
+    /// Export the sheets of the workbook embedded with each chart, with the values they were
+    /// last saved with, as charts/slide_007_chart_1_sheet_1.csv
+    #[arg(long, value_enum)]
+    chart_data: Option<ChartDataFormat>,
+
+    /// Export each slide to bundles/slide_007 with its images, a text.json and a preview.html
+    /// linking only to them, to share a single slide
+    #[arg(long)]
+    bundle: bool,
+
+    /// Export the text of each shape as chunks.jsonl, a chunk a line with its deck,
+    /// slide and shape, in chunks of at most --max-tokens for embedding
+    #[arg(long)]
+    chunks: bool,
+
+    /// Estimated tokens a chunk holds at most, at 4 characters a token
+    #[arg(long, default_value_t = chunks::DEFAULT_MAX_TOKENS, value_parser = clap::value_parser!(u32).range(1..))]
+    max_tokens: u32,
+
+    /// Export the graph of the package parts and their relationships, as Graphviz
+    /// graph.dot or graph.json of each part's relationships
+    #[arg(long, value_enum)]
+    graph: Option<GraphFormat>,
 
-#[derive(Debug, Serialize)]
-struct PageRes {
-    slides: HashMap<u32, SingleRes>,
-    masters: HashMap<u32, SingleRes>,
+    /// Export the text of each shape with its bounding box, as layout.json with boxes relative
+    /// to the slide size or as hOCR layout.hocr
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "json")]
+    layout: Option<LayoutFormat>,
+
+    /// Skip the slide masters, leaving the masters section out of the index
+    #[arg(long)]
+    no_masters: bool,
+
+    /// Leave out slide placeholders holding the prompt text of their layout or master,
+    /// e.g. "Click to add title"
+    #[arg(long)]
+    exclude_prompt_text: bool,
+
+    /// Download the externally linked images of http(s) urls along with the media
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    fetch_external: bool,
+
+    /// Add the duration and codecs of each video, read from its container
+    #[cfg(feature = "media-probe")]
+    #[arg(long)]
+    probe_media: bool,
+
+    /// Downscale exported images wider or higher than this many pixels to fit, keeping
+    /// their aspect ratio
+    #[cfg(feature = "resize")]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    max_dimension: Option<u32>,
+
+    /// Export a montage of every slide image, labeled with its slide number, as
+    /// _contact_sheet.png
+    #[cfg(feature = "contact-sheet")]
+    #[arg(long)]
+    contact_sheet: bool,
+
+    /// Group the images that are the same picture at different sizes, crops or compression
+    /// by their perceptual hashes, listing the groups under similar_images in the index with
+    /// the largest copy first
+    #[cfg(feature = "similar")]
+    #[arg(long)]
+    dedupe_similar: bool,
+
+    /// Add the fields a plugin returns as a JSON object to each slide and media file in
+    /// the index: a .wasm module, or a command run as `<command> slide` with the slide
+    /// JSON on stdin and as `<command> media <name>` with the file on stdin
+    #[cfg(feature = "plugins")]
+    #[arg(long = "plugin")]
+    plugins: Vec<Plugin>,
+
+    /// Extract only the slides whose text matches this regex, e.g. 'roadmap|timeline',
+    /// and the media they show
+    #[arg(long)]
+    grep: Option<Regex>,
+
+    /// Replace matches in the slide text and notes with placeholders, the patterns read from
+    /// FILE as `<name> <regex>` lines, e.g. `email [\w.+-]+@[\w-]+\.[\w.]+`, each match
+    /// becoming [<name>] and counted under redactions in the index
+    #[arg(long, value_name = "FILE", value_parser = Redactor::load)]
+    redact: Option<Redactor>,
+
+    /// Leave out the text of these placeholder types, e.g. ftr,sldNum,dt
+    #[arg(long, value_delimiter = ',')]
+    exclude_placeholders: Vec<String>,
+
+    /// Run these extractors whatever the other options, e.g. fonts,graph
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(extractor::names()))]
+    enable: Vec<String>,
+
+    /// Skip these extractors, e.g. media,themes
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(extractor::names()))]
+    disable: Vec<String>,
+
+    /// Export the VBA project of macro-enabled presentations
+    #[arg(long)]
+    vba: bool,
+
+    /// Add md5 checksums next to the sha256 ones
+    #[arg(long)]
+    md5: bool,
+
+    /// Name exported media after a template, e.g. "{doc}_{slide:03}_{n:02}.{ext}",
+    /// with {doc}, {slide}, {n}, {rid}, {hash}, {name} and {ext}
+    #[arg(long)]
+    name_template: Option<NameTemplate>,
+
+    /// Write the index as index.json, or as bulk.ndjson, an Elasticsearch _bulk request
+    /// indexing a document of the text, notes and images of each slide
+    #[arg(long, value_enum, default_value_t = IndexFormat::Index)]
+    format: IndexFormat,
+
+    /// Elasticsearch index of the documents of --format es-bulk [default: the one the
+    /// request is posted to]
+    #[arg(long)]
+    es_index: Option<String>,
+
+    /// Add the text of each slide's notes to the index
+    #[arg(long)]
+    notes: bool,
+
+    /// Add the top N keywords of each slide by TF-IDF among the slides of the deck to the
+    /// index, 5 without N, and among the slides of all the decks to the catalog.json
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "5")]
+    keywords: Option<usize>,
+
+    /// Write a CSV of the images of each slide and master, with their width, height and
+    /// SHA-256, to FILE in the output
+    #[arg(long, value_name = "FILE")]
+    image_map: Option<String>,
+
+    /// Compare the slides with those of the index.json of an earlier extraction, by their text
+    /// and image hashes, writing the added, removed and modified slides to changes.json
+    #[arg(long, value_name = "INDEX")]
+    baseline: Option<String>,
+
+    /// Also write each slide to slides/slide_007.json, or with "only" instead of the index
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "also")]
+    split_index: Option<split::SplitIndex>,
+
+    /// Overwrite existing output files without warning
+    #[arg(long, conflicts_with_all = ["skip_existing", "error_if_exists"])]
+    force: bool,
+
+    /// Keep existing output files instead of overwriting them
+    #[arg(long, conflicts_with = "error_if_exists")]
+    skip_existing: bool,
+
+    /// Run this shell command for each exported media file as soon as it's written, {path}
+    /// replaced with the file's path, e.g. "clamscan {path}"
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["output_zip", "output_tar"])]
+    #[cfg_attr(feature = "s3", arg(conflicts_with = "output_url"))]
+    exec_per_media: Option<String>,
+
+    /// Run this shell command for each slide as soon as it's read, {json} replaced with the
+    /// slide's entry in the index
+    #[arg(long, value_name = "COMMAND")]
+    exec_per_slide: Option<String>,
+
+    /// Run at most this many --exec-per-media and --exec-per-slide commands at once
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    exec_jobs: u32,
+
+    /// Fail when the output directory already holds an extraction or an output file exists
+    #[arg(long)]
+    error_if_exists: bool,
+
+    /// Skip the extraction when the input and command line are the same as for the last
+    /// one into the output directory
+    #[arg(long, conflicts_with_all = ["output_zip", "output_tar", "dry_run"])]
+    incremental: bool,
+
+    /// Write an empty .done file into the output directory once the index and media are all
+    /// in place, removing the one of an earlier extraction first
+    #[arg(long, conflicts_with_all = ["output_zip", "output_tar"])]
+    #[cfg_attr(feature = "s3", arg(conflicts_with = "output_url"))]
+    done_marker: bool,
+
+    /// What becomes of the files written by an extraction that fails midway: kept, removed,
+    /// or moved into the output directory's name with .failed appended, e.g. out.failed
+    #[arg(long, value_enum, default_value_t = OnFailure::Keep, conflicts_with_all = ["output_zip", "output_tar"])]
+    #[cfg_attr(feature = "s3", arg(conflicts_with = "output_url"))]
+    on_failure: OnFailure,
+
+    /// Refuse archives whose entries expand to more than this many bytes in total
+    #[arg(long, default_value_t = limits::DEFAULT_MAX_UNCOMPRESSED_SIZE)]
+    max_uncompressed_size: u64,
+
+    /// Refuse archives with more entries than this
+    #[arg(long, default_value_t = limits::DEFAULT_MAX_ENTRIES)]
+    max_entries: usize,
+
+    /// Skip media files larger than this many bytes
+    #[arg(long, default_value_t = limits::DEFAULT_MAX_MEDIA_SIZE)]
+    max_media_size: u64,
+
+    /// Give up on a presentation taking longer than this to extract, e.g. 120s, 2m or 1h,
+    /// failing it like any other in a batch
+    #[arg(long, value_parser = limits::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Record the time spent per phase and per part in the index
+    #[arg(long)]
+    profile: bool,
+
+    /// Recover what can be read from an archive too damaged to open, the index then
+    /// lists the recovered entries under partial
+    #[arg(long)]
+    salvage: bool,
+
+    /// Print what would be extracted without writing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print progress as JSON lines on stderr: started, slide_done, media_exported and
+    /// finished with counts
+    #[arg(long)]
+    events: bool,
 }
 
-#[derive(Debug, Serialize)]
-struct Res<'a> {
-    doc_title: &'a str,
-    pages: PageRes,
+#[derive(Subcommand)]
+enum Command {
+    /// Extract the media, text and notes of presentations, the default without a subcommand
+    Extract(Box<ExtractArgs>),
+    /// Search slide and notes text without extracting
+    Search(search::SearchArgs),
+    /// Compare the slides of two presentations or index files
+    Diff(diff::DiffArgs),
+    /// Catalog the decks of several index.json or catalog.json files in one, e.g. of sharded
+    /// batch runs
+    Merge(merge::MergeArgs),
+    /// Print the JSON Schema of index.json
+    Schema,
+    /// List the slides and media of a presentation without extracting
+    List(list::ListArgs),
+    /// Print the document properties and part counts of a presentation
+    Meta(meta::MetaArgs),
+    /// Check a presentation for dangling relationships, missing media, malformed XML and
+    /// parts without a content type
+    Validate(validate::ValidateArgs),
+    /// Check that the media files an index lists are in the output directory with the sizes
+    /// and checksums it has for them
+    Verify(verify::VerifyArgs),
+    /// Time repeated extractions of a presentation, per run and per phase
+    Bench(bench::BenchArgs),
+    /// Serve extractions over http, POST a presentation to /extract
+    Serve(serve::ServeArgs),
 }
 
 fn main() {
-    env_logger::init();
-    let args = Args::parse();
-
-    let mut res = Res {
-        doc_title: Path::new(&args.input_file)
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap(),
-        pages: PageRes {
-            slides: HashMap::new(),
-            masters: HashMap::new(),
-        },
-    };
-
-    let archivef = fs::File::open(Path::new(&args.input_file)).expect("failed to open input file");
-    let freader = std::io::BufReader::new(archivef);
-    let mut archive = zip::ZipArchive::new(freader).expect("failed to open archive");
-
-    for i in 0..archive.len() {
-        let mut file: ZipFile = archive.by_index(i).unwrap();
-        if file.is_dir() {
-            continue;
+    let matches = Cli::command().get_matches();
+    let Cli {
+        command,
+        global,
+        extract: args,
+    } = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // the global flags may come before a subcommand, the extract flags may not
+    if let Some((name, _)) = matches.subcommand() {
+        let extract = ExtractArgs::augment_args(clap::Command::new("extract"));
+        let given = extract
+            .get_arguments()
+            .map(|arg| arg.get_id())
+            .find(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine));
+        if let Some(id) = given {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "the subcommand '{}' cannot be used with --{}",
+                        name,
+                        id.as_str().replace('_', "-")
+                    ),
+                )
+                .exit();
         }
+    }
+    if let Err(e) = logging::init(
+        global.log_file.as_deref(),
+        global.log_max_size,
+        global.log_keep,
+        global.log_format,
+    ) {
+        eprintln!("failed to open log file, error: {}", e);
+        process::exit(2);
+    }
 
-        let fname = file.name().to_owned();
-        if fname.starts_with(DIR_MEDIA) {
-            match export_media(&Path::new(&args.output_dir), &mut file) {
-                Ok(()) => {
-                    trace!("exported media {}", fname)
-                }
+    let (args, matches) = match command {
+        Some(Command::Extract(args)) => (*args, matches.subcommand_matches("extract").unwrap()),
+        Some(Command::Search(search_args)) => match search::run(&search_args) {
+            Ok(found) => process::exit(if found { 0 } else { 1 }),
+            Err(e) => {
+                error!("failed to search, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Diff(diff_args)) => match diff::run(&diff_args) {
+            Ok(changed) => process::exit(if changed { 1 } else { 0 }),
+            Err(e) => {
+                error!("failed to diff, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Merge(merge_args)) => match merge::run(&merge_args) {
+            Ok(()) => process::exit(0),
+            Err(e) => {
+                error!("failed to merge, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Schema) => match schema::run() {
+            Ok(()) => process::exit(0),
+            Err(e) => {
+                error!("failed to print schema, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::List(list_args)) => match list::run(&list_args) {
+            Ok(()) => process::exit(0),
+            Err(e) => {
+                error!("failed to list, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Meta(meta_args)) => match meta::run(&meta_args) {
+            Ok(()) => process::exit(0),
+            Err(e) => {
+                error!("failed to read properties, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Validate(validate_args)) => match validate::run(&validate_args) {
+            Ok(issues) => process::exit(if issues { 1 } else { 0 }),
+            Err(e) => {
+                error!("failed to validate, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Verify(verify_args)) => match verify::run(&verify_args) {
+            Ok(issues) => process::exit(if issues { 1 } else { 0 }),
+            Err(e) => {
+                error!("failed to verify, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Bench(bench_args)) => match bench::run(&bench_args) {
+            Ok(()) => process::exit(0),
+            Err(e) => {
+                error!("failed to benchmark, error: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Serve(serve_args)) => {
+            let config = match config::load(global.config.as_deref()) {
+                Ok(config) => config,
                 Err(e) => {
-                    error!("failed to export media: {}, error: {}", fname, e)
+                    error!("failed to load config, error: {}", e);
+                    process::exit(2);
                 }
             };
-        } else if fname.starts_with(DIR_SLIDES_RELS) {
-            match rels(file) {
-                Ok((page_no, rels)) => {
-                    trace!("got page {:?}, rels: {:?}", page_no, rels);
-                    let page_res = res
-                        .pages
-                        .slides
-                        .entry(page_no)
-                        .or_insert_with(|| SingleRes {
-                            page_no,
-                            slide_master: false,
-                            images: Vec::new(),
-                            texts: Vec::new(),
-                        });
-                    page_res.images = rels.values().cloned().collect();
-                }
+            match serve::run(&serve_args, config) {
+                Ok(()) => process::exit(0),
                 Err(e) => {
-                    error!("failed to get rels, error: {}", e)
+                    error!("failed to serve, error: {}", e);
+                    process::exit(2);
                 }
             }
-        } else if fname.starts_with(DIR_SLIDES) {
-            trace!("file {:?} is slide rels", fname);
-            let page_res = slide(file);
-            if page_res.is_err() {
-                error!("failed to get slide, error: {}", page_res.unwrap_err());
+        }
+        None => (args, &matches),
+    };
+    extract_main(args, matches, global.config.as_deref());
+}
+
+// the extract subcommand, or the bare flags without one
+fn extract_main(mut args: ExtractArgs, matches: &ArgMatches, config: Option<&str>) {
+    match config::load(config) {
+        Ok(config) => config.apply(&mut args, matches),
+        Err(e) => {
+            error!("failed to load config, error: {}", e);
+            process::exit(2);
+        }
+    }
+    #[cfg(feature = "http")]
+    if let Some(input_url) = &args.input_url {
+        let input = http::display(input_url);
+        logging::set_file(Some(input));
+        if let Err(e) = extract(&args, input, None, Path::new(&args.output_dir)) {
+            error!("failed to extract {}, error: {}", input, e);
+            process::exit(2);
+        }
+        return;
+    }
+    if let Some(files_from) = &args.files_from {
+        match read_files_from(files_from, args.null) {
+            Ok(input_files) => args.input_file.extend(input_files),
+            Err(e) => {
+                error!("failed to read {}, error: {}", files_from, e);
+                process::exit(2);
+            }
+        }
+        if args.input_file.is_empty() {
+            error!("no input files in {}", files_from);
+            process::exit(2);
+        }
+    }
+    // clap requires an input file when no subcommand is given
+    let batch = args.container || args.duplicate_slides;
+    if let ([input_file], false) = (args.input_file.as_slice(), batch) {
+        logging::set_file(Some(input_file));
+        if let Err(e) = extract(&args, input_file, None, Path::new(&args.output_dir)) {
+            error!("failed to extract {}, error: {}", input_file, e);
+            process::exit(2);
+        }
+        return;
+    }
+    match extract_batch(&args) {
+        Ok(true) => {}
+        Ok(false) => process::exit(2),
+        Err(e) => {
+            error!("failed to extract, error: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+// create the directory the output goes into, output_dir or the parent of the
+// archive, or make sure it's there
+fn output_parent(args: &ExtractArgs, output_dir: &Path) -> Result<(), ExportMediaError> {
+    let dir = match (&args.output_zip, &args.output_tar) {
+        (Some(path), _) => Path::new(path).parent(),
+        (_, Some(path)) if path != output::STDOUT => Path::new(path).parent(),
+        (_, Some(_)) => None,
+        _ => Some(output_dir),
+    };
+    #[cfg(feature = "s3")]
+    let dir = dir.filter(|_| args.output_url.is_none());
+    let dir = match dir.filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => dir,
+        None => return Ok(()),
+    };
+    if args.create_dirs {
+        fs::create_dir_all(dir)?;
+    } else if !dir.is_dir() {
+        return Err(format!("output directory {} does not exist", dir.display()).into());
+    }
+    Ok(())
+}
+
+// the paths listed in path, or stdin for -, a line each or separated by NULs,
+// blank ones skipped
+fn read_files_from(path: &str, null: bool) -> io::Result<Vec<String>> {
+    let data = if path == "-" {
+        let mut data = Vec::new();
+        io::stdin().lock().read_to_end(&mut data)?;
+        data
+    } else {
+        fs::read(path)?
+    };
+    let separator = if null { b'\0' } else { b'\n' };
+    data.split(|b| *b == separator)
+        .map(|line| {
+            let line = String::from_utf8(line.to_vec())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "path is not UTF-8"))?;
+            // a file written on Windows ends its lines in CRLF
+            Ok(if null {
+                line
+            } else {
+                line.trim_end_matches('\r').to_owned()
+            })
+        })
+        .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+        .collect()
+}
+
+// extract several presentations, or those in containers, into a directory
+// each and catalog them, returns whether all of them were extracted
+fn extract_batch(args: &ExtractArgs) -> Result<bool, ExportMediaError> {
+    let start = Instant::now();
+    let warnings = logging::warnings();
+    if args.output_zip.is_some() || args.output_tar.is_some() {
+        return Err(String::from("several input files need an output directory").into());
+    }
+    #[cfg(feature = "s3")]
+    if args.output_url.is_some() {
+        return Err(String::from("several input files need an output directory").into());
+    }
+    if !args.dry_run {
+        output_parent(args, Path::new(&args.output_dir))?;
+    }
+    let mut batch = Batch {
+        output_dir: Path::new(&args.output_dir),
+        decks: Vec::new(),
+        dirs: HashSet::new(),
+        ok: true,
+    };
+    for input_file in &args.input_file {
+        if !args.container {
+            batch.extract(args, input_file, input_file, None);
+            continue;
+        }
+        let mut container = match Container::open(input_file) {
+            Ok(container) => container,
+            Err(e) => {
+                error!("failed to open container {}, error: {}", input_file, e);
+                batch.ok = false;
                 continue;
             }
-            let page_res = page_res.unwrap();
-            trace!(
-                "got page {:?}, texts: {:?}",
-                page_res.page_no,
-                page_res.texts
-            );
-            let single_res =
-                res.pages
-                    .slides
-                    .entry(page_res.page_no)
-                    .or_insert_with(|| SingleRes {
-                        page_no: (&page_res).page_no,
-                        slide_master: false,
-                        images: Vec::new(),
-                        texts: (&page_res).texts.clone(),
-                    });
-            single_res.texts = page_res.texts.clone();
-        } else if fname.starts_with(MASTER_RELS_DIR) {
-            match rels(file) {
-                Ok((page_no, rels)) => {
-                    trace!("got page {:?}, rels: {:?}", page_no, rels);
-                    let page_res = res
-                        .pages
-                        .masters
-                        .entry(page_no)
-                        .or_insert_with(|| SingleRes {
-                            page_no,
-                            slide_master: true,
-                            images: Vec::new(),
-                            texts: Vec::new(),
-                        });
-                    page_res.images = rels.values().cloned().collect();
-                }
+        };
+        let presentations = container.presentations();
+        if presentations.is_empty() {
+            warn!("no presentations in {}", input_file);
+        }
+        for name in presentations {
+            let source = container.source(&name);
+            match container.read(&name, args.max_uncompressed_size) {
+                Ok(data) => batch.extract(args, &source, &name, Some(data)),
                 Err(e) => {
-                    error!("failed to get rels, error: {}", e)
+                    error!("failed to read {}, error: {}", source, e);
+                    batch.ok = false;
+                    let deck = catalog::deck(&source, "", batch.output_dir, Some(e.to_string()));
+                    batch.decks.push(deck);
                 }
             }
         }
     }
-    debug!("res: {:?}", res);
-    let j = serde_json::to_string_pretty(&res).unwrap();
-    // write j to {output_dir}/{INDEX_FILE}
-    fs::write(Path::new(&args.output_dir).join(INDEX_FILE), j).unwrap();
+    if !args.dry_run {
+        let failed = batch
+            .decks
+            .iter()
+            .filter(|deck| deck.error.is_some())
+            .count();
+        info!(
+            target: logging::SUMMARY,
+            "extracted {} of {} presentations, {} warnings in {:.1?}",
+            batch.decks.len() - failed,
+            batch.decks.len(),
+            logging::warnings() - warnings,
+            start.elapsed()
+        );
+        if args.duplicate_slides {
+            duplicates::write(batch.output_dir, &batch.decks)?;
+        }
+        if let Some(n) = args.keywords {
+            catalog::keywords(&mut batch.decks, n);
+        }
+        catalog::write(&batch.output_dir.join(catalog::CATALOG_FILE), batch.decks)?;
+    }
+    Ok(batch.ok)
 }
 
-#[derive(Debug)]
-enum ExportMediaError {
-    Io(std::io::Error),
-    Parse(xmltree::ParseError, String),
-    Custom(String),
+// the presentations of a batch extracted so far
+struct Batch<'a> {
+    output_dir: &'a Path,
+    decks: Vec<catalog::Deck>,
+    // the directories taken, a presentation going into one named after it
+    dirs: HashSet<String>,
+    ok: bool,
 }
 
-impl fmt::Display for ExportMediaError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ExportMediaError::Io(err) => write!(f, "IO error: {}", err),
-            ExportMediaError::Parse(err, fname) => write!(f, "Parse error: {} in {}", err, fname),
-            ExportMediaError::Custom(err) => write!(f, "Custom error: {}", err),
+impl Batch<'_> {
+    // extract source, the file path or else data read from a container,
+    // into a directory named after path
+    fn extract(&mut self, args: &ExtractArgs, source: &str, path: &str, data: Option<Vec<u8>>) {
+        let stem = Path::new(path)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let mut dir = stem.clone();
+        let mut n = 1;
+        while !self.dirs.insert(dir.clone()) {
+            n += 1;
+            dir = format!("{}_{}", stem, n);
+        }
+        let deck_dir = self.output_dir.join(&dir);
+        logging::set_file(Some(source));
+        let res = if args.dry_run {
+            extract(args, source, data, &deck_dir)
+        } else {
+            fs::create_dir_all(&deck_dir)
+                .map_err(ExportMediaError::from)
+                .and_then(|_| extract(args, source, data, &deck_dir))
+        };
+        if let Err(e) = &res {
+            error!("failed to extract {}, error: {}", source, e);
+            self.ok = false;
         }
+        logging::set_file(None);
+        self.decks.push(catalog::deck(
+            source,
+            &dir,
+            self.output_dir,
+            res.err().map(|e| e.to_string()),
+        ));
     }
 }
 
-impl std::error::Error for ExportMediaError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match self {
-            ExportMediaError::Io(err) => Some(err),
-            ExportMediaError::Parse(err, _) => Some(err),
-            ExportMediaError::Custom(_) => None,
-        }
+// the extraction options the args ask for
+fn options(args: &ExtractArgs) -> Options {
+    Options {
+        text: text::TextOptions {
+            normalize: args.normalize,
+            trim: args.trim_text,
+        },
+        fonts: args.fonts,
+        vba: args.vba,
+        stats: args.stats,
+        paragraphs: args.paragraphs,
+        shapes: args.shapes,
+        equations: args.equations,
+        mathml: args.mathml,
+        resolve_inherited: args.resolve_inherited,
+        keep_paths: args.keep_paths,
+        sanitize_names: args.sanitize_names,
+        fix_extensions: args.fix_extensions,
+        strip_metadata: args.strip_metadata,
+        exif_report: args.exif_report,
+        anonymize: args.anonymize,
+        dimensions: args.image_map.is_some(),
+        // the bulk documents carry the notes
+        notes: args.notes || args.format == IndexFormat::EsBulk,
+        srt: args.srt,
+        reveal: args.reveal,
+        shapes_svg: args.shapes_svg,
+        chart_data: args.chart_data,
+        keywords: args.keywords,
+        bundle: args.bundle,
+        chunks: args.chunks,
+        max_tokens: args.max_tokens,
+        graph: args.graph,
+        layout: args.layout,
+        masters: !args.no_masters,
+        exclude_prompt_text: args.exclude_prompt_text,
+        #[cfg(feature = "http")]
+        fetch_external: args.fetch_external,
+        #[cfg(feature = "media-probe")]
+        probe_media: args.probe_media,
+        #[cfg(feature = "resize")]
+        max_dimension: args.max_dimension,
+        #[cfg(feature = "contact-sheet")]
+        contact_sheet: args.contact_sheet,
+        #[cfg(feature = "similar")]
+        dedupe_similar: args.dedupe_similar,
+        #[cfg(feature = "plugins")]
+        plugins: args.plugins.clone(),
+        grep: args.grep.clone(),
+        redact: args.redact.clone(),
+        exclude_placeholders: args.exclude_placeholders.clone(),
+        #[cfg(feature = "lang-detect")]
+        detect_lang: args.detect_lang,
+        md5: args.md5,
+        name_template: args.name_template.clone(),
+        max_uncompressed_size: args.max_uncompressed_size,
+        max_entries: args.max_entries,
+        max_media_size: args.max_media_size,
+        // from when the options are made, as the presentation is opened
+        deadline: limits::Deadline::new(args.timeout),
+        salvage: args.salvage,
+        profile: args.profile,
+        dry_run: args.dry_run,
+        enable: args.enable.clone(),
+        disable: args.disable.clone(),
     }
 }
 
-impl From<std::io::Error> for ExportMediaError {
-    fn from(error: std::io::Error) -> Self {
-        ExportMediaError::Io(error)
+// extract input_file, or data when it was read already from a container,
+// into output_dir or into the zip, tar or upload the args ask for, reporting
+// progress with events
+fn extract(
+    args: &ExtractArgs,
+    input_file: &str,
+    data: Option<Vec<u8>>,
+    output_dir: &Path,
+) -> Result<(), ExportMediaError> {
+    let mut events = args.events.then(|| Events::new(input_file));
+    let mut hooks = Hooks::new(args, output_dir);
+    let res = extract_visiting(
+        args,
+        input_file,
+        data,
+        output_dir,
+        &mut (&mut events, &mut hooks),
+    );
+    // the commands already running are waited for whatever the outcome
+    let res = res.and(hooks.finish());
+    if let Some(events) = &events {
+        events.finished(res.as_ref().err());
     }
+    res
 }
 
-impl From<String> for ExportMediaError {
-    fn from(error: String) -> Self {
-        ExportMediaError::Custom(error)
+fn extract_visiting(
+    args: &ExtractArgs,
+    input_file: &str,
+    data: Option<Vec<u8>>,
+    output_dir: &Path,
+    visitor: &mut dyn ExtractVisitor,
+) -> Result<(), ExportMediaError> {
+    let mut phases = logging::Phases::start("open");
+    let options = options(args);
+
+    // the manifest of this run and that of the last one, when incremental
+    let mut manifest = None;
+    if args.incremental {
+        let current = Manifest::new(input_file)?;
+        let previous = Manifest::load(output_dir);
+        if previous
+            .as_ref()
+            .is_some_and(|previous| current.unchanged(previous))
+        {
+            info!("{} is unchanged, skipping", input_file);
+            return Ok(());
+        }
+        manifest = Some((current, previous));
     }
-}
 
-fn export_media(output: &Path, f: &mut ZipFile) -> Result<(), ExportMediaError> {
-    // get the filename from f
-    let filename = Path::new(f.name()).file_name().unwrap();
-    let outfilename = output.join(filename);
-    trace!("out filename: {:?}", outfilename);
-    // write contents of f to outfilename
-    let mut outfile = fs::File::create(outfilename).map_err(|e| e.to_string())?;
+    // an object store is read from as needed, not to be downloaded whole
+    #[cfg(any(feature = "s3", feature = "gcs", feature = "azure"))]
+    let remote_url = args
+        .input_url
+        .as_deref()
+        .filter(|url| remote::is_remote(url));
+    // an input url is downloaded into memory
+    #[cfg(feature = "http")]
+    let data = match (data, &args.input_url) {
+        #[cfg(any(feature = "s3", feature = "gcs", feature = "azure"))]
+        (None, Some(_)) if remote_url.is_some() => None,
+        (None, Some(input_url)) => Some(http::download(
+            input_url,
+            &args.input_header,
+            args.max_download_size,
+        )?),
+        (data, _) => data,
+    };
+    let archivef: Box<dyn ReadSeek> = match data {
+        Some(data) => Box::new(io::Cursor::new(data)),
+        #[cfg(any(feature = "s3", feature = "gcs", feature = "azure"))]
+        None if remote_url.is_some() => Box::new(remote::Remote::open(remote_url.unwrap())?),
+        // a package stored unzipped is packed in memory
+        None if Path::new(input_file).is_dir() => Box::new(io::Cursor::new(unpacked::pack(
+            Path::new(input_file),
+            args.max_uncompressed_size,
+        )?)),
+        None => Box::new(io::BufReader::new(fs::File::open(input_file)?)),
+    };
+    // hashing a huge file counts against the timeout too
+    let mut archivef = limits::Timed::new(archivef, options.deadline);
+    // hashing an object in an object store would download it whole
+    #[cfg(any(feature = "s3", feature = "gcs", feature = "azure"))]
+    let hash = remote_url.is_none();
+    #[cfg(not(any(feature = "s3", feature = "gcs", feature = "azure")))]
+    let hash = true;
+    let source = match hash.then(|| checksum::checksum(&mut archivef, args.md5)) {
+        None => None,
+        Some(Ok(source)) => Some(source),
+        Some(Err(e)) => {
+            // reading it again to extract would time out as well
+            options.deadline.check()?;
+            error!("failed to hash input file, error: {}", e);
+            None
+        }
+    };
+    if let (Some((current, previous)), Some(source)) = (manifest.as_mut(), &source) {
+        current.set_checksum(source);
+        if previous
+            .as_ref()
+            .is_some_and(|previous| current.same_content(previous))
+        {
+            if let Err(e) = current.save(output_dir) {
+                error!("failed to write manifest, error: {}", e)
+            }
+            return Ok(());
+        }
+    }
+    archivef.seek(io::SeekFrom::Start(0))?;
 
-    match io::copy(f, &mut outfile) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e.into()),
+    let overwrite = if args.force {
+        Overwrite::Force
+    } else if args.skip_existing {
+        Overwrite::Skip
+    } else if args.error_if_exists {
+        Overwrite::Error
+    } else {
+        Overwrite::Warn
+    };
+    let mut output = match (&args.output_zip, &args.output_tar) {
+        (Some(output_zip), _) => Output::zip(Path::new(output_zip), overwrite),
+        (_, Some(output_tar)) => Output::tar(output_tar, overwrite),
+        _ => Output::new(output_dir, overwrite),
+    };
+    #[cfg(feature = "s3")]
+    if let Some(output_url) = &args.output_url {
+        output = Output::s3(output_url, args.upload_concurrency, overwrite)?;
+    }
+    if !args.dry_run {
+        output_parent(args, output_dir)?;
+        output.check(INDEX_FILE)?;
+        // the directory is incomplete again until the marker is back
+        if args.done_marker {
+            match fs::remove_file(output_dir.join(DONE_FILE)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
     }
-}
 
-fn slide(mut f: ZipFile) -> Result<SingleRes, String> {
-    let mut res = SingleRes {
-        page_no: 0,
-        slide_master: false,
-        images: Vec::new(),
-        texts: Vec::new(),
+    // the file name, or the name of where a directory such as . resolves to,
+    // empty for a url or stdin without one
+    let doc_title = Path::new(input_file)
+        .file_name()
+        .map(OsStr::to_owned)
+        .or_else(|| {
+            fs::canonicalize(input_file)
+                .ok()?
+                .file_name()
+                .map(OsStr::to_owned)
+        })
+        .unwrap_or_default();
+    let doc_title = doc_title.to_string_lossy();
+    // the parts that failed, for errors.json
+    let mut part_errors: Vec<PartError> = Vec::new();
+    let res = ppt_img_extract::extract_archive(
+        archivef,
+        &doc_title,
+        &options,
+        &mut output,
+        &mut phases,
+        &mut (visitor, &mut part_errors),
+    )
+    .and_then(|Extracted { mut res, dry_media }| {
+        res.source = source;
+        if !part_errors.is_empty() && !args.dry_run {
+            let report = errors::report(input_file, &part_errors, None);
+            output.write(errors::ERRORS_FILE, report.as_bytes())?;
+        }
+        write_index(args, &mut output, &res, &dry_media)?;
+        Ok(res.summary)
+    });
+    let summary = match res {
+        Ok(summary) => summary,
+        Err(e) => {
+            match output.discard(args.on_failure) {
+                Ok(Some(dir)) if !args.dry_run => {
+                    // what failed is kept with what is left of the output
+                    let report = errors::report(input_file, &part_errors, Some(&e.to_string()));
+                    if let Err(e) = fs::create_dir_all(&dir).and_then(|_| {
+                        output::write_atomically(&dir.join(errors::ERRORS_FILE), report.as_bytes())
+                    }) {
+                        error!("failed to write {}, error: {}", errors::ERRORS_FILE, e);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => error!("failed to clean up the output, error: {}", e),
+            }
+            return Err(e);
+        }
     };
-    let mut content: String = String::new();
-    f.read_to_string(&mut content).map_err(|e| e.to_string())?;
-    for cap in RE_TEXT.captures_iter(&content) {
-        if let Some(text) = cap.get(1) {
-            res.texts.push(text.as_str().to_owned());
+    output.finish()?;
+    if let Some((current, _)) = manifest {
+        if let Err(e) = current.save(output_dir) {
+            error!("failed to write manifest, error: {}", e)
         }
     }
-    let fname = f.name();
-    res.page_no = page_no(fname)?;
-    debug!("page res: {:?}", res);
-    Ok(res)
+    if let (Some(summary), false) = (summary, args.dry_run) {
+        info!(target: logging::SUMMARY, "extracted {}: {}", input_file, summary);
+    }
+    Ok(())
 }
 
-fn rels(f: zip::read::ZipFile) -> Result<(u32, HashMap<String, String>), ExportMediaError> {
-    let fname = f.name().to_owned();
-    let el = xmltree::Element::parse(f).map_err(|e| ExportMediaError::Parse(e, fname.clone()))?;
-    let image_rel_nodes = el.children.into_iter().filter(|node: &xmltree::XMLNode| {
-        let el = node.as_element().unwrap();
-        el.name == "Relationship"
-            && el.attributes.get("Type") == Some(&ATTR_REL_TYPE_IMAGE.to_string())
-    });
-    let mut res = HashMap::new();
-    for image_rel_node in image_rel_nodes {
-        let image_rel_el = image_rel_node.as_element().unwrap();
-        let rel_image_path = image_rel_el.attributes.get("Target").unwrap();
-        res.insert(
-            image_rel_el.attributes.get("Id").unwrap().to_owned(),
-            Path::new(rel_image_path)
-                .file_name()
-                .unwrap()
-                .to_string_lossy()
-                .into_owned(),
-        );
+// write what goes with the extraction into output, the index last, or list
+// it all for a dry run
+fn write_index(
+    args: &ExtractArgs,
+    output: &mut Output,
+    res: &Res,
+    dry_media: &[(String, u64)],
+) -> Result<(), ExportMediaError> {
+    if args.dry_run {
+        print_dry_run(res, args, output, dry_media);
+        return Ok(());
+    }
+    // before the index, which the baseline may be the last of
+    if let Some(baseline) = &args.baseline {
+        diff::baseline(output, baseline, res)?;
+    }
+    if args.split_index.is_some() {
+        split::write(output, res)?;
     }
-    let page_no = page_no(&fname)?;
-    Ok((page_no, res))
+    if let Some(image_map) = &args.image_map {
+        image_map::write(output, res, image_map)?;
+    }
+    if args.format == IndexFormat::EsBulk {
+        bulk::write(output, res, args.es_index.as_deref())?;
+    } else if args.split_index != Some(split::SplitIndex::Only) {
+        let j = serde_json::to_string_pretty(res).unwrap();
+        // write j to {output_dir}/{INDEX_FILE}
+        output.write(INDEX_FILE, j.as_bytes())?;
+    }
+    if args.done_marker {
+        output.write(DONE_FILE, b"")?;
+    }
+    Ok(())
 }
 
-// get page no from filename
-fn page_no(fname: &str) -> Result<u32, String> {
-    if let Some(matched) = RE_PAGE_NO.captures(fname) {
-        if let Some(page_no) = matched.get(2) {
-            Ok(page_no.as_str().parse::<u32>().unwrap())
-        } else {
-            Err("Can't find valid page no".into())
+// list the slides and the files an extraction would write
+fn print_dry_run(res: &Res, args: &ExtractArgs, output: &Output, media: &[(String, u64)]) {
+    for single_res in res.pages.slides.values() {
+        println!(
+            "slide {} ({}): {} texts, {} images",
+            single_res.page_no,
+            single_res.part_name,
+            single_res.texts.len(),
+            single_res.images.len()
+        );
+    }
+    let mut total = 0;
+    for (filename, size) in media {
+        println!("{} ({} bytes)", output.display(filename), size);
+        total += size;
+    }
+    for font_res in &res.fonts {
+        for filename in font_res.files.values() {
+            println!(
+                "{}",
+                output.display(&format!("{}/{}", fonts::DIR_FONTS, filename))
+            );
         }
-    } else {
-        Err("Can't find valid page no".into())
     }
+    if args.vba {
+        if let Some(vba_res) = &res.vba {
+            let filename = Path::new(&vba_res.part_name).file_name().unwrap();
+            println!("{}", output.display(&filename.to_string_lossy()));
+        }
+    }
+    if args.split_index.is_some() {
+        for page_no in res.pages.slides.keys() {
+            println!("{}", output.display(&split::slide_file(*page_no)));
+        }
+    }
+    if let Some(image_map) = &args.image_map {
+        println!("{}", output.display(image_map));
+    }
+    if args.bundle {
+        for page_no in res.pages.slides.keys() {
+            let dir = bundle::bundle_dir(*page_no);
+            for name in [bundle::TEXT_FILE, bundle::PREVIEW_FILE] {
+                println!("{}", output.display(&format!("{}/{}", dir, name)));
+            }
+        }
+    }
+    if args.baseline.is_some() {
+        println!("{}", output.display(diff::CHANGES_FILE));
+    }
+    if args.format == IndexFormat::EsBulk {
+        println!("{}", output.display(bulk::BULK_FILE));
+    } else if args.split_index != Some(split::SplitIndex::Only) {
+        println!("{}", output.display(INDEX_FILE));
+    }
+    println!("{} media files, {} bytes", media.len(), total);
 }
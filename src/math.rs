@@ -0,0 +1,591 @@
+use roxmltree::Node;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::xml::NodeExt;
+
+const NS_MATH: &str = "http://schemas.openxmlformats.org/officeDocument/2006/math";
+const NS_MATHML: &str = "http://www.w3.org/1998/Math/MathML";
+
+// function names LaTeX has an operator for
+const FUNCTIONS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg", "det", "dim",
+    "exp", "gcd", "hom", "inf", "ker", "lg", "lim", "ln", "log", "max", "min", "Pr", "sec", "sin",
+    "sinh", "sup", "tan", "tanh",
+];
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Equation {
+    pub latex: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mathml: Option<String>,
+}
+
+// get the OMML equations in the part, m:oMath elements, in document order
+pub fn equations(el: Node) -> Vec<Equation> {
+    let mut res = Vec::new();
+    walk_equations(el, &mut res);
+    res
+}
+
+fn walk_equations(el: Node, res: &mut Vec<Equation>) {
+    for child in children(el) {
+        if is_math(child, "oMath") {
+            let latex = latex(child).trim().to_owned();
+            if !latex.is_empty() {
+                res.push(Equation {
+                    latex,
+                    mathml: Some(format!(
+                        "<math xmlns=\"{}\">{}</math>",
+                        NS_MATHML,
+                        mathml(child)
+                    )),
+                });
+            }
+            continue;
+        }
+        walk_equations(child, res);
+    }
+}
+
+fn latex(el: Node) -> String {
+    let arg = |name: &str| el.child(NS_MATH, name).map(latex).unwrap_or_default();
+    let prop = |pr: &str, name: &str| property(el, pr, name);
+    match math_name(el) {
+        "r" => {
+            let text = run_text(el);
+            match run_style(el) {
+                RunStyle::Text => format!("\\text{{{}}}", text),
+                RunStyle::Upright if is_word(&text) => format!("\\mathrm{{{}}}", text),
+                _ => text.chars().map(latex_char).collect(),
+            }
+        }
+        "f" => match prop("fPr", "type").as_deref() {
+            Some("lin") => format!("{{{}}}/{{{}}}", arg("num"), arg("den")),
+            Some("noBar") => format!(
+                "\\genfrac{{}}{{}}{{0pt}}{{}}{{{}}}{{{}}}",
+                arg("num"),
+                arg("den")
+            ),
+            _ => format!("\\frac{{{}}}{{{}}}", arg("num"), arg("den")),
+        },
+        "sSup" => format!("{{{}}}^{{{}}}", arg("e"), arg("sup")),
+        "sSub" => format!("{{{}}}_{{{}}}", arg("e"), arg("sub")),
+        "sSubSup" => format!("{{{}}}_{{{}}}^{{{}}}", arg("e"), arg("sub"), arg("sup")),
+        "sPre" => format!("{{}}_{{{}}}^{{{}}}{{{}}}", arg("sub"), arg("sup"), arg("e")),
+        "rad" => {
+            let deg = arg("deg");
+            if deg.is_empty() || is_on(prop("radPr", "degHide")) {
+                format!("\\sqrt{{{}}}", arg("e"))
+            } else {
+                format!("\\sqrt[{}]{{{}}}", deg, arg("e"))
+            }
+        }
+        "nary" => {
+            let chr = prop("naryPr", "chr").unwrap_or_else(|| String::from("\u{222B}"));
+            let mut res = match chr.as_str() {
+                "\u{2211}" => String::from("\\sum"),
+                "\u{220F}" => String::from("\\prod"),
+                "\u{2210}" => String::from("\\coprod"),
+                "\u{222B}" => String::from("\\int"),
+                "\u{222C}" => String::from("\\iint"),
+                "\u{222D}" => String::from("\\iiint"),
+                "\u{222E}" => String::from("\\oint"),
+                "\u{22C0}" => String::from("\\bigwedge"),
+                "\u{22C1}" => String::from("\\bigvee"),
+                "\u{22C2}" => String::from("\\bigcap"),
+                "\u{22C3}" => String::from("\\bigcup"),
+                chr => chr.chars().map(latex_char).collect(),
+            };
+            if !is_on(prop("naryPr", "subHide")) && !arg("sub").is_empty() {
+                res.push_str(&format!("_{{{}}}", arg("sub")));
+            }
+            if !is_on(prop("naryPr", "supHide")) && !arg("sup").is_empty() {
+                res.push_str(&format!("^{{{}}}", arg("sup")));
+            }
+            format!("{}{{{}}}", res, arg("e"))
+        }
+        "d" => {
+            let beg = prop("dPr", "begChr").unwrap_or_else(|| String::from("("));
+            let end = prop("dPr", "endChr").unwrap_or_else(|| String::from(")"));
+            let sep = prop("dPr", "sepChr").unwrap_or_else(|| String::from("|"));
+            let sep = if sep == "|" {
+                String::from("\\middle|")
+            } else {
+                sep.chars().map(latex_char).collect()
+            };
+            let items: Vec<_> = children(el)
+                .filter(|child| is_math(*child, "e"))
+                .map(latex)
+                .collect();
+            format!(
+                "\\left{}{}\\right{}",
+                latex_fence(&beg),
+                items.join(&sep),
+                latex_fence(&end)
+            )
+        }
+        "func" => {
+            let name = el
+                .child(NS_MATH, "fName")
+                .map(plain_text)
+                .unwrap_or_default();
+            let name = if FUNCTIONS.contains(&name.as_str()) {
+                format!("\\{}", name)
+            } else {
+                arg("fName")
+            };
+            format!("{}{{{}}}", name, arg("e"))
+        }
+        "limLow" | "limUpp" => {
+            let base = el.child(NS_MATH, "e").map(plain_text).unwrap_or_default();
+            let (script, command) = match math_name(el) {
+                "limLow" => ("_", "\\underset"),
+                _ => ("^", "\\overset"),
+            };
+            if FUNCTIONS.contains(&base.as_str()) {
+                format!("\\{}{}{{{}}}", base, script, arg("lim"))
+            } else {
+                format!("{}{{{}}}{{{}}}", command, arg("lim"), arg("e"))
+            }
+        }
+        "acc" => {
+            let chr = prop("accPr", "chr").unwrap_or_else(|| String::from("\u{0302}"));
+            let command = match chr.as_str() {
+                "\u{0300}" => "\\grave",
+                "\u{0301}" => "\\acute",
+                "\u{0303}" => "\\tilde",
+                "\u{0304}" | "\u{0305}" => "\\bar",
+                "\u{0306}" => "\\breve",
+                "\u{0307}" => "\\dot",
+                "\u{0308}" => "\\ddot",
+                "\u{030C}" => "\\check",
+                "\u{20D7}" => "\\vec",
+                _ => "\\hat",
+            };
+            format!("{}{{{}}}", command, arg("e"))
+        }
+        "bar" => match prop("barPr", "pos").as_deref() {
+            Some("top") => format!("\\overline{{{}}}", arg("e")),
+            _ => format!("\\underline{{{}}}", arg("e")),
+        },
+        "groupChr" => match prop("groupChrPr", "pos").as_deref() {
+            Some("top") => format!("\\overbrace{{{}}}", arg("e")),
+            _ => format!("\\underbrace{{{}}}", arg("e")),
+        },
+        "m" => {
+            let rows: Vec<_> = children(el)
+                .filter(|child| is_math(*child, "mr"))
+                .map(|row| {
+                    children(row)
+                        .filter(|child| is_math(*child, "e"))
+                        .map(latex)
+                        .collect::<Vec<_>>()
+                        .join(" & ")
+                })
+                .collect();
+            format!("\\begin{{matrix}}{}\\end{{matrix}}", rows.join(" \\\\ "))
+        }
+        "eqArr" => {
+            let rows: Vec<_> = children(el)
+                .filter(|child| is_math(*child, "e"))
+                .map(latex)
+                .collect();
+            format!("\\begin{{aligned}}{}\\end{{aligned}}", rows.join(" \\\\ "))
+        }
+        "borderBox" => format!("\\boxed{{{}}}", arg("e")),
+        "phant" if is_off(prop("phantPr", "show")) => format!("\\phantom{{{}}}", arg("e")),
+        name if name.ends_with("Pr") => String::new(),
+        _ => children(el).map(latex).collect(),
+    }
+}
+
+fn mathml(el: Node) -> String {
+    let arg = |name: &str| mrow(el.child(NS_MATH, name).map(mathml).unwrap_or_default());
+    let prop = |pr: &str, name: &str| property(el, pr, name);
+    match math_name(el) {
+        "r" => {
+            let text = run_text(el);
+            match run_style(el) {
+                RunStyle::Text => format!("<mtext>{}</mtext>", escape(&text)),
+                RunStyle::Upright if is_word(&text) => {
+                    format!("<mi mathvariant=\"normal\">{}</mi>", text)
+                }
+                _ => mathml_tokens(&text),
+            }
+        }
+        "f" => match prop("fPr", "type").as_deref() {
+            Some("lin") => format!("<mrow>{}<mo>/</mo>{}</mrow>", arg("num"), arg("den")),
+            Some("noBar") => format!(
+                "<mfrac linethickness=\"0\">{}{}</mfrac>",
+                arg("num"),
+                arg("den")
+            ),
+            _ => format!("<mfrac>{}{}</mfrac>", arg("num"), arg("den")),
+        },
+        "sSup" => format!("<msup>{}{}</msup>", arg("e"), arg("sup")),
+        "sSub" => format!("<msub>{}{}</msub>", arg("e"), arg("sub")),
+        "sSubSup" => format!(
+            "<msubsup>{}{}{}</msubsup>",
+            arg("e"),
+            arg("sub"),
+            arg("sup")
+        ),
+        "sPre" => format!(
+            "<mmultiscripts>{}<mprescripts/>{}{}</mmultiscripts>",
+            arg("e"),
+            arg("sub"),
+            arg("sup")
+        ),
+        "rad" => {
+            let deg = el.child(NS_MATH, "deg").map(plain_text).unwrap_or_default();
+            if deg.is_empty() || is_on(prop("radPr", "degHide")) {
+                format!("<msqrt>{}</msqrt>", arg("e"))
+            } else {
+                format!("<mroot>{}{}</mroot>", arg("e"), arg("deg"))
+            }
+        }
+        "nary" => {
+            let chr = prop("naryPr", "chr").unwrap_or_else(|| String::from("\u{222B}"));
+            let op = format!("<mo>{}</mo>", escape(&chr));
+            let sub = (!is_on(prop("naryPr", "subHide"))).then(|| arg("sub"));
+            let sup = (!is_on(prop("naryPr", "supHide"))).then(|| arg("sup"));
+            // integrals take their limits as scripts, the others under and over
+            let limits = match prop("naryPr", "limLoc").as_deref() {
+                Some(loc) => loc == "undOvr",
+                None => !matches!(
+                    chr.as_str(),
+                    "\u{222B}" | "\u{222C}" | "\u{222D}" | "\u{222E}"
+                ),
+            };
+            let op = match (sub, sup, limits) {
+                (Some(sub), Some(sup), true) => {
+                    format!("<munderover>{}{}{}</munderover>", op, sub, sup)
+                }
+                (Some(sub), Some(sup), false) => format!("<msubsup>{}{}{}</msubsup>", op, sub, sup),
+                (Some(sub), None, true) => format!("<munder>{}{}</munder>", op, sub),
+                (Some(sub), None, false) => format!("<msub>{}{}</msub>", op, sub),
+                (None, Some(sup), true) => format!("<mover>{}{}</mover>", op, sup),
+                (None, Some(sup), false) => format!("<msup>{}{}</msup>", op, sup),
+                (None, None, _) => op,
+            };
+            format!("<mrow>{}{}</mrow>", op, arg("e"))
+        }
+        "d" => {
+            let beg = prop("dPr", "begChr").unwrap_or_else(|| String::from("("));
+            let end = prop("dPr", "endChr").unwrap_or_else(|| String::from(")"));
+            let sep = prop("dPr", "sepChr").unwrap_or_else(|| String::from("|"));
+            let items: Vec<_> = children(el)
+                .filter(|child| is_math(*child, "e"))
+                .map(|child| mrow(mathml(child)))
+                .collect();
+            let fence = |chr: &str| match chr {
+                "" => String::new(),
+                chr => format!("<mo>{}</mo>", escape(chr)),
+            };
+            format!(
+                "<mrow>{}{}{}</mrow>",
+                fence(&beg),
+                items.join(&fence(&sep)),
+                fence(&end)
+            )
+        }
+        "func" => format!(
+            "<mrow>{}<mo>&#x2061;</mo>{}</mrow>",
+            match el.child(NS_MATH, "fName").map(plain_text) {
+                Some(name) if FUNCTIONS.contains(&name.as_str()) => format!("<mi>{}</mi>", name),
+                _ => arg("fName"),
+            },
+            arg("e")
+        ),
+        "limLow" => format!("<munder>{}{}</munder>", arg("e"), arg("lim")),
+        "limUpp" => format!("<mover>{}{}</mover>", arg("e"), arg("lim")),
+        "acc" => {
+            let chr = prop("accPr", "chr").unwrap_or_else(|| String::from("\u{0302}"));
+            // the spacing forms of the combining accents
+            let chr = match chr.as_str() {
+                "\u{0300}" => "`",
+                "\u{0301}" => "\u{00B4}",
+                "\u{0302}" => "^",
+                "\u{0303}" => "~",
+                "\u{0304}" | "\u{0305}" => "\u{00AF}",
+                "\u{0306}" => "\u{02D8}",
+                "\u{0307}" => "\u{02D9}",
+                "\u{0308}" => "\u{00A8}",
+                "\u{030C}" => "\u{02C7}",
+                "\u{20D7}" => "\u{2192}",
+                chr => chr,
+            };
+            format!(
+                "<mover accent=\"true\">{}<mo>{}</mo></mover>",
+                arg("e"),
+                escape(chr)
+            )
+        }
+        "bar" => match prop("barPr", "pos").as_deref() {
+            Some("top") => format!("<mover>{}<mo>&#x203E;</mo></mover>", arg("e")),
+            _ => format!("<munder>{}<mo>_</mo></munder>", arg("e")),
+        },
+        "groupChr" => {
+            let pos = prop("groupChrPr", "pos");
+            let default = if pos.as_deref() == Some("top") {
+                "\u{23DE}"
+            } else {
+                "\u{23DF}"
+            };
+            let chr = prop("groupChrPr", "chr").unwrap_or_else(|| String::from(default));
+            match pos.as_deref() {
+                Some("top") => format!("<mover>{}<mo>{}</mo></mover>", arg("e"), escape(&chr)),
+                _ => format!("<munder>{}<mo>{}</mo></munder>", arg("e"), escape(&chr)),
+            }
+        }
+        "m" => {
+            let rows: String = children(el)
+                .filter(|child| is_math(*child, "mr"))
+                .map(|row| {
+                    let cells: String = children(row)
+                        .filter(|child| is_math(*child, "e"))
+                        .map(|cell| format!("<mtd>{}</mtd>", mathml(cell)))
+                        .collect();
+                    format!("<mtr>{}</mtr>", cells)
+                })
+                .collect();
+            format!("<mtable>{}</mtable>", rows)
+        }
+        "eqArr" => {
+            let rows: String = children(el)
+                .filter(|child| is_math(*child, "e"))
+                .map(|row| format!("<mtr><mtd>{}</mtd></mtr>", mathml(row)))
+                .collect();
+            format!("<mtable>{}</mtable>", rows)
+        }
+        "borderBox" => format!("<menclose notation=\"box\">{}</menclose>", arg("e")),
+        "phant" if is_off(prop("phantPr", "show")) => {
+            format!("<mphantom>{}</mphantom>", arg("e"))
+        }
+        name if name.ends_with("Pr") => String::new(),
+        _ => children(el).map(mathml).collect(),
+    }
+}
+
+enum RunStyle {
+    Math,
+    // m:sty m:val="p", upright like function names
+    Upright,
+    // m:nor, ordinary text
+    Text,
+}
+
+fn run_style(run: Node) -> RunStyle {
+    match run.child(NS_MATH, "rPr") {
+        Some(rpr) if rpr.child(NS_MATH, "nor").is_some() => RunStyle::Text,
+        Some(rpr)
+            if rpr
+                .child(NS_MATH, "sty")
+                .and_then(|sty| sty.attribute((NS_MATH, "val")))
+                .is_some_and(|val| val == "p") =>
+        {
+            RunStyle::Upright
+        }
+        _ => RunStyle::Math,
+    }
+}
+
+// several letters and nothing else, to be kept together
+fn is_word(text: &str) -> bool {
+    text.chars().count() > 1 && text.chars().all(|c| c.is_alphabetic())
+}
+
+// tokens for the text of a run, numbers as mn, letters as mi and the rest as mo
+fn mathml_tokens(text: &str) -> String {
+    let mut res = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            let mut number = String::from(c);
+            while let Some(&next) = chars.peek() {
+                if !next.is_ascii_digit() && next != '.' {
+                    break;
+                }
+                number.push(next);
+                chars.next();
+            }
+            res.push_str(&format!("<mn>{}</mn>", number));
+        } else if c.is_alphabetic() {
+            res.push_str(&format!("<mi>{}</mi>", c));
+        } else if !c.is_whitespace() {
+            res.push_str(&format!("<mo>{}</mo>", escape(&c.to_string())));
+        }
+    }
+    res
+}
+
+// a single element for an argument made of several
+fn mrow(content: String) -> String {
+    format!("<mrow>{}</mrow>", content)
+}
+
+fn latex_char(c: char) -> String {
+    let command = match c {
+        '\\' => "\\backslash",
+        '{' => "\\{",
+        '}' => "\\}",
+        '#' => "\\#",
+        '$' => "\\$",
+        '%' => "\\%",
+        '&' => "\\&",
+        '_' => "\\_",
+        '~' => "\\sim",
+        '\u{2212}' => "-",
+        '\u{00B1}' => "\\pm",
+        '\u{2213}' => "\\mp",
+        '\u{00D7}' => "\\times",
+        '\u{00F7}' => "\\div",
+        '\u{00B7}' | '\u{22C5}' => "\\cdot",
+        '\u{2218}' => "\\circ",
+        '\u{2264}' => "\\leq",
+        '\u{2265}' => "\\geq",
+        '\u{2260}' => "\\neq",
+        '\u{2248}' => "\\approx",
+        '\u{2261}' => "\\equiv",
+        '\u{221D}' => "\\propto",
+        '\u{223C}' => "\\sim",
+        '\u{226A}' => "\\ll",
+        '\u{226B}' => "\\gg",
+        '\u{221E}' => "\\infty",
+        '\u{2202}' => "\\partial",
+        '\u{2207}' => "\\nabla",
+        '\u{2208}' => "\\in",
+        '\u{2209}' => "\\notin",
+        '\u{2282}' => "\\subset",
+        '\u{2286}' => "\\subseteq",
+        '\u{2283}' => "\\supset",
+        '\u{2287}' => "\\supseteq",
+        '\u{222A}' => "\\cup",
+        '\u{2229}' => "\\cap",
+        '\u{2205}' => "\\emptyset",
+        '\u{2200}' => "\\forall",
+        '\u{2203}' => "\\exists",
+        '\u{00AC}' => "\\neg",
+        '\u{2227}' => "\\wedge",
+        '\u{2228}' => "\\vee",
+        '\u{2192}' => "\\rightarrow",
+        '\u{2190}' => "\\leftarrow",
+        '\u{2194}' => "\\leftrightarrow",
+        '\u{21D2}' => "\\Rightarrow",
+        '\u{21D0}' => "\\Leftarrow",
+        '\u{21D4}' => "\\Leftrightarrow",
+        '\u{21A6}' => "\\mapsto",
+        '\u{2026}' => "\\ldots",
+        '\u{22EF}' => "\\cdots",
+        '\u{2032}' => "'",
+        '\u{210F}' => "\\hbar",
+        '\u{2113}' => "\\ell",
+        '\u{211D}' => "\\mathbb{R}",
+        '\u{2124}' => "\\mathbb{Z}",
+        '\u{2115}' => "\\mathbb{N}",
+        '\u{211A}' => "\\mathbb{Q}",
+        '\u{2102}' => "\\mathbb{C}",
+        '\u{00B0}' => "^\\circ",
+        c => match greek(c) {
+            Some(name) => return format!("\\{} ", name),
+            None => return c.to_string(),
+        },
+    };
+    if command.ends_with(|c: char| c.is_ascii_alphabetic()) {
+        format!("{} ", command)
+    } else {
+        command.to_owned()
+    }
+}
+
+fn greek(c: char) -> Option<&'static str> {
+    const LOWER: [&str; 25] = [
+        "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota", "kappa",
+        "lambda", "mu", "nu", "xi", "o", "pi", "rho", "varsigma", "sigma", "tau", "upsilon", "phi",
+        "chi", "psi", "omega",
+    ];
+    const UPPER: [&str; 25] = [
+        "A", "B", "Gamma", "Delta", "E", "Z", "H", "Theta", "I", "K", "Lambda", "M", "N", "Xi",
+        "O", "Pi", "P", "", "Sigma", "T", "Upsilon", "Phi", "X", "Psi", "Omega",
+    ];
+    let name = match c {
+        '\u{03B1}'..='\u{03C9}' => LOWER[c as usize - 0x03B1],
+        '\u{0391}'..='\u{03A9}' => UPPER[c as usize - 0x0391],
+        '\u{03D5}' => "phi",
+        '\u{03F5}' => "epsilon",
+        _ => return None,
+    };
+    // the letters that look latin have no command of their own
+    (name.len() > 2).then_some(name)
+}
+
+fn latex_fence(chr: &str) -> String {
+    match chr {
+        "" => String::from("."),
+        "{" => String::from("\\{"),
+        "}" => String::from("\\}"),
+        "\u{2016}" => String::from("\\|"),
+        "\u{27E8}" | "\u{2329}" => String::from("\\langle "),
+        "\u{27E9}" | "\u{232A}" => String::from("\\rangle "),
+        "\u{230A}" => String::from("\\lfloor "),
+        "\u{230B}" => String::from("\\rfloor "),
+        "\u{2308}" => String::from("\\lceil "),
+        "\u{2309}" => String::from("\\rceil "),
+        chr => chr.to_owned(),
+    }
+}
+
+// the text of the m:t elements of a run
+fn run_text(run: Node) -> String {
+    children(run)
+        .filter(|child| is_math(*child, "t"))
+        .map(|t| t.text().unwrap_or_default())
+        .collect()
+}
+
+// all the text within el, to recognize function names
+fn plain_text(el: Node) -> String {
+    if is_math(el, "r") {
+        return run_text(el);
+    }
+    children(el).map(plain_text).collect()
+}
+
+// m:val of a property like m:fPr/m:type, None when the property is absent
+fn property(el: Node, pr: &str, name: &str) -> Option<String> {
+    el.child(NS_MATH, pr)?
+        .child(NS_MATH, name)
+        .map(|prop| prop.attribute((NS_MATH, "val")).unwrap_or("on").to_owned())
+}
+
+// on/off properties without a value are on
+fn is_on(val: Option<String>) -> bool {
+    val.is_some_and(|val| matches!(val.as_str(), "1" | "on" | "true"))
+}
+
+fn is_off(val: Option<String>) -> bool {
+    val.is_some_and(|val| matches!(val.as_str(), "0" | "off" | "false"))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn children<'a, 'input>(el: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    el.elements()
+}
+
+fn is_math(el: Node, name: &str) -> bool {
+    el.has_tag_name((NS_MATH, name))
+}
+
+// the name of an OMML element, empty for others like the a:rPr of runs
+fn math_name<'a>(el: Node<'a, '_>) -> &'a str {
+    match el.tag_name().namespace() {
+        Some(NS_MATH) => el.tag_name().name(),
+        _ => "",
+    }
+}
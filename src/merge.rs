@@ -0,0 +1,100 @@
+use log::warn;
+use std::{collections::HashSet, env, fs, path::Path};
+
+use clap::Args;
+
+use crate::{catalog, ExportMediaError, INDEX_FILE};
+
+#[derive(Args)]
+pub struct MergeArgs {
+    /// index.json files of extractions, or catalog.json files of batch runs
+    #[arg(required = true)]
+    indexes: Vec<String>,
+
+    /// Catalog to write, the paths in it relative to where it is
+    #[arg(short, long, default_value_t = String::from(catalog::CATALOG_FILE))]
+    output: String,
+}
+
+// catalog the decks of several indexes and catalogs in one, as a batch run of
+// them all would
+pub fn run(args: &MergeArgs) -> Result<(), ExportMediaError> {
+    let output = Path::new(&args.output);
+    let base = match output.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            dir
+        }
+        None => Path::new("."),
+    };
+    let mut decks = Vec::new();
+    // the index files taken, an index being cataloged once
+    let mut seen = HashSet::new();
+    for path in &args.indexes {
+        let path = Path::new(path);
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}, error: {}", path.display(), e))?;
+        let index: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("failed to parse {}, error: {}", path.display(), e))?;
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        // a catalog lists the decks in the directories next to it
+        let entries = match index["decks"].as_array() {
+            Some(catalog_decks) => catalog_decks
+                .iter()
+                .map(|deck| {
+                    (
+                        deck["source"].as_str().unwrap_or_default().to_owned(),
+                        dir.join(deck["output_dir"].as_str().unwrap_or_default()),
+                        deck["error"].as_str().map(String::from),
+                    )
+                })
+                .collect(),
+            None => vec![(path.display().to_string(), dir.to_owned(), None)],
+        };
+        for (source, deck_dir, error) in entries {
+            let rel = relative(base, &deck_dir)?;
+            if error.is_none() && !seen.insert(rel.clone()) {
+                warn!("skipping {}, merged already", deck_dir.display());
+                continue;
+            }
+            if error.is_none() && !deck_dir.join(INDEX_FILE).is_file() {
+                return Err(format!("{} has no {}", deck_dir.display(), INDEX_FILE).into());
+            }
+            decks.push(catalog::deck(&source, &rel, base, error));
+        }
+    }
+    catalog::write(output, decks)
+}
+
+// the path of the directory to relative to the directory from, both made
+// absolute, with / between the components
+fn relative(from: &Path, to: &Path) -> Result<String, ExportMediaError> {
+    let from = fs::canonicalize(from)?;
+    // a deck that failed may have no directory
+    let to = match fs::canonicalize(to) {
+        Ok(to) => to,
+        Err(_) => env::current_dir()?.join(to),
+    };
+    let common = from
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts: Vec<String> = from
+        .components()
+        .skip(common)
+        .map(|_| String::from(".."))
+        .collect();
+    parts.extend(
+        to.components()
+            .skip(common)
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    Ok(match parts.is_empty() {
+        true => String::from("."),
+        false => parts.join("/"),
+    })
+}
@@ -0,0 +1,108 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use clap::Args;
+use roxmltree::Node;
+use serde::Serialize;
+
+use crate::{
+    errors::Errors,
+    package,
+    xml::{self, NodeExt},
+    ExportMediaError,
+};
+
+const REL_CORE_PROPERTIES: &str = "core-properties";
+const REL_EXTENDED_PROPERTIES: &str = "extended-properties";
+// the properties naming people or the organization, left out with anonymize
+const AUTHORSHIP: &[&str] = &["creator", "lastModifiedBy", "Company", "Manager"];
+
+#[derive(Args)]
+pub struct MetaArgs {
+    /// Input file
+    input_file: String,
+
+    /// Leave out the author, last modified by, company and manager properties
+    #[arg(long)]
+    anonymize: bool,
+}
+
+#[derive(Serialize)]
+struct Meta {
+    package_type: Option<package::PackageType>,
+    slides: usize,
+    masters: usize,
+    media: usize,
+    // docProps/core.xml, title, creator, created, ...
+    core: BTreeMap<String, String>,
+    // docProps/app.xml, Application, Company, TotalTime, ...
+    app: BTreeMap<String, String>,
+}
+
+// print the document properties and part counts of a presentation as JSON
+pub fn run(args: &MetaArgs) -> Result<(), ExportMediaError> {
+    let archivef = fs::File::open(Path::new(&args.input_file))?;
+    let mut archive =
+        zip::ZipArchive::new(io::BufReader::new(archivef)).map_err(|e| e.to_string())?;
+    let pkg = package::discover(&mut archive, &mut Errors::default())?;
+    let mut res = Meta {
+        package_type: package::package_type(&mut archive).ok(),
+        slides: pkg.slides.len(),
+        masters: pkg.masters.len(),
+        media: pkg.media.len(),
+        core: BTreeMap::new(),
+        app: BTreeMap::new(),
+    };
+    for rel in package::part_rels(&mut archive, "")? {
+        let properties = match rel.kind.as_str() {
+            REL_CORE_PROPERTIES => &mut res.core,
+            REL_EXTENDED_PROPERTIES => &mut res.app,
+            _ => continue,
+        };
+        let f = archive.by_name(&rel.target).map_err(|e| e.to_string())?;
+        let text = xml::read(f)?;
+        let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, rel.target.clone()))?;
+        properties.extend(plain_properties(doc.root_element(), args.anonymize));
+    }
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&res).map_err(|e| e.to_string())?
+    );
+    Ok(())
+}
+
+// only the plain values, not vectors like the titles of parts, keyed by name
+// whatever namespace the property is in, those of AUTHORSHIP left out with
+// anonymize
+fn plain_properties(el: Node, anonymize: bool) -> BTreeMap<String, String> {
+    let mut res = BTreeMap::new();
+    for child in el.elements() {
+        if child.elements().next().is_some() {
+            continue;
+        }
+        if anonymize && AUTHORSHIP.contains(&child.tag_name().name()) {
+            continue;
+        }
+        if let Some(text) = child.text() {
+            res.insert(child.tag_name().name().to_owned(), text.to_owned());
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::plain_properties;
+    use crate::xml;
+
+    const CORE: &str = r#"<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Quarterly review</dc:title><dc:creator>Jane Doe</dc:creator><cp:lastModifiedBy>John Roe</cp:lastModifiedBy><cp:revision>3</cp:revision></cp:coreProperties>"#;
+
+    #[test]
+    fn anonymize_leaves_out_creator_and_last_modified_by() {
+        let doc = xml::parse(CORE).unwrap();
+        let all = plain_properties(doc.root_element(), false);
+        assert_eq!(all["creator"], "Jane Doe");
+        assert_eq!(all["lastModifiedBy"], "John Roe");
+        let anonymized = plain_properties(doc.root_element(), true);
+        assert_eq!(anonymized.keys().collect::<Vec<_>>(), ["revision", "title"]);
+    }
+}
@@ -0,0 +1,548 @@
+use log::{trace, warn};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use tar::{Builder, Header};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+#[cfg(feature = "s3")]
+use crate::s3;
+use crate::ExportMediaError;
+
+// --output-tar - writes the archive to stdout
+pub const STDOUT: &str = "-";
+
+// what to do about files that are already in the output directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overwrite {
+    // replace them, warning about each one
+    #[default]
+    Warn,
+    // replace them quietly
+    Force,
+    // keep them and write nothing
+    Skip,
+    // fail instead of touching them
+    Error,
+}
+
+enum Target {
+    // a file is written under a temporary name and renamed once the next one
+    // starts, so that readers of the directory never see it half written
+    Dir {
+        dir: PathBuf,
+        pending: Option<Pending>,
+        // the names of the files in place, to clean up should the extraction fail
+        written: Vec<String>,
+    },
+    // the zip file is only created once something is written to it
+    Zip(PathBuf, Option<Box<ZipWriter<fs::File>>>),
+    // - being stdout, a tar entry needs its size up front so the file being
+    // written is kept in memory until the next one starts
+    Tar {
+        path: String,
+        builder: Option<Builder<Box<dyn Write + Send>>>,
+        pending: Option<(String, Vec<u8>)>,
+    },
+    // files are kept in memory until the next one starts and then queued
+    // for upload
+    #[cfg(feature = "s3")]
+    S3 {
+        client: s3::Client,
+        uploader: s3::Uploader,
+        pending: Option<(String, Vec<u8>)>,
+    },
+    Memory(BTreeMap<String, Vec<u8>>),
+    // discards everything, for benchmarks
+    Null,
+}
+
+// a file being written into a directory, and where it goes once it's done
+struct Pending {
+    file: fs::File,
+    temp: PathBuf,
+    name: String,
+    path: PathBuf,
+}
+
+impl Pending {
+    // returns the name it was written as
+    fn complete(self) -> io::Result<String> {
+        drop(self.file);
+        fs::rename(&self.temp, &self.path)?;
+        Ok(self.name)
+    }
+}
+
+// what becomes of the files of an extraction into a directory that fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    // leave them as they are
+    #[default]
+    Keep,
+    Remove,
+    // move them into the directory named after the output directory with
+    // .failed appended, replacing the one of an earlier failure
+    Move,
+}
+
+// where extracted files are written to, a directory, a zip or a tar archive,
+// an upload or memory
+pub struct Output {
+    target: Target,
+    overwrite: Overwrite,
+}
+
+impl Output {
+    pub fn new(dir: &Path, overwrite: Overwrite) -> Self {
+        Output {
+            target: Target::Dir {
+                dir: dir.to_owned(),
+                pending: None,
+                written: Vec::new(),
+            },
+            overwrite,
+        }
+    }
+
+    pub fn zip(path: &Path, overwrite: Overwrite) -> Self {
+        Output {
+            target: Target::Zip(path.to_owned(), None),
+            overwrite,
+        }
+    }
+
+    pub fn memory() -> Self {
+        Output {
+            target: Target::Memory(BTreeMap::new()),
+            overwrite: Overwrite::Force,
+        }
+    }
+
+    pub fn null() -> Self {
+        Output {
+            target: Target::Null,
+            overwrite: Overwrite::Force,
+        }
+    }
+
+    pub fn tar(path: &str, overwrite: Overwrite) -> Self {
+        Output {
+            target: Target::Tar {
+                path: path.to_owned(),
+                builder: None,
+                pending: None,
+            },
+            overwrite,
+        }
+    }
+
+    #[cfg(feature = "s3")]
+    pub fn s3(
+        url: &str,
+        concurrency: usize,
+        overwrite: Overwrite,
+    ) -> Result<Self, ExportMediaError> {
+        let client = s3::Client::from_env(url)?;
+        Ok(Output {
+            target: Target::S3 {
+                uploader: s3::Uploader::new(client.clone(), concurrency),
+                client,
+                pending: None,
+            },
+            overwrite,
+        })
+    }
+
+    // refuse an output that already holds an extraction, before anything is
+    // written to it
+    pub fn check(&self, index_file: &str) -> Result<(), ExportMediaError> {
+        let existing = match &self.target {
+            Target::Dir { dir, .. } => dir.join(index_file),
+            Target::Zip(path, _) => path.clone(),
+            Target::Tar { path, .. } if path == STDOUT => return Ok(()),
+            Target::Memory(_) | Target::Null => return Ok(()),
+            Target::Tar { path, .. } => PathBuf::from(path),
+            #[cfg(feature = "s3")]
+            Target::S3 { client, .. } => {
+                if self.overwrite == Overwrite::Error && client.exists(index_file)? {
+                    return Err(format!(
+                        "output already holds an extraction, found {}",
+                        client.url(index_file)
+                    )
+                    .into());
+                }
+                return Ok(());
+            }
+        };
+        if self.overwrite == Overwrite::Error && existing.exists() {
+            return Err(format!(
+                "output already holds an extraction, found {}",
+                existing.display()
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    // where name ends up, for display
+    pub fn display(&self, name: &str) -> String {
+        match &self.target {
+            Target::Dir { dir, .. } => dir.join(name).display().to_string(),
+            Target::Zip(path, _) => format!("{}:{}", path.display(), name),
+            Target::Tar { path, .. } => format!("{}:{}", path, name),
+            Target::Memory(_) | Target::Null => name.to_owned(),
+            #[cfg(feature = "s3")]
+            Target::S3 { client, .. } => client.url(name),
+        }
+    }
+
+    // create a file at name relative to the output, None when an existing
+    // file is to be kept
+    pub fn create(&mut self, name: &str) -> Result<Option<Box<dyn Write + '_>>, ExportMediaError> {
+        // names come from the package, one escaping the output is refused
+        // whatever it's written to
+        let inside = Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if name.is_empty() || !inside {
+            return Err(format!("refusing to write {} outside of the output", name).into());
+        }
+        let overwrite = self.overwrite;
+        match &mut self.target {
+            Target::Dir {
+                dir,
+                pending,
+                written,
+            } => {
+                if let Some(pending) = pending.take() {
+                    written.push(pending.complete()?);
+                }
+                if let Some(parent) = Path::new(name)
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                {
+                    fs::create_dir_all(dir.join(parent))?;
+                }
+                let path = dir.join(name);
+                if !keep_or_replace(&path, overwrite)? {
+                    return Ok(None);
+                }
+                let temp = temp_path(&path);
+                let file = fs::File::create(&temp)?;
+                let pending = pending.insert(Pending {
+                    file,
+                    temp,
+                    name: name.to_owned(),
+                    path,
+                });
+                Ok(Some(Box::new(&mut pending.file)))
+            }
+            Target::Zip(path, writer) => {
+                if writer.is_none() {
+                    // keeping an existing archive would leave nothing to write to
+                    let overwrite = match overwrite {
+                        Overwrite::Skip => Overwrite::Error,
+                        overwrite => overwrite,
+                    };
+                    let file = create_file(path, overwrite)?
+                        .ok_or_else(|| format!("{} already exists", path.display()))?;
+                    *writer = Some(Box::new(ZipWriter::new(file)));
+                }
+                let writer = writer.as_mut().unwrap();
+                writer
+                    .start_file(name, SimpleFileOptions::default())
+                    .map_err(|e| e.to_string())?;
+                Ok(Some(Box::new(writer.as_mut())))
+            }
+            Target::Tar {
+                path,
+                builder,
+                pending,
+            } => {
+                if builder.is_none() {
+                    let writer: Box<dyn Write + Send> = if path == STDOUT {
+                        Box::new(io::stdout())
+                    } else {
+                        let overwrite = match overwrite {
+                            Overwrite::Skip => Overwrite::Error,
+                            overwrite => overwrite,
+                        };
+                        let file = create_file(Path::new(path), overwrite)?
+                            .ok_or_else(|| format!("{} already exists", path))?;
+                        Box::new(io::BufWriter::new(file))
+                    };
+                    *builder = Some(Builder::new(writer));
+                }
+                append_pending(builder.as_mut().unwrap(), pending)?;
+                let (_, data) = pending.insert((name.to_owned(), Vec::new()));
+                Ok(Some(Box::new(data)))
+            }
+            #[cfg(feature = "s3")]
+            Target::S3 {
+                client,
+                uploader,
+                pending,
+            } => {
+                if let Some((name, data)) = pending.take() {
+                    uploader.upload(name, data);
+                }
+                if matches!(overwrite, Overwrite::Skip | Overwrite::Error) && client.exists(name)? {
+                    if overwrite == Overwrite::Skip {
+                        trace!("keeping existing {}", client.url(name));
+                        return Ok(None);
+                    }
+                    return Err(format!("{} already exists", client.url(name)).into());
+                }
+                let (_, data) = pending.insert((name.to_owned(), Vec::new()));
+                Ok(Some(Box::new(data)))
+            }
+            Target::Memory(files) => {
+                let data = files.entry(name.to_owned()).or_default();
+                data.clear();
+                Ok(Some(Box::new(data)))
+            }
+            Target::Null => Ok(Some(Box::new(io::sink()))),
+        }
+    }
+
+    // write data to name, returns whether it was written
+    pub fn write(&mut self, name: &str, data: &[u8]) -> Result<bool, ExportMediaError> {
+        match self.create(name)? {
+            Some(mut file) => {
+                file.write_all(data)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // the files written to a memory output
+    pub fn into_files(self) -> BTreeMap<String, Vec<u8>> {
+        match self.target {
+            Target::Memory(files) => files,
+            _ => BTreeMap::new(),
+        }
+    }
+
+    // undo the output of a failed extraction into a directory as on_failure
+    // says, the files written are removed or moved and the directories left empty
+    // by that removed. Returns the directory the output is left in, if any
+    pub fn discard(self, on_failure: OnFailure) -> Result<Option<PathBuf>, ExportMediaError> {
+        let (dir, pending, written) = match self.target {
+            Target::Dir {
+                dir,
+                pending,
+                written,
+            } => (dir, pending, written),
+            _ => return Ok(None),
+        };
+        // the file being written is never of use
+        if let Some(pending) = pending {
+            drop(pending.file);
+            fs::remove_file(&pending.temp)?;
+        }
+        if on_failure == OnFailure::Keep {
+            return Ok(Some(dir));
+        }
+        let failed = failed_dir(&dir);
+        if on_failure == OnFailure::Move && failed.exists() {
+            fs::remove_dir_all(&failed)?;
+        }
+        for name in &written {
+            let path = dir.join(name);
+            match on_failure {
+                OnFailure::Move => {
+                    let to = failed.join(name);
+                    fs::create_dir_all(to.parent().unwrap())?;
+                    fs::rename(&path, &to)?;
+                }
+                _ => fs::remove_file(&path)?,
+            }
+            // the directories made for the file, which fail to go while
+            // anything else is in them
+            for parent in Path::new(name).ancestors().skip(1) {
+                if parent.as_os_str().is_empty() || fs::remove_dir(dir.join(parent)).is_err() {
+                    break;
+                }
+            }
+        }
+        // and the output directory itself likewise
+        let _ = fs::remove_dir(&dir);
+        match on_failure {
+            _ if written.is_empty() => {}
+            OnFailure::Move => warn!("moved the output to {}", failed.display()),
+            _ => warn!("removed the output from {}", dir.display()),
+        }
+        Ok((on_failure == OnFailure::Move).then_some(failed))
+    }
+
+    // put the file being written into a directory in its place now rather
+    // than once the next one starts, for it to be read while more are written
+    pub fn complete(&mut self) -> Result<(), ExportMediaError> {
+        if let Target::Dir {
+            pending, written, ..
+        } = &mut self.target
+        {
+            if let Some(pending) = pending.take() {
+                written.push(pending.complete()?);
+            }
+        }
+        Ok(())
+    }
+
+    // complete the output, which for a zip archive writes its central directory
+    // and for a directory puts the last file in its place
+    pub fn finish(self) -> Result<(), ExportMediaError> {
+        match self.target {
+            Target::Dir {
+                pending: Some(pending),
+                ..
+            } => {
+                pending.complete()?;
+            }
+            Target::Zip(_, Some(writer)) => {
+                writer.finish().map_err(|e| e.to_string())?;
+            }
+            Target::Tar {
+                builder: Some(mut builder),
+                mut pending,
+                ..
+            } => {
+                append_pending(&mut builder, &mut pending)?;
+                builder.into_inner()?.flush()?;
+            }
+            #[cfg(feature = "s3")]
+            Target::S3 {
+                uploader, pending, ..
+            } => {
+                if let Some((name, data)) = pending {
+                    uploader.upload(name, data);
+                }
+                uploader.finish()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// o.failed for o
+pub fn failed_dir(dir: &Path) -> PathBuf {
+    // a name to append to even for . or o/
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_owned());
+    let mut name = dir.into_os_string();
+    name.push(".failed");
+    PathBuf::from(name)
+}
+
+fn append_pending(
+    builder: &mut Builder<Box<dyn Write + Send>>,
+    pending: &mut Option<(String, Vec<u8>)>,
+) -> Result<(), ExportMediaError> {
+    if let Some((name, data)) = pending.take() {
+        let mut header = Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default(),
+        );
+        builder.append_data(&mut header, &name, data.as_slice())?;
+    }
+    Ok(())
+}
+
+// whether to write path, which is refused when it exists and overwrite is Error
+fn keep_or_replace(path: &Path, overwrite: Overwrite) -> Result<bool, ExportMediaError> {
+    if path.exists() {
+        match overwrite {
+            Overwrite::Warn => warn!("overwriting {}", path.display()),
+            Overwrite::Force => trace!("overwriting {}", path.display()),
+            Overwrite::Skip => {
+                trace!("keeping existing {}", path.display());
+                return Ok(false);
+            }
+            Overwrite::Error => {
+                return Err(format!("{} already exists", path.display()).into());
+            }
+        }
+    }
+    Ok(true)
+}
+
+// the hidden file next to path it's written as before being renamed to path,
+// .index.json.tmp for index.json
+pub fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.tmp", name))
+}
+
+// write data to path through a temporary file, so that it's either missing or
+// complete
+pub fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let temp = temp_path(path);
+    fs::write(&temp, data)?;
+    fs::rename(&temp, path)
+}
+
+fn create_file(path: &Path, overwrite: Overwrite) -> Result<Option<fs::File>, ExportMediaError> {
+    if path.exists() {
+        match overwrite {
+            Overwrite::Warn => warn!("overwriting {}", path.display()),
+            Overwrite::Force => trace!("overwriting {}", path.display()),
+            Overwrite::Skip => {
+                trace!("keeping existing {}", path.display());
+                return Ok(None);
+            }
+            Overwrite::Error => {}
+        }
+    }
+    if overwrite == Overwrite::Error {
+        return match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                Err(format!("{} already exists", path.display()).into())
+            }
+            Err(e) => Err(e.into()),
+        };
+    }
+    Ok(Some(fs::File::create(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Output;
+
+    #[test]
+    fn refuses_names_outside_the_output() {
+        let mut output = Output::memory();
+        for name in [
+            "../escaped.png",
+            "media/../../escaped.png",
+            "/escaped.png",
+            "",
+        ] {
+            assert!(output.write(name, b"data").is_err(), "{}", name);
+        }
+        assert!(output.write("ppt/media/image1.png", b"data").unwrap());
+        assert_eq!(
+            output.into_files().into_keys().collect::<Vec<_>>(),
+            ["ppt/media/image1.png"]
+        );
+    }
+}
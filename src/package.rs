@@ -0,0 +1,513 @@
+use log::{trace, warn};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Read, Seek},
+    path::Path,
+};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    errors::Errors,
+    xml::{
+        self, NodeExt, NS_CONTENT_TYPES, NS_PACKAGE_RELATIONSHIPS, NS_PRESENTATIONML as P,
+        NS_RELATIONSHIPS,
+    },
+    ExportMediaError,
+};
+
+pub const CONTENT_TYPES_FILE: &str = "[Content_Types].xml";
+// where the main part usually lives, used when the package rels don't say
+pub const PRESENTATION_FILE: &str = "ppt/presentation.xml";
+
+// relationship kinds, the last segment of the relationship type, so that both
+// the transitional and strict namespaces match
+pub const REL_OFFICE_DOCUMENT: &str = "officeDocument";
+pub const REL_SLIDE: &str = "slide";
+pub const REL_SLIDE_MASTER: &str = "slideMaster";
+pub const REL_SLIDE_LAYOUT: &str = "slideLayout";
+pub const REL_THEME: &str = "theme";
+pub const REL_NOTES_SLIDE: &str = "notesSlide";
+pub const REL_IMAGE: &str = "image";
+pub const REL_THUMBNAIL: &str = "thumbnail";
+pub const REL_MEDIA: &[&str] = &[REL_IMAGE, "video", "audio", "media"];
+
+// main part content types of the presentation package variants
+const MAIN_CONTENT_TYPES: &[(&str, PackageType)] = &[
+    (
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation.main+xml",
+        PackageType::Pptx,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.presentationml.slideshow.main+xml",
+        PackageType::Ppsx,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.presentationml.template.main+xml",
+        PackageType::Potx,
+    ),
+    (
+        "application/vnd.ms-powerpoint.presentation.macroEnabled.main+xml",
+        PackageType::Pptm,
+    ),
+    (
+        "application/vnd.ms-powerpoint.slideshow.macroEnabled.main+xml",
+        PackageType::Ppsm,
+    ),
+    (
+        "application/vnd.ms-powerpoint.template.macroEnabled.main+xml",
+        PackageType::Potm,
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageType {
+    Pptx,
+    Ppsx,
+    Potx,
+    Pptm,
+    Ppsm,
+    Potm,
+}
+
+// detect the package variant from the content type of its main part
+pub fn package_type<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> Result<PackageType, ExportMediaError> {
+    let f = archive
+        .by_name(CONTENT_TYPES_FILE)
+        .map_err(|e| e.to_string())?;
+    let text = xml::read(f)?;
+    let doc =
+        xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, CONTENT_TYPES_FILE.into()))?;
+    for el in doc.root_element().elements() {
+        let content_type = match Some(el)
+            .filter(|el| el.has_tag_name((NS_CONTENT_TYPES, "Override")))
+            .and_then(|el| el.attribute("ContentType"))
+        {
+            Some(content_type) => content_type,
+            None => continue,
+        };
+        if let Some((_, package_type)) = MAIN_CONTENT_TYPES
+            .iter()
+            .find(|(main_content_type, _)| *main_content_type == content_type)
+        {
+            return Ok(*package_type);
+        }
+    }
+    Err(ExportMediaError::Custom(String::from(
+        "not a presentation package, no presentation main part",
+    )))
+}
+
+// the content types of [Content_Types].xml, which match case-insensitively
+#[derive(Debug, Default)]
+pub struct ContentTypes {
+    // extension -> content type
+    defaults: HashMap<String, String>,
+    // part name, without the leading / -> content type
+    overrides: HashMap<String, String>,
+}
+
+impl ContentTypes {
+    pub fn get(&self, part_name: &str) -> Option<&str> {
+        let part_name = part_name.to_lowercase();
+        if let Some(content_type) = self.overrides.get(&part_name) {
+            return Some(content_type);
+        }
+        // split by hand, Path taking _rels/.rels for a file without one
+        let file_name = part_name.rsplit('/').next()?;
+        let (_, extension) = file_name.rsplit_once('.')?;
+        self.defaults.get(extension).map(String::as_str)
+    }
+}
+
+// read [Content_Types].xml
+pub fn content_types<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> Result<ContentTypes, ExportMediaError> {
+    let f = archive
+        .by_name(CONTENT_TYPES_FILE)
+        .map_err(|e| e.to_string())?;
+    let text = xml::read(f)?;
+    let doc =
+        xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, CONTENT_TYPES_FILE.into()))?;
+    let mut res = ContentTypes::default();
+    for el in doc.root_element().elements() {
+        let content_type = match el.attribute("ContentType") {
+            Some(content_type) => content_type.to_owned(),
+            None => continue,
+        };
+        if el.has_tag_name((NS_CONTENT_TYPES, "Default")) {
+            if let Some(extension) = el.attribute("Extension") {
+                res.defaults.insert(extension.to_lowercase(), content_type);
+            }
+        } else if el.has_tag_name((NS_CONTENT_TYPES, "Override")) {
+            if let Some(part_name) = el.attribute("PartName") {
+                let part_name = part_name.trim_start_matches('/').to_lowercase();
+                res.overrides.insert(part_name, content_type);
+            }
+        }
+    }
+    Ok(res)
+}
+
+#[derive(Debug, Clone)]
+pub struct Rel {
+    pub id: String,
+    // last segment of the relationship type, e.g. slide, image
+    pub kind: String,
+    // resolved part name, or the target as is for external relationships
+    pub target: String,
+    pub external: bool,
+}
+
+// the parts of a presentation found by following relationships from the
+// package root, in presentation order where there is one
+#[derive(Debug, Default)]
+pub struct Package {
+    pub main_part: String,
+    pub main_rels: Vec<Rel>,
+    pub slides: Vec<String>,
+    pub masters: Vec<String>,
+    pub layouts: Vec<String>,
+    pub themes: Vec<String>,
+    // slide part -> its notes slide part
+    pub notes: HashMap<String, String>,
+    // slide part -> its layout part, layout part -> its master part
+    pub slide_layouts: HashMap<String, String>,
+    pub layout_masters: HashMap<String, String>,
+    // embedded images, video and audio referenced by any of the above
+    pub media: Vec<String>,
+}
+
+impl Package {
+    pub fn main_rel(&self, id: &str) -> Option<&Rel> {
+        self.main_rels.iter().find(|rel| rel.id == id)
+    }
+
+    // the 1-based numbers of the layout and master of a slide, in the order of
+    // layouts and masters
+    pub fn layout_and_master(&self, slide: &str) -> (Option<u32>, Option<u32>) {
+        let layout = self.slide_layouts.get(slide);
+        let master = layout.and_then(|layout| self.layout_masters.get(layout));
+        let position = |parts: &[String], part: Option<&String>| {
+            part.and_then(|part| parts.iter().position(|p| p == part))
+                .map(|i| i as u32 + 1)
+        };
+        (
+            position(&self.layouts, layout),
+            position(&self.masters, master),
+        )
+    }
+}
+
+// walk the package from _rels/.rels to the presentation part and on to its
+// slides, masters, layouts, themes and media by relationship type. A slide,
+// layout or master whose rels fail to read is recorded in errors and taken
+// to have none
+pub fn discover<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    errors: &mut Errors,
+) -> Result<Package, ExportMediaError> {
+    let mut res = Package {
+        main_part: part_rels(archive, "")?
+            .into_iter()
+            .find(|rel| rel.kind == REL_OFFICE_DOCUMENT && !rel.external)
+            .map(|rel| rel.target)
+            .unwrap_or_else(|| {
+                warn!(
+                    "package has no main part relationship, assuming {}",
+                    PRESENTATION_FILE
+                );
+                String::from(PRESENTATION_FILE)
+            }),
+        ..Default::default()
+    };
+    res.main_rels = part_rels(archive, &res.main_part)?;
+    let text = xml::read(archive.by_name(&res.main_part).map_err(|e| e.to_string())?)?;
+    let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, res.main_part.clone()))?;
+
+    // slides and masters are listed in p:sldIdLst and p:sldMasterIdLst
+    for (list, item, kind) in [
+        ("sldIdLst", "sldId", REL_SLIDE),
+        ("sldMasterIdLst", "sldMasterId", REL_SLIDE_MASTER),
+    ] {
+        let ids = doc
+            .root_element()
+            .child(P, list)
+            .into_iter()
+            .flat_map(|list| list.elements())
+            .filter(|el| el.has_tag_name((P, item)))
+            .filter_map(|el| el.attribute((NS_RELATIONSHIPS, "id")));
+        for id in ids {
+            match res.main_rel(id).filter(|rel| rel.kind == kind) {
+                Some(rel) => {
+                    let target = rel.target.clone();
+                    match kind {
+                        REL_SLIDE => res.slides.push(target),
+                        _ => res.masters.push(target),
+                    }
+                }
+                None => warn!("{} id {} has no relationship in presentation", kind, id),
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    let mut add_media = |media: &mut Vec<String>, rels: &[Rel]| {
+        for rel in rels {
+            if REL_MEDIA.contains(&rel.kind.as_str())
+                && !rel.external
+                && seen.insert(rel.target.clone())
+            {
+                media.push(rel.target.clone());
+            }
+        }
+    };
+    add_themes(&mut res.themes, &res.main_rels);
+
+    for master in res.masters.clone() {
+        let rels = part_rels_or_none(archive, &master, errors);
+        add_themes(&mut res.themes, &rels);
+        for rel in rels.iter().filter(|rel| rel.kind == REL_SLIDE_LAYOUT) {
+            if !res.layouts.contains(&rel.target) {
+                res.layouts.push(rel.target.clone());
+            }
+        }
+        add_media(&mut res.media, &rels);
+    }
+    for layout in res.layouts.clone() {
+        let rels = part_rels_or_none(archive, &layout, errors);
+        if let Some(master) = rels.iter().find(|rel| rel.kind == REL_SLIDE_MASTER) {
+            res.layout_masters
+                .insert(layout.clone(), master.target.clone());
+        }
+        add_media(&mut res.media, &rels);
+    }
+    for slide in res.slides.clone() {
+        let rels = part_rels_or_none(archive, &slide, errors);
+        if let Some(notes) = rels.iter().find(|rel| rel.kind == REL_NOTES_SLIDE) {
+            res.notes.insert(slide.clone(), notes.target.clone());
+        }
+        if let Some(layout) = rels.iter().find(|rel| rel.kind == REL_SLIDE_LAYOUT) {
+            res.slide_layouts
+                .insert(slide.clone(), layout.target.clone());
+        }
+        add_media(&mut res.media, &rels);
+    }
+    trace!("discovered package: {:?}", res);
+    Ok(res)
+}
+
+fn add_themes(themes: &mut Vec<String>, rels: &[Rel]) {
+    for rel in rels.iter().filter(|rel| rel.kind == REL_THEME) {
+        if !themes.contains(&rel.target) {
+            themes.push(rel.target.clone());
+        }
+    }
+}
+
+// the relationships of a part, none when they fail to read
+fn part_rels_or_none<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+    errors: &mut Errors,
+) -> Vec<Rel> {
+    part_rels(archive, part_name).unwrap_or_else(|e| {
+        errors.error("failed to get rels of", Some(part_name), e);
+        Vec::new()
+    })
+}
+
+// get the relationships of a part, a part without a rels file has none
+pub fn part_rels<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+) -> Result<Vec<Rel>, ExportMediaError> {
+    let rels_name = rels_part(part_name);
+    let rels_file = match archive.by_name(&rels_name) {
+        Ok(rels_file) => rels_file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string().into()),
+    };
+    let text = xml::read(rels_file)?;
+    let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, rels_name.clone()))?;
+    let base_dir = Path::new(part_name)
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut res = Vec::new();
+    for rel_el in doc
+        .root_element()
+        .elements()
+        .filter(|el| el.has_tag_name((NS_PACKAGE_RELATIONSHIPS, "Relationship")))
+    {
+        let (id, rel_type, target) = match (
+            rel_el.attribute("Id"),
+            rel_el.attribute("Type"),
+            rel_el.attribute("Target"),
+        ) {
+            (Some(id), Some(rel_type), Some(target)) => (id, rel_type, target),
+            _ => continue,
+        };
+        let external = rel_el.attribute("TargetMode") == Some("External");
+        res.push(Rel {
+            id: id.to_owned(),
+            kind: rel_type.rsplit('/').next().unwrap_or_default().to_owned(),
+            target: if external {
+                target.to_owned()
+            } else {
+                resolve_target(&base_dir, target)
+            },
+            external,
+        });
+    }
+    Ok(res)
+}
+
+// get the rels file of a part, e.g. ppt/slides/slide1.xml -> ppt/slides/_rels/slide1.xml.rels,
+// the package itself being the empty part name
+pub fn rels_part(part_name: &str) -> String {
+    let path = Path::new(part_name);
+    let parent = path
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    if parent.is_empty() {
+        format!("_rels/{}.rels", file_name)
+    } else {
+        format!("{}/_rels/{}.rels", parent, file_name)
+    }
+}
+
+// resolve a relationship target relative to the directory of its source part,
+// or to the package root when it starts with '/'. A '..' at the root is
+// dropped so that the part name stays inside the package
+pub fn resolve_target(base_dir: &str, target: &str) -> String {
+    let (base_dir, target) = match target.strip_prefix('/') {
+        Some(absolute) => ("", absolute),
+        None => (base_dir, target),
+    };
+    let mut segments: Vec<&str> = base_dir.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+    use super::{discover, resolve_target};
+    use crate::errors::Errors;
+
+    const RELS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+    const TYPES: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+    fn archive(parts: &[(&str, String)]) -> ZipArchive<Cursor<Vec<u8>>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, text) in parts {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(text.as_bytes()).unwrap();
+        }
+        ZipArchive::new(writer.finish().unwrap()).unwrap()
+    }
+
+    fn rels(rels: &[(&str, &str, &str)]) -> String {
+        let rels: String = rels
+            .iter()
+            .map(|(id, kind, target)| {
+                format!(
+                    r#"<Relationship Id="{}" Type="{}/{}" Target="{}"/>"#,
+                    id, TYPES, kind, target
+                )
+            })
+            .collect();
+        format!(
+            r#"<Relationships xmlns="{}">{}</Relationships>"#,
+            RELS, rels
+        )
+    }
+
+    #[test]
+    fn resolves_relative_targets() {
+        assert_eq!(
+            resolve_target("ppt/slides", "../media/image1.png"),
+            "ppt/media/image1.png"
+        );
+        assert_eq!(
+            resolve_target("ppt/slides", "./chart1.xml"),
+            "ppt/slides/chart1.xml"
+        );
+    }
+
+    #[test]
+    fn keeps_targets_inside_the_package() {
+        assert_eq!(
+            resolve_target("ppt/slides", "../../../../escaped.png"),
+            "escaped.png"
+        );
+        assert_eq!(
+            resolve_target("ppt/slides", "/../../escaped.png"),
+            "escaped.png"
+        );
+        assert_eq!(
+            resolve_target("ppt/slides", "/ppt/media/../media/image1.png"),
+            "ppt/media/image1.png"
+        );
+    }
+
+    #[test]
+    fn slide_with_malformed_rels_has_none() {
+        let presentation = format!(
+            r#"<p:presentation xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main" xmlns:r="{}"><p:sldIdLst><p:sldId id="256" r:id="rId1"/><p:sldId id="257" r:id="rId2"/></p:sldIdLst></p:presentation>"#,
+            TYPES
+        );
+        let mut archive = archive(&[
+            (
+                "_rels/.rels",
+                rels(&[("rId1", "officeDocument", "ppt/presentation.xml")]),
+            ),
+            ("ppt/presentation.xml", presentation),
+            (
+                "ppt/_rels/presentation.xml.rels",
+                rels(&[
+                    ("rId1", "slide", "slides/slide1.xml"),
+                    ("rId2", "slide", "slides/slide2.xml"),
+                ]),
+            ),
+            (
+                "ppt/slides/_rels/slide1.xml.rels",
+                String::from("<Relationships"),
+            ),
+            (
+                "ppt/slides/_rels/slide2.xml.rels",
+                rels(&[("rId1", "image", "../media/image1.png")]),
+            ),
+        ]);
+        let mut errors = Errors::default();
+        let pkg = discover(&mut archive, &mut errors).unwrap();
+        assert_eq!(
+            pkg.slides,
+            ["ppt/slides/slide1.xml", "ppt/slides/slide2.xml"]
+        );
+        assert_eq!(pkg.media, ["ppt/media/image1.png"]);
+        let errors = errors.take();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].part.as_deref(), Some("ppt/slides/slide1.xml"));
+    }
+}
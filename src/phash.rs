@@ -0,0 +1,91 @@
+use std::{collections::BTreeMap, f64::consts::PI};
+
+use image::imageops::FilterType;
+
+// images are compared at this size, and by the lowest LOW x LOW frequencies
+const SIZE: usize = 32;
+const LOW: usize = 8;
+// the differing bits of two hashes up to which images are taken for the same,
+// the same photo scaled, recompressed or slightly cropped differing by a few
+pub const MAX_DISTANCE: u32 = 10;
+
+// the perceptual hash of an image and its size in pixels, none when it can't
+// be decoded, such as vector images. Each bit tells whether a low frequency
+// of the image scaled down in grey is above the median of them, which stays
+// the same when the image is scaled or recompressed.
+pub fn phash(data: &[u8]) -> Option<(u64, u64)> {
+    let img = image::load_from_memory(data).ok()?;
+    let pixels = u64::from(img.width()) * u64::from(img.height());
+    let grey = img
+        .resize_exact(SIZE as u32, SIZE as u32, FilterType::Triangle)
+        .to_luma8();
+    let cos: Vec<Vec<f64>> = (0..LOW)
+        .map(|u| {
+            (0..SIZE)
+                .map(|x| ((2 * x + 1) as f64 * u as f64 * PI / (2 * SIZE) as f64).cos())
+                .collect()
+        })
+        .collect();
+    let mut dct = Vec::with_capacity(LOW * LOW);
+    for v in 0..LOW {
+        for u in 0..LOW {
+            let mut sum = 0.0;
+            for (y, row) in grey.rows().enumerate() {
+                for (x, pixel) in row.enumerate() {
+                    sum += f64::from(pixel[0]) * cos[u][x] * cos[v][y];
+                }
+            }
+            dct.push(sum);
+        }
+    }
+    // the median leaving out the average brightness, which tells nothing of
+    // the picture
+    let mut sorted = dct[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+    let hash = dct
+        .iter()
+        .enumerate()
+        .filter(|(_, coefficient)| **coefficient > median)
+        .fold(0u64, |hash, (i, _)| hash | 1 << i);
+    Some((hash, pixels))
+}
+
+// the groups of images whose hashes are within MAX_DISTANCE bits of another
+// in the group, by exported file name, each starting with the one of the most
+// pixels, the copy to keep. Images without a near duplicate are left out.
+pub fn similar(hashes: &BTreeMap<String, (u64, u64)>) -> Vec<Vec<String>> {
+    let names: Vec<&String> = hashes.keys().collect();
+    // union-find over the images, each pointing towards its group's root
+    let mut parents: Vec<usize> = (0..names.len()).collect();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+    for i in 0..names.len() {
+        for j in i + 1..names.len() {
+            let distance = (hashes[names[i]].0 ^ hashes[names[j]].0).count_ones();
+            if distance <= MAX_DISTANCE {
+                let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                parents[b] = a;
+            }
+        }
+    }
+    let mut groups: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for (i, name) in names.iter().enumerate() {
+        let group = root(&mut parents, i);
+        groups.entry(group).or_default().push((*name).clone());
+    }
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            // the most pixels first, names keeping the order of ties
+            group.sort_by_key(|name| std::cmp::Reverse(hashes[name].1));
+            group
+        })
+        .collect()
+}
@@ -0,0 +1,301 @@
+use log::{error, trace, warn};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    process::{Command, Stdio},
+    str::FromStr,
+    thread,
+};
+#[cfg(feature = "wasm-plugins")]
+use std::{fs, path::PathBuf};
+
+use serde_json::Value;
+
+use crate::{ExportMediaError, SingleRes};
+
+// the fields a plugin adds to a slide or a media file in the index
+pub type Fields = BTreeMap<String, Value>;
+
+// a user-supplied program given each slide and each media file, returning
+// fields to add to the index, e.g. classification tags. A command is run as
+// `<command> slide` with the slide as JSON on stdin, or `<command> media
+// <file name>` with the media file on stdin, and prints a JSON object, or
+// nothing. A WASM module exports its memory, alloc(len) -> ptr, and slide(ptr,
+// len) or media(name_ptr, name_len, ptr, len), returning the pointer to a JSON
+// object shifted 32 bits left and ored with its length, or 0.
+#[derive(Debug, Clone)]
+pub enum Plugin {
+    Command(Vec<String>),
+    #[cfg(feature = "wasm-plugins")]
+    Wasm(PathBuf),
+}
+
+impl FromStr for Plugin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.ends_with(".wasm") {
+            #[cfg(feature = "wasm-plugins")]
+            return Ok(Plugin::Wasm(PathBuf::from(s)));
+            #[cfg(not(feature = "wasm-plugins"))]
+            return Err(format!("{} needs the wasm-plugins feature", s));
+        }
+        let command: Vec<String> = s.split_whitespace().map(str::to_owned).collect();
+        if command.is_empty() {
+            return Err("empty plugin command".to_owned());
+        }
+        Ok(Plugin::Command(command))
+    }
+}
+
+impl Plugin {
+    fn name(&self) -> String {
+        match self {
+            Plugin::Command(command) => command.join(" "),
+            #[cfg(feature = "wasm-plugins")]
+            Plugin::Wasm(path) => path.display().to_string(),
+        }
+    }
+}
+
+// the plugins of an extraction, the WASM modules loaded once for all the
+// slides and media
+pub struct Plugins {
+    loaded: Vec<(String, Loaded)>,
+}
+
+enum Loaded {
+    Command(Vec<String>),
+    #[cfg(feature = "wasm-plugins")]
+    Wasm(Box<wasm::Module>),
+}
+
+impl Plugins {
+    pub fn load(plugins: &[Plugin]) -> Result<Self, ExportMediaError> {
+        let mut loaded = Vec::new();
+        for plugin in plugins {
+            let name = plugin.name();
+            let plugin = match plugin {
+                Plugin::Command(command) => Loaded::Command(command.clone()),
+                #[cfg(feature = "wasm-plugins")]
+                Plugin::Wasm(path) => {
+                    let module = fs::read(path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|wasm| wasm::Module::new(&wasm))
+                        .map_err(|e| format!("failed to load plugin {}: {}", name, e))?;
+                    Loaded::Wasm(Box::new(module))
+                }
+            };
+            loaded.push((name, plugin));
+        }
+        Ok(Plugins { loaded })
+    }
+
+    // add the fields the plugins return for a slide, those it already has
+    // being left as they are
+    pub fn slide(&mut self, single_res: &mut SingleRes) {
+        if self.loaded.is_empty() {
+            return;
+        }
+        let json = serde_json::to_vec(single_res).unwrap();
+        let existing = match serde_json::from_slice(&json) {
+            Ok(Value::Object(existing)) => existing,
+            _ => serde_json::Map::new(),
+        };
+        for (name, plugin) in self.loaded.iter_mut() {
+            let fields = match plugin {
+                Loaded::Command(command) => run(command, &["slide"], &json),
+                #[cfg(feature = "wasm-plugins")]
+                Loaded::Wasm(module) => module.slide(&json),
+            };
+            for (key, value) in ok_or_log(name, fields) {
+                if existing.contains_key(&key) || single_res.fields.contains_key(&key) {
+                    warn!(
+                        "plugin {} returned field {} the slide already has",
+                        name, key
+                    );
+                    continue;
+                }
+                single_res.fields.insert(key, value);
+            }
+        }
+        trace!(
+            "plugins added to slide {}: {:?}",
+            single_res.page_no,
+            single_res.fields
+        );
+    }
+
+    // the fields the plugins return for a media file, filename being the name
+    // it's exported as
+    pub fn media(&mut self, filename: &str, data: &[u8]) -> Fields {
+        let mut res = Fields::new();
+        for (name, plugin) in self.loaded.iter_mut() {
+            let fields = match plugin {
+                Loaded::Command(command) => run(command, &["media", filename], data),
+                #[cfg(feature = "wasm-plugins")]
+                Loaded::Wasm(module) => module.media(filename, data),
+            };
+            for (key, value) in ok_or_log(name, fields) {
+                if res.contains_key(&key) {
+                    warn!("plugin {} returned field {} another plugin did", name, key);
+                    continue;
+                }
+                res.insert(key, value);
+            }
+        }
+        res
+    }
+}
+
+fn ok_or_log(name: &str, fields: Result<Fields, String>) -> Fields {
+    fields.unwrap_or_else(|e| {
+        error!("failed to run plugin {}, error: {}", name, e);
+        Fields::new()
+    })
+}
+
+// the fields in a plugin's output, a JSON object or nothing
+fn fields(output: &[u8]) -> Result<Fields, String> {
+    if output.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Fields::new());
+    }
+    serde_json::from_slice(output).map_err(|e| format!("expected a JSON object: {}", e))
+}
+
+// run a command plugin with input on its stdin
+fn run(command: &[String], args: &[&str], input: &[u8]) -> Result<Fields, String> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let mut stdin = child.stdin.take().unwrap();
+    // written on a thread of its own so that a plugin printing before it has
+    // read all of its input doesn't block on a full pipe
+    let output = thread::scope(|scope| {
+        let writer = scope.spawn(move || match stdin.write_all(input) {
+            // a plugin needn't read its input
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            res => res,
+        });
+        let output = child.wait_with_output();
+        writer.join().expect("plugin writer panicked").and(output)
+    })
+    .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(output.status.to_string());
+    }
+    fields(&output.stdout)
+}
+
+#[cfg(feature = "wasm-plugins")]
+mod wasm {
+    use wasmi::{Engine, Instance, Linker, Memory, Store, TypedFunc};
+
+    use super::{fields, Fields};
+
+    pub struct Module {
+        store: Store<()>,
+        memory: Memory,
+        alloc: TypedFunc<i32, i32>,
+        slide: Option<TypedFunc<(i32, i32), i64>>,
+        media: Option<TypedFunc<(i32, i32, i32, i32), i64>>,
+    }
+
+    impl Module {
+        // compile and instantiate a module, which imports nothing
+        pub fn new(wasm: &[u8]) -> Result<Self, String> {
+            let engine = Engine::default();
+            let module = wasmi::Module::new(&engine, wasm).map_err(|e| e.to_string())?;
+            let mut store = Store::new(&engine, ());
+            let instance = Linker::new(&engine)
+                .instantiate_and_start(&mut store, &module)
+                .map_err(|e| e.to_string())?;
+            let memory = instance
+                .get_memory(&store, "memory")
+                .ok_or("no memory export")?;
+            let alloc = instance
+                .get_typed_func(&store, "alloc")
+                .map_err(|e| format!("alloc: {}", e))?;
+            Ok(Module {
+                slide: typed_func(&instance, &store, "slide")?,
+                media: typed_func(&instance, &store, "media")?,
+                store,
+                memory,
+                alloc,
+            })
+        }
+
+        pub fn slide(&mut self, json: &[u8]) -> Result<Fields, String> {
+            let slide = match self.slide {
+                Some(slide) => slide,
+                None => return Ok(Fields::new()),
+            };
+            let (ptr, len) = self.copy(json)?;
+            let res = slide
+                .call(&mut self.store, (ptr, len))
+                .map_err(|e| e.to_string())?;
+            self.fields(res)
+        }
+
+        pub fn media(&mut self, filename: &str, data: &[u8]) -> Result<Fields, String> {
+            let media = match self.media {
+                Some(media) => media,
+                None => return Ok(Fields::new()),
+            };
+            let (name_ptr, name_len) = self.copy(filename.as_bytes())?;
+            let (ptr, len) = self.copy(data)?;
+            let res = media
+                .call(&mut self.store, (name_ptr, name_len, ptr, len))
+                .map_err(|e| e.to_string())?;
+            self.fields(res)
+        }
+
+        // copy data into memory the module allocates
+        fn copy(&mut self, data: &[u8]) -> Result<(i32, i32), String> {
+            let len = i32::try_from(data.len()).map_err(|e| e.to_string())?;
+            let ptr = self
+                .alloc
+                .call(&mut self.store, len)
+                .map_err(|e| e.to_string())?;
+            self.memory
+                .write(&mut self.store, ptr as u32 as usize, data)
+                .map_err(|e| e.to_string())?;
+            Ok((ptr, len))
+        }
+
+        // read the fields a function returned the pointer and length of
+        fn fields(&self, res: i64) -> Result<Fields, String> {
+            if res == 0 {
+                return Ok(Fields::new());
+            }
+            let (ptr, len) = ((res as u64 >> 32) as usize, res as u32 as usize);
+            let mut output = vec![0; len];
+            self.memory
+                .read(&self.store, ptr, &mut output)
+                .map_err(|e| e.to_string())?;
+            fields(&output)
+        }
+    }
+
+    // an optional export, which has to have the right type if there is one
+    fn typed_func<Params, Results>(
+        instance: &Instance,
+        store: &Store<()>,
+        name: &str,
+    ) -> Result<Option<TypedFunc<Params, Results>>, String>
+    where
+        Params: wasmi::WasmParams,
+        Results: wasmi::WasmResults,
+    {
+        instance
+            .get_func(store, name)
+            .map(|func| func.typed(store))
+            .transpose()
+            .map_err(|e| format!("{}: {}", name, e))
+    }
+}
@@ -0,0 +1,131 @@
+use std::io::{self, Read};
+
+// larger movie headers than this are not read
+const MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+
+// what the container says about an MP4 or QuickTime file
+#[derive(Debug, Default)]
+pub struct MediaInfo {
+    pub duration_ms: Option<u64>,
+    // sample entry types of the tracks, e.g. avc1, hvc1, mp4a
+    pub codecs: Vec<String>,
+}
+
+// read the ISO base media boxes of r up to the moov box, skipping the media
+// data, and get the duration and codecs from it. Other formats give nothing.
+pub fn probe<R: Read>(r: &mut R) -> io::Result<MediaInfo> {
+    while let Some((kind, size)) = box_header(r)? {
+        let size = match size {
+            Some(size) => size,
+            // the last box, up to the end of the file
+            None => break,
+        };
+        if &kind != b"moov" {
+            io::copy(&mut r.take(size), &mut io::sink())?;
+            continue;
+        }
+        if size > MAX_MOOV_SIZE {
+            return Err(io::Error::other(format!(
+                "movie header of {} bytes is too large",
+                size
+            )));
+        }
+        let mut moov = Vec::with_capacity(size as usize);
+        r.take(size).read_to_end(&mut moov)?;
+        let mut info = MediaInfo::default();
+        parse_moov(&moov, &mut info);
+        return Ok(info);
+    }
+    Ok(MediaInfo::default())
+}
+
+// the type and the size of the content of the next box, None for the size of
+// a box that runs to the end, or None at the end
+fn box_header<R: Read>(r: &mut R) -> io::Result<Option<([u8; 4], Option<u64>)>> {
+    let mut header = [0u8; 8];
+    match r.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let kind = [header[4], header[5], header[6], header[7]];
+    let size = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+        0 => None,
+        1 => {
+            let mut large = [0u8; 8];
+            r.read_exact(&mut large)?;
+            Some(u64::from_be_bytes(large).saturating_sub(16))
+        }
+        size => Some((size as u64).saturating_sub(8)),
+    };
+    Ok(Some((kind, size)))
+}
+
+// the boxes within data as (type, content)
+fn boxes(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut res = Vec::new();
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let kind = [data[4], data[5], data[6], data[7]];
+        let (start, end) = match size {
+            0 => (8, data.len()),
+            1 if data.len() >= 16 => {
+                let mut large = [0u8; 8];
+                large.copy_from_slice(&data[8..16]);
+                (16, u64::from_be_bytes(large) as usize)
+            }
+            size => (8, size),
+        };
+        if end < start || end > data.len() {
+            break;
+        }
+        res.push((kind, &data[start..end]));
+        data = &data[end..];
+    }
+    res
+}
+
+fn parse_moov(moov: &[u8], info: &mut MediaInfo) {
+    for (kind, content) in boxes(moov) {
+        match &kind {
+            b"mvhd" => info.duration_ms = movie_duration(content),
+            b"trak" => {
+                // trak/mdia/minf/stbl/stsd, the first sample entry
+                let stsd =
+                    ["mdia", "minf", "stbl", "stsd"]
+                        .iter()
+                        .try_fold(content, |content, name| {
+                            boxes(content)
+                                .into_iter()
+                                .find(|(kind, _)| kind == name.as_bytes())
+                                .map(|(_, content)| content)
+                        });
+                // version and flags, the entry count, then the entries
+                if let Some((kind, _)) = stsd
+                    .and_then(|stsd| stsd.get(8..))
+                    .and_then(|entries| boxes(entries).into_iter().next())
+                {
+                    let codec = String::from_utf8_lossy(&kind).trim().to_owned();
+                    if !info.codecs.contains(&codec) {
+                        info.codecs.push(codec);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// the duration from mvhd, in the time scale it gives
+fn movie_duration(mvhd: &[u8]) -> Option<u64> {
+    let be = |range: std::ops::Range<usize>| -> Option<u64> {
+        let bytes = mvhd.get(range)?;
+        Some(bytes.iter().fold(0u64, |n, &b| n << 8 | b as u64))
+    };
+    // version 1 has 64-bit times and duration
+    let (timescale, duration) = match mvhd.first()? {
+        1 => (be(20..24)?, be(24..32)?),
+        _ => (be(12..16)?, be(16..20)?),
+    };
+    (timescale > 0).then(|| duration * 1000 / timescale)
+}
@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Timing {
+    // phase, or part of the package for entries
+    pub name: String,
+    // phase the entry was handled in, none for phases themselves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<&'static str>,
+    pub ms: f64,
+}
+
+// where the time of an extraction went
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Profile {
+    // zip decode, XML parsing and media writes, in the order they ran
+    pub phases: Vec<Timing>,
+    pub entries: Vec<Timing>,
+    pub total_ms: f64,
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl Profile {
+    // an entry handled since start
+    pub fn entry(&mut self, phase: &'static str, name: &str, start: Instant) {
+        self.entry_took(phase, name, start.elapsed());
+    }
+
+    // an entry handled elsewhere, e.g. on another thread
+    pub fn entry_took(&mut self, phase: &'static str, name: &str, duration: Duration) {
+        self.entries.push(Timing {
+            name: name.to_owned(),
+            phase: Some(phase),
+            ms: ms(duration),
+        });
+    }
+
+    pub fn phases(&mut self, phases: &[(&'static str, Duration)]) {
+        for (phase, duration) in phases {
+            self.phases.push(Timing {
+                name: phase.to_string(),
+                phase: None,
+                ms: ms(*duration),
+            });
+        }
+        self.total_ms = ms(phases.iter().map(|(_, duration)| *duration).sum());
+    }
+}
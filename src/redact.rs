@@ -0,0 +1,70 @@
+use std::{collections::BTreeMap, fs};
+
+use regex::Regex;
+
+use crate::shapes::{Paragraph, Shape};
+
+// patterns of text to keep out of the index, e.g. emails and phone numbers,
+// read from a file of `<name> <regex>` lines, blank lines and lines starting
+// with # aside. A match is replaced with [<name>].
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<(String, Regex)>,
+}
+
+impl Redactor {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut patterns = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, pattern) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("{}:{}: expected a name and a pattern", path, i + 1))?;
+            let regex = Regex::new(pattern.trim_start())
+                .map_err(|e| format!("{}:{}: {}", path, i + 1, e))?;
+            patterns.push((name.to_owned(), regex));
+        }
+        if patterns.is_empty() {
+            return Err(format!("{}: no patterns", path));
+        }
+        Ok(Redactor { patterns })
+    }
+
+    // replace the matches of each pattern in turn, counting them by name
+    pub fn redact(&self, text: &mut String, counts: &mut BTreeMap<String, u64>) {
+        for (name, regex) in &self.patterns {
+            let matches = regex.find_iter(text).count() as u64;
+            if matches == 0 {
+                continue;
+            }
+            *text = regex
+                .replace_all(text, format!("[{}]", name).as_str())
+                .into_owned();
+            *counts.entry(name.clone()).or_default() += matches;
+        }
+    }
+
+    pub fn redact_all(&self, texts: &mut [String], counts: &mut BTreeMap<String, u64>) {
+        texts.iter_mut().for_each(|text| self.redact(text, counts));
+    }
+
+    pub fn redact_paragraphs(
+        &self,
+        paragraphs: &mut [Paragraph],
+        counts: &mut BTreeMap<String, u64>,
+    ) {
+        for paragraph in paragraphs {
+            self.redact(&mut paragraph.text, counts);
+        }
+    }
+
+    pub fn redact_shapes(&self, shapes: &mut [Shape], counts: &mut BTreeMap<String, u64>) {
+        for shape in shapes {
+            self.redact_paragraphs(&mut shape.paragraphs, counts);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use log::{debug, trace};
+#[cfg(any(feature = "gcs", feature = "azure"))]
+use std::env;
+use std::io::{self, Read, Seek, SeekFrom};
+
+#[cfg(any(feature = "gcs", feature = "azure"))]
+use crate::http::uri_encode;
+#[cfg(feature = "s3")]
+use crate::s3;
+use crate::ExportMediaError;
+
+#[cfg(feature = "gcs")]
+pub const GCS_SCHEME: &str = "gs://";
+#[cfg(feature = "azure")]
+pub const AZURE_SCHEME: &str = "az://";
+// how much is fetched a request, the zip reader seeking back and forth within
+// an entry being served from the last block
+const BLOCK_SIZE: u64 = 4 * 1024 * 1024;
+// the Blob service version the requests are made for
+#[cfg(feature = "azure")]
+const AZURE_VERSION: &str = "2021-08-06";
+
+// whether url is of an object store, s3://, gs:// or az://, for those built in
+pub fn is_remote(url: &str) -> bool {
+    #[cfg(feature = "s3")]
+    if url.starts_with(s3::SCHEME) {
+        return true;
+    }
+    #[cfg(feature = "gcs")]
+    if url.starts_with(GCS_SCHEME) {
+        return true;
+    }
+    #[cfg(feature = "azure")]
+    if url.starts_with(AZURE_SCHEME) {
+        return true;
+    }
+    false
+}
+
+// where an object is and how to ask for it
+enum Object {
+    #[cfg(feature = "s3")]
+    S3(s3::Client, String),
+    // https url of the object, and a token to send as the bearer if any
+    #[cfg(feature = "gcs")]
+    Gcs(String, Option<String>),
+    // https url of the object, a SAS token appended to it if any
+    #[cfg(feature = "azure")]
+    Azure(String),
+}
+
+impl Object {
+    fn parse(url: &str) -> Result<Self, ExportMediaError> {
+        #[cfg(feature = "s3")]
+        if let Some(rest) = url.strip_prefix(s3::SCHEME) {
+            let (bucket, key) = split_object(url, rest)?;
+            let client = s3::Client::from_env(&format!("{}{}", s3::SCHEME, bucket))?;
+            return Ok(Object::S3(client, key.to_owned()));
+        }
+        // gs://bucket/object, with a token from gcloud auth print-access-token
+        #[cfg(feature = "gcs")]
+        if let Some(rest) = url.strip_prefix(GCS_SCHEME) {
+            let (bucket, object) = split_object(url, rest)?;
+            let endpoint = var("STORAGE_EMULATOR_HOST")
+                .map(|host| host.trim_end_matches('/').to_owned())
+                .unwrap_or_else(|| String::from("https://storage.googleapis.com"));
+            return Ok(Object::Gcs(
+                format!("{}/{}/{}", endpoint, uri_encode(bucket), uri_encode(object)),
+                var("GOOGLE_OAUTH_ACCESS_TOKEN"),
+            ));
+        }
+        // az://container/blob of the AZURE_STORAGE_ACCOUNT account
+        #[cfg(feature = "azure")]
+        if let Some(rest) = url.strip_prefix(AZURE_SCHEME) {
+            let (container, blob) = split_object(url, rest)?;
+            let endpoint = match var("AZURE_STORAGE_ENDPOINT") {
+                Some(endpoint) => endpoint.trim_end_matches('/').to_owned(),
+                None => format!(
+                    "https://{}.blob.core.windows.net",
+                    var("AZURE_STORAGE_ACCOUNT")
+                        .ok_or_else(|| String::from("AZURE_STORAGE_ACCOUNT is not set"))?
+                ),
+            };
+            let mut url = format!(
+                "{}/{}/{}",
+                endpoint,
+                uri_encode(container),
+                uri_encode(blob)
+            );
+            if let Some(sas_token) = var("AZURE_STORAGE_SAS_TOKEN") {
+                url.push('?');
+                url.push_str(sas_token.trim_start_matches('?'));
+            }
+            return Ok(Object::Azure(url));
+        }
+        Err(format!("{} is not an object store url", url).into())
+    }
+
+    fn request(&self, method: &str) -> ureq::Request {
+        match self {
+            #[cfg(feature = "s3")]
+            Object::S3(client, key) => client.signed(method, key),
+            #[cfg(feature = "gcs")]
+            Object::Gcs(url, token) => {
+                let request = ureq::request(method, url);
+                match token {
+                    Some(token) => request.set("authorization", &format!("Bearer {}", token)),
+                    None => request,
+                }
+            }
+            #[cfg(feature = "azure")]
+            Object::Azure(url) => ureq::request(method, url).set("x-ms-version", AZURE_VERSION),
+        }
+    }
+}
+
+// the bucket or container of an object url and the key in it
+fn split_object<'a>(url: &str, rest: &'a str) -> Result<(&'a str, &'a str), ExportMediaError> {
+    match rest.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok((bucket, key)),
+        _ => Err(format!("{} is not of an object, expected {{bucket}}/{{key}}", url).into()),
+    }
+}
+
+// an object in an object store read with ranged GETs, a block at a time, so a
+// presentation is extracted without downloading what isn't needed of it
+pub struct Remote {
+    url: String,
+    object: Object,
+    len: u64,
+    pos: u64,
+    // the last block fetched and where in the object it starts
+    block_start: u64,
+    block: Vec<u8>,
+}
+
+impl Remote {
+    // look up the size of the object at url
+    pub fn open(url: &str) -> Result<Self, ExportMediaError> {
+        let object = Object::parse(url)?;
+        let response = object
+            .request("HEAD")
+            .call()
+            .map_err(|e| format!("failed to look up {}: {}", url, status(e)))?;
+        let len = response
+            .header("content-length")
+            .and_then(|length| length.parse::<u64>().ok())
+            .ok_or_else(|| format!("{} has no content-length", url))?;
+        debug!("opened {}, {} bytes", url, len);
+        Ok(Remote {
+            url: url.to_owned(),
+            object,
+            len,
+            pos: 0,
+            block_start: 0,
+            block: Vec::new(),
+        })
+    }
+
+    // fetch the block from pos
+    fn fetch(&mut self) -> io::Result<()> {
+        let end = (self.pos + BLOCK_SIZE).min(self.len);
+        let response = self
+            .object
+            .request("GET")
+            .set("range", &format!("bytes={}-{}", self.pos, end - 1))
+            .call()
+            .map_err(|e| io::Error::other(format!("failed to read {}: {}", self.url, status(e))))?;
+        // a whole object in reply to a range would be read from its start
+        if response.status() != 206 {
+            return Err(io::Error::other(format!(
+                "{} doesn't support ranged requests, status {}",
+                self.url,
+                response.status()
+            )));
+        }
+        let mut block = Vec::with_capacity((end - self.pos) as usize);
+        response
+            .into_reader()
+            .take(end - self.pos)
+            .read_to_end(&mut block)?;
+        if block.len() as u64 != end - self.pos {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "{} ended early at {}",
+                    self.url,
+                    self.pos + block.len() as u64
+                ),
+            ));
+        }
+        trace!("read {} bytes of {} at {}", block.len(), self.url, self.pos);
+        self.block_start = self.pos;
+        self.block = block;
+        Ok(())
+    }
+}
+
+impl Read for Remote {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let block_end = self.block_start + self.block.len() as u64;
+        if self.pos < self.block_start || self.pos >= block_end {
+            self.fetch()?;
+        }
+        let offset = (self.pos - self.block_start) as usize;
+        let n = buf.len().min(self.block.len() - offset);
+        buf[..n].copy_from_slice(&self.block[offset..offset + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for Remote {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        self.pos = pos
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the start"))?;
+        Ok(self.pos)
+    }
+}
+
+// an environment variable that is set and not empty
+#[cfg(any(feature = "gcs", feature = "azure"))]
+fn var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// the status of a failed request, or what failed in sending it
+fn status(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(status, _) => format!("status {}", status),
+        ureq::Error::Transport(transport) => transport.kind().to_string(),
+    }
+}
@@ -0,0 +1,34 @@
+use std::io::Cursor;
+
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, ImageFormat, ImageReader};
+
+// JPEG quality of downscaled images
+const JPEG_QUALITY: u8 = 90;
+
+// downscale an image whose width or height is more than max_dimension to fit,
+// keeping its aspect ratio and format. Images that fit, and formats that
+// can't be decoded, such as vector images, are left as they are, none.
+pub fn downscale(data: &[u8], max_dimension: u32) -> Result<Option<Vec<u8>>, String> {
+    let format = match image::guess_format(data) {
+        Ok(format) if format.writing_enabled() => format,
+        _ => return Ok(None),
+    };
+    let reader = ImageReader::with_format(Cursor::new(data), format);
+    let (width, height) = reader.into_dimensions().map_err(|e| e.to_string())?;
+    if width <= max_dimension && height <= max_dimension {
+        return Ok(None);
+    }
+    let img = image::load_from_memory_with_format(data, format).map_err(|e| e.to_string())?;
+    let img = img.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    let mut res = Vec::new();
+    match format {
+        // JPEGs are written at the quality photos are usually saved at rather
+        // than the encoder's default
+        ImageFormat::Jpeg => img
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut res, JPEG_QUALITY)),
+        _ => img.write_to(&mut Cursor::new(&mut res), format),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(Some(res))
+}
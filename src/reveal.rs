@@ -0,0 +1,73 @@
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use crate::SingleRes;
+
+pub const REVEAL_FILE: &str = "reveal.html";
+// reveal.js is loaded from a CDN, so that the deck is one file next to the
+// media
+const REVEAL_URL: &str = "https://cdn.jsdelivr.net/npm/reveal.js@5";
+// image formats browsers show, EMF and WMF drawings and TIFF being left out
+const WEB_IMAGES: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp", "avif"];
+
+// a reveal.js deck of the slides, a section each with the first text as its
+// heading, the rest as paragraphs and then the images and videos, which are
+// linked by the names they were exported as
+pub fn reveal(title: &str, slides: &BTreeMap<u32, SingleRes>) -> String {
+    let mut res = String::new();
+    res.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    writeln!(res, "<title>{}</title>", escape(title)).unwrap();
+    for css in ["dist/reveal.css", "dist/theme/white.css"] {
+        writeln!(
+            res,
+            "<link rel=\"stylesheet\" href=\"{}/{}\">",
+            REVEAL_URL, css
+        )
+        .unwrap();
+    }
+    res.push_str(
+        "<style>.reveal section img, .reveal section video { max-height: 50vh; }</style>\n",
+    );
+    res.push_str("</head>\n<body>\n<div class=\"reveal\">\n<div class=\"slides\">\n");
+    for single_res in slides.values() {
+        writeln!(res, "<section data-slide=\"{}\">", single_res.page_no).unwrap();
+        let mut texts = single_res.texts.iter();
+        if let Some(heading) = texts.next() {
+            writeln!(res, "<h2>{}</h2>", escape(heading)).unwrap();
+        }
+        for text in texts {
+            writeln!(res, "<p>{}</p>", escape(text)).unwrap();
+        }
+        for image in single_res.images.iter().filter(|image| is_web_image(image)) {
+            writeln!(res, "<img src=\"{}\" alt=\"\">", escape(image)).unwrap();
+        }
+        for video in &single_res.videos {
+            if let Some(media) = &video.media {
+                writeln!(res, "<video src=\"{}\" controls></video>", escape(media)).unwrap();
+            }
+        }
+        res.push_str("</section>\n");
+    }
+    res.push_str("</div>\n</div>\n");
+    writeln!(
+        res,
+        "<script src=\"{}/dist/reveal.js\"></script>",
+        REVEAL_URL
+    )
+    .unwrap();
+    res.push_str("<script>Reveal.initialize({ hash: true });</script>\n</body>\n</html>\n");
+    res
+}
+
+pub(crate) fn is_web_image(image: &str) -> bool {
+    Path::new(image)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| WEB_IMAGES.contains(&ext.as_str()))
+}
+
+pub(crate) fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
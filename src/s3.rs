@@ -0,0 +1,259 @@
+use log::{debug, error, trace};
+use std::{
+    env,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{http::uri_encode, ExportMediaError};
+
+pub const SCHEME: &str = "s3://";
+const DEFAULT_REGION: &str = "us-east-1";
+// hex sha256 of an empty payload, for requests without a body
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+// an S3 bucket and key prefix, with credentials and endpoint from the usual
+// AWS_* environment variables
+#[derive(Debug, Clone)]
+pub struct Client {
+    // scheme and host the requests go to
+    endpoint: String,
+    host: String,
+    // bucket in the path rather than the host, for S3-compatible endpoints
+    path_style: bool,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    bucket: String,
+    prefix: String,
+}
+
+impl Client {
+    // url is s3://bucket/prefix/
+    pub fn from_env(url: &str) -> Result<Self, ExportMediaError> {
+        let rest = url
+            .strip_prefix(SCHEME)
+            .ok_or_else(|| format!("{} is not an {} url", url, SCHEME))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("{} has no bucket", url).into());
+        }
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+        let region = var("AWS_REGION")
+            .or_else(|| var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|| String::from(DEFAULT_REGION));
+        let (endpoint, path_style) = match var("AWS_ENDPOINT_URL") {
+            Some(endpoint) => (endpoint.trim_end_matches('/').to_owned(), true),
+            None => (
+                format!("https://{}.s3.{}.amazonaws.com", bucket, region),
+                false,
+            ),
+        };
+        let host = endpoint
+            .split_once("://")
+            .map_or(endpoint.as_str(), |(_, host)| host)
+            .to_owned();
+        let mut prefix = prefix.to_owned();
+        if !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+        Ok(Client {
+            endpoint,
+            host,
+            path_style,
+            region,
+            access_key: var("AWS_ACCESS_KEY_ID")
+                .ok_or_else(|| String::from("AWS_ACCESS_KEY_ID is not set"))?,
+            secret_key: var("AWS_SECRET_ACCESS_KEY")
+                .ok_or_else(|| String::from("AWS_SECRET_ACCESS_KEY is not set"))?,
+            session_token: var("AWS_SESSION_TOKEN"),
+            bucket: bucket.to_owned(),
+            prefix,
+        })
+    }
+
+    pub fn url(&self, name: &str) -> String {
+        format!("{}{}/{}{}", SCHEME, self.bucket, self.prefix, name)
+    }
+
+    pub fn put(&self, name: &str, data: &[u8]) -> Result<(), ExportMediaError> {
+        let payload_hash = format!("{:x}", Sha256::digest(data));
+        self.request("PUT", name, &payload_hash)
+            .send_bytes(data)
+            .map_err(|e| format!("failed to upload {}: {}", self.url(name), e))?;
+        trace!("uploaded {}", self.url(name));
+        Ok(())
+    }
+
+    pub fn exists(&self, name: &str) -> Result<bool, ExportMediaError> {
+        match self.signed("HEAD", name).call() {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::Status(404, _)) => Ok(false),
+            Err(e) => Err(format!("failed to look up {}: {}", self.url(name), e).into()),
+        }
+    }
+
+    // a request without a body, a GET or HEAD, for the object name
+    pub fn signed(&self, method: &str, name: &str) -> ureq::Request {
+        self.request(method, name, EMPTY_SHA256)
+    }
+
+    // a request signed with AWS signature version 4
+    fn request(&self, method: &str, name: &str, payload_hash: &str) -> ureq::Request {
+        let key = uri_encode(&format!("{}{}", self.prefix, name));
+        let path = if self.path_style {
+            format!("/{}/{}", uri_encode(&self.bucket), key)
+        } else {
+            format!("/{}", key)
+        };
+        let (date, time) = utc_now();
+        let amz_date = format!("{}T{}Z", date, time);
+        let mut headers = vec![
+            ("host", self.host.clone()),
+            ("x-amz-content-sha256", payload_hash.to_owned()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(session_token) = &self.session_token {
+            headers.push(("x-amz-security-token", session_token.clone()));
+        }
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method, path, canonical_headers, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+            amz_date,
+            scope,
+            Sha256::digest(canonical_request.as_bytes())
+        );
+        let mut key = hmac(
+            format!("AWS4{}", self.secret_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        let signature: String = hmac(&key, string_to_sign.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        debug!("{} {}{}", method, self.endpoint, path);
+
+        let mut request = ureq::request(method, &format!("{}{}", self.endpoint, path));
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.set(name, value);
+        }
+        request.set(
+            "authorization",
+            &format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key, scope, signed_headers, signature
+            ),
+        )
+    }
+}
+
+// uploads files on a pool of threads
+pub struct Uploader {
+    sender: mpsc::Sender<(String, Vec<u8>)>,
+    workers: Vec<thread::JoinHandle<usize>>,
+}
+
+impl Uploader {
+    pub fn new(client: Client, concurrency: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<(String, Vec<u8>)>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..concurrency.max(1))
+            .map(|_| {
+                let client = client.clone();
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || {
+                    let mut failed = 0;
+                    loop {
+                        let next = receiver.lock().unwrap().recv();
+                        let (name, data) = match next {
+                            Ok(next) => next,
+                            Err(_) => return failed,
+                        };
+                        if let Err(e) = client.put(&name, &data) {
+                            error!("{}", e);
+                            failed += 1;
+                        }
+                    }
+                })
+            })
+            .collect();
+        Uploader { sender, workers }
+    }
+
+    pub fn upload(&self, name: String, data: Vec<u8>) {
+        // the workers only go away once the sender is dropped
+        self.sender.send((name, data)).unwrap();
+    }
+
+    // wait for the queued uploads
+    pub fn finish(self) -> Result<(), ExportMediaError> {
+        drop(self.sender);
+        let mut failed = 0;
+        for worker in self.workers {
+            failed += worker.join().unwrap_or(1);
+        }
+        match failed {
+            0 => Ok(()),
+            failed => Err(format!("{} uploads failed", failed).into()),
+        }
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(data);
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+// (YYYYMMDD, HHMMSS) in UTC
+fn utc_now() -> (String, String) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+    // civil date from days since 1970-01-01, Howard Hinnant's algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (
+        format!("{:04}{:02}{:02}", year, month, day),
+        format!("{:02}{:02}{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+    )
+}
@@ -0,0 +1,81 @@
+use log::{debug, warn};
+use std::io::{Cursor, Read, Write};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{limits::Limited, ExportMediaError};
+
+const LOCAL_FILE_HEADER: &[u8] = b"PK\x03\x04";
+
+// marks an index written from what could be recovered of a corrupt archive
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Partial {
+    // why the archive couldn't be read as a whole
+    pub error: String,
+    // entries that were read in full
+    pub recovered: Vec<String>,
+    // entries whose data is cut short or corrupt
+    pub unreadable: Vec<String>,
+}
+
+// read every entry with an intact local file header and data out of data,
+// into a new archive in memory of at most max_size bytes
+pub fn salvage(
+    data: &[u8],
+    error: String,
+    max_size: u64,
+) -> Result<(Vec<u8>, Partial), ExportMediaError> {
+    let mut partial = Partial {
+        error,
+        recovered: Vec::new(),
+        unreadable: Vec::new(),
+    };
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut offset = 0;
+    let mut total = 0;
+    while let Some(found) = data[offset..]
+        .windows(LOCAL_FILE_HEADER.len())
+        .position(|window| window == LOCAL_FILE_HEADER)
+    {
+        let start = offset + found;
+        offset = start + LOCAL_FILE_HEADER.len();
+        let mut reader = &data[start..];
+        let mut file = match zip::read::read_zipfile_from_stream(&mut reader) {
+            Ok(Some(file)) => file,
+            Ok(None) => continue,
+            Err(e) => {
+                debug!("no entry at offset {}, error: {}", start, e);
+                continue;
+            }
+        };
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_owned();
+        let mut content = Vec::new();
+        let mut limited = Limited::new(&mut file, max_size - total);
+        if let Err(e) = limited.read_to_end(&mut content) {
+            warn!("failed to salvage {}, error: {}", name, e);
+            partial.unreadable.push(name);
+            continue;
+        }
+        if partial.recovered.contains(&name) {
+            continue;
+        }
+        writer
+            .start_file(name.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        writer.write_all(&content)?;
+        total += content.len() as u64;
+        debug!("salvaged {}, {} bytes", name, content.len());
+        partial.recovered.push(name);
+    }
+    if partial.recovered.is_empty() {
+        return Err(format!("nothing to salvage, error: {}", partial.error).into());
+    }
+    let cursor = writer.finish().map_err(|e| e.to_string())?;
+    Ok((cursor.into_inner(), partial))
+}
@@ -0,0 +1,80 @@
+use std::collections::{BTreeMap, HashSet};
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+// names Windows reserves for devices, whatever the extension
+const RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+// stems are cut to this many chars, leaving room for a suffix and extension
+const MAX_STEM_LEN: usize = 200;
+// the stem of names with nothing left after sanitizing
+const FALLBACK_STEM: &str = "media";
+
+// sanitize the names media parts are exported as so that they are valid on
+// Windows and safe as S3 keys, and unique regardless of case. Takes media
+// part -> name and returns media part -> sanitized name, in the given order
+// so that the first of the names that end up the same keeps it.
+pub fn names(names: Vec<(String, String)>) -> BTreeMap<String, String> {
+    let mut taken = HashSet::new();
+    let mut res = BTreeMap::new();
+    for (part_name, name) in names {
+        let sanitized = name.split('/').map(component).collect::<Vec<_>>().join("/");
+        // the extension of the last component
+        let (stem, ext) = match sanitized.rsplit_once('.') {
+            Some((stem, ext)) if !ext.contains('/') => (stem, Some(ext)),
+            _ => (sanitized.as_str(), None),
+        };
+        let mut unique = sanitized.clone();
+        let mut n = 1;
+        while !taken.insert(unique.to_lowercase()) {
+            n += 1;
+            unique = match ext {
+                Some(ext) => format!("{}-{}.{}", stem, n, ext),
+                None => format!("{}-{}", stem, n),
+            };
+        }
+        res.insert(part_name, unique);
+    }
+    res
+}
+
+// a path component of ASCII letters, digits, '.', '-' and '_', accents
+// dropped and anything else replaced by '_'
+fn component(name: &str) -> String {
+    let mut res = String::with_capacity(name.len());
+    for c in name.nfkd().filter(|c| !is_combining_mark(*c)) {
+        let c = match c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+            true => c,
+            false => '_',
+        };
+        // a run of replaced chars is one '_'
+        if c != '_' || !res.ends_with('_') {
+            res.push(c);
+        }
+    }
+    let (stem, ext) = match res.rsplit_once('.') {
+        Some((stem, ext)) => (stem, Some(ext.trim_matches('_'))),
+        None => (res.as_str(), None),
+    };
+    // leading dots hide files and Windows drops trailing ones
+    let mut stem: String = stem
+        .trim_matches(|c| c == '_' || c == '.')
+        .chars()
+        .take(MAX_STEM_LEN)
+        .collect();
+    if stem.is_empty() {
+        stem = FALLBACK_STEM.to_owned();
+    }
+    if RESERVED
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        stem.insert(0, '_');
+    }
+    match ext {
+        Some(ext) if !ext.is_empty() => format!("{}.{}", stem, ext),
+        _ => stem,
+    }
+}
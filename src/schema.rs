@@ -0,0 +1,17 @@
+use schemars::generate::SchemaSettings;
+
+use crate::{ExportMediaError, Res};
+
+// print the JSON Schema of index.json, draft 2020-12, describing the index as
+// it is written so that skipped fields are optional
+pub fn run() -> Result<(), ExportMediaError> {
+    let schema = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<Res>();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema).map_err(|e| e.to_string())?
+    );
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use log::{error, trace};
+use std::{collections::HashSet, fs, io, path::Path};
+
+use clap::Args;
+use regex::{Regex, RegexBuilder};
+
+use crate::{errors::Errors, package, slide, ExportMediaError};
+
+// chars of context shown on each side of a match
+const SNIPPET_CONTEXT: usize = 40;
+
+#[derive(Args)]
+pub struct SearchArgs {
+    /// Regular expression to look for in slide and notes text
+    #[arg(short, long)]
+    pattern: String,
+
+    /// Match case-insensitively
+    #[arg(long)]
+    ignore_case: bool,
+
+    /// Input file
+    input_file: String,
+}
+
+#[derive(Debug)]
+struct Hit {
+    page_no: u32,
+    notes: bool,
+    snippet: String,
+}
+
+// search slide and notes text without extracting anything, returns whether
+// anything matched
+pub fn run(args: &SearchArgs) -> Result<bool, ExportMediaError> {
+    let re = RegexBuilder::new(&args.pattern)
+        .case_insensitive(args.ignore_case)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let archivef = fs::File::open(Path::new(&args.input_file))?;
+    let mut archive =
+        zip::ZipArchive::new(io::BufReader::new(archivef)).map_err(|e| e.to_string())?;
+    let pkg = package::discover(&mut archive, &mut Errors::default())?;
+
+    let mut parts = Vec::new();
+    for (i, slide_part) in pkg.slides.iter().enumerate() {
+        let page_no = i as u32 + 1;
+        parts.push((slide_part, page_no, false));
+        if let Some(notes_part) = pkg.notes.get(slide_part) {
+            parts.push((notes_part, page_no, true));
+        }
+    }
+    let mut hits = Vec::new();
+    for (part_name, page_no, notes) in parts {
+        let file = archive.by_name(part_name).map_err(|e| e.to_string())?;
+        let texts = match slide(file, &[], &HashSet::new()) {
+            Ok(content) => content.texts,
+            Err(e) => {
+                error!("failed to get text of {}, error: {}", part_name, e);
+                continue;
+            }
+        };
+        for text in texts {
+            if let Some(snippet) = snippet(&re, &text) {
+                trace!("{} matched: {}", part_name, text);
+                hits.push(Hit {
+                    page_no,
+                    notes,
+                    snippet,
+                });
+            }
+        }
+    }
+
+    hits.sort_by_key(|hit| (hit.page_no, hit.notes));
+    for hit in &hits {
+        let kind = if hit.notes { "notes" } else { "slide" };
+        println!("{} {}: {}", kind, hit.page_no, hit.snippet);
+    }
+    Ok(!hits.is_empty())
+}
+
+// the first match in text with some context around it
+fn snippet(re: &Regex, text: &str) -> Option<String> {
+    let m = re.find(text)?;
+    let start = text[..m.start()]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let end = text[m.end()..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map(|(i, _)| m.end() + i)
+        .unwrap_or(text.len());
+    let mut snippet = text[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if start > 0 {
+        snippet.insert_str(0, "...");
+    }
+    if end < text.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
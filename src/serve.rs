@@ -0,0 +1,349 @@
+use log::{error, info, warn};
+use std::{
+    io::{self, BufRead, BufReader, Cursor, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use clap::{Args, FromArgMatches};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    config::Config,
+    limits::{self, Deadline, Timed},
+    ExportMediaError, ExtractArgs, INDEX_FILE,
+};
+
+// the longest request or header line, and the most header lines, taken
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// Refuse presentations larger than this many bytes
+    #[arg(long, default_value_t = 256 * 1024 * 1024)]
+    max_body_size: u64,
+
+    /// Answer 503 to connections beyond this many at once
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u32).range(1..))]
+    max_connections: u32,
+
+    /// Drop connections that take longer than this to send the request or read the
+    /// response, e.g. 30s or 2m
+    #[arg(long, default_value = "60s", value_parser = limits::parse_duration)]
+    request_timeout: Duration,
+}
+
+// the extract flags a query may give, those that only shape what is extracted.
+// Limits, timeouts and anything reading files or running commands on the server
+// come from its config alone.
+const QUERY_FLAGS: &[&str] = &[
+    "fonts",
+    "normalize",
+    "trim-text",
+    "detect-lang",
+    "stats",
+    "paragraphs",
+    "shapes",
+    "equations",
+    "mathml",
+    "resolve-inherited",
+    "sanitize-names",
+    "fix-extensions",
+    "strip-metadata",
+    "exif-report",
+    "anonymize",
+    "srt",
+    "reveal",
+    "shapes-svg",
+    "chart-data",
+    "bundle",
+    "chunks",
+    "max-tokens",
+    "graph",
+    "layout",
+    "no-masters",
+    "exclude-prompt-text",
+    "probe-media",
+    "max-dimension",
+    "contact-sheet",
+    "dedupe-similar",
+    "grep",
+    "exclude-placeholders",
+    "enable",
+    "disable",
+    "vba",
+    "md5",
+    "name-template",
+    "notes",
+    "keywords",
+    "salvage",
+];
+
+// an error response, status and message
+struct Failure(u16, String);
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+                Failure(408, String::from("timed out reading the request"))
+            }
+            _ => Failure(400, e.to_string()),
+        }
+    }
+}
+
+// a connection being handled, counted until it's dropped
+struct Connection(Arc<AtomicUsize>);
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// serve extractions, a thread per connection up to max_connections. POST a presentation to
+// /extract for a zip of the media and index.json, or to /index for only the
+// index. The query string takes the extract flags, e.g. /extract?stats&md5
+pub fn run(args: &ServeArgs, config: Config) -> Result<(), ExportMediaError> {
+    let listener = TcpListener::bind(&args.listen)?;
+    info!("listening on {}", listener.local_addr()?);
+    let config = Arc::new(config);
+    let connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("failed to accept connection, error: {}", e);
+                continue;
+            }
+        };
+        // a client that stops reading or writing lets go of its thread
+        if let Err(e) = stream
+            .set_read_timeout(Some(args.request_timeout))
+            .and_then(|_| stream.set_write_timeout(Some(args.request_timeout)))
+        {
+            error!("failed to set the timeouts of a connection, error: {}", e);
+            continue;
+        }
+        if connections.fetch_add(1, Ordering::Relaxed) >= args.max_connections as usize {
+            connections.fetch_sub(1, Ordering::Relaxed);
+            warn!("refusing a connection, {} already", args.max_connections);
+            let body = serde_json::json!({ "error": "too many connections" }).to_string();
+            if let Err(e) = write_response(&mut stream, 503, "application/json", body.as_bytes()) {
+                error!("failed to respond, error: {}", e);
+            }
+            continue;
+        }
+        let connection = Connection(Arc::clone(&connections));
+        let config = Arc::clone(&config);
+        let max_body_size = args.max_body_size;
+        let deadline = Deadline::new(Some(args.request_timeout));
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &config, max_body_size, deadline) {
+                error!("failed to respond, error: {}", e);
+            }
+            drop(connection);
+        });
+    }
+    Ok(())
+}
+
+fn handle(
+    mut stream: TcpStream,
+    config: &Config,
+    max_body_size: u64,
+    deadline: Deadline,
+) -> io::Result<()> {
+    let (content_type, body) = match respond(&mut stream, config, max_body_size, deadline) {
+        Ok(response) => response,
+        Err(Failure(status, message)) => {
+            info!("{} {}", status, message);
+            let body = serde_json::json!({ "error": message }).to_string();
+            return write_response(&mut stream, status, "application/json", body.as_bytes());
+        }
+    };
+    write_response(&mut stream, 200, content_type, &body)
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    config: &Config,
+    max_body_size: u64,
+    deadline: Deadline,
+) -> Result<(&'static str, Vec<u8>), Failure> {
+    // the whole request is to arrive in time, not only each read of it
+    let mut reader = BufReader::new(Timed::new(stream, deadline));
+    let mut line = String::new();
+    read_line(&mut reader, &mut line, 414)?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_owned(), target.to_owned()),
+        _ => return Err(Failure(400, String::from("invalid request line"))),
+    };
+    let mut length = None;
+    for n in 0.. {
+        if read_line(&mut reader, &mut line, 431)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if n == MAX_HEADERS {
+            return Err(Failure(431, String::from("too many header lines")));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<u64>().ok();
+            }
+        }
+    }
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    if path != "/extract" && path != "/index" {
+        return Err(Failure(404, format!("no such path {}", path)));
+    }
+    if method != "POST" {
+        return Err(Failure(405, format!("{} {} is not allowed", method, path)));
+    }
+    let length = length.ok_or_else(|| Failure(411, String::from("missing content-length")))?;
+    if length > max_body_size {
+        return Err(Failure(
+            413,
+            format!("body is larger than the limit of {} bytes", max_body_size),
+        ));
+    }
+    let args = extract_args(query, config)?;
+    // grown as the body arrives rather than sized by what the client claims
+    let mut data = Vec::new();
+    reader.take(length).read_to_end(&mut data)?;
+    info!("{} {}, {} bytes", method, target, data.len());
+
+    let extraction = ppt_img_extract::extract_from_bytes(&data, crate::options(&args))
+        .map_err(|e| Failure(422, e.to_string()))?;
+    let j = serde_json::to_string_pretty(&extraction.res).unwrap();
+    if path == "/index" {
+        return Ok(("application/json", j.into_bytes()));
+    }
+    let mut files = extraction.files;
+    files.insert(INDEX_FILE.to_owned(), j.into_bytes());
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        writer
+            .start_file(name, SimpleFileOptions::default())
+            .map_err(|e| Failure(500, e.to_string()))?;
+        writer
+            .write_all(&data)
+            .map_err(|e| Failure(500, e.to_string()))?;
+    }
+    let zipped = writer.finish().map_err(|e| Failure(500, e.to_string()))?;
+    Ok(("application/zip", zipped.into_inner()))
+}
+
+// read a line of at most MAX_LINE bytes into line, failing with status for
+// a longer one
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String, status: u16) -> Result<usize, Failure> {
+    line.clear();
+    let n = reader.take(MAX_LINE as u64).read_line(line)?;
+    if n == MAX_LINE && !line.ends_with('\n') {
+        return Err(Failure(
+            status,
+            String::from("request line or header too long"),
+        ));
+    }
+    Ok(n)
+}
+
+// the extract flags of the query string, "stats&name-template=..." for
+// --stats --name-template=..., with the config for the rest. The input file is
+// the request body, so a placeholder stands in for it.
+fn extract_args(query: &str, config: &Config) -> Result<ExtractArgs, Failure> {
+    let mut argv = vec![String::from("--input-file=-")];
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let pair = decode(pair);
+        let name = pair.split_once('=').map_or(pair.as_str(), |(name, _)| name);
+        if !QUERY_FLAGS.contains(&name) {
+            return Err(Failure(
+                400,
+                format!("--{} is not allowed when serving", name),
+            ));
+        }
+        argv.push(format!("--{}", pair));
+    }
+    let command = ExtractArgs::augment_args(clap::Command::new("extract").no_binary_name(true));
+    let matches = command.try_get_matches_from(argv).map_err(invalid_query)?;
+    let mut args = ExtractArgs::from_arg_matches(&matches).map_err(invalid_query)?;
+    config.clone().apply(&mut args, &matches);
+    Ok(args)
+}
+
+// the first line of a clap error, without the usage
+fn invalid_query(e: clap::Error) -> Failure {
+    let message = e.render().to_string();
+    let line = message.lines().next().unwrap_or_default();
+    Failure(400, line.trim_start_matches("error: ").to_owned())
+}
+
+// percent-decode a query string pair, + is a space
+fn decode(s: &str) -> String {
+    let mut res = Vec::new();
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'+' => res.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(b) => res.push(b),
+                    None => {
+                        res.push(b'%');
+                        res.extend(hex);
+                    }
+                }
+            }
+            b => res.push(b),
+        }
+    }
+    String::from_utf8_lossy(&res).into_owned()
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        414 => "URI Too Long",
+        422 => "Unprocessable Content",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
@@ -0,0 +1,753 @@
+use std::{collections::HashSet, ops::Range};
+
+use roxmltree::{Document, Node};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    text::{self, Direction, TextOptions},
+    xml::{
+        NodeExt, NS_DRAWINGML as A, NS_MARKUP_COMPATIBILITY as MC, NS_POWERPOINT_2010 as P14,
+        NS_PRESENTATIONML as P, NS_RELATIONSHIPS as R,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Paragraph {
+    // bullet indent level from a:pPr@lvl, 0 being the outermost
+    pub level: u32,
+    pub text: String,
+    // from a:pPr@rtl, or the text itself when the paragraph doesn't say
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Shape {
+    // from the shape's non-visual properties p:cNvPr
+    pub id: u32,
+    pub name: String,
+    // placeholder type from p:ph@type (title, body, ftr, sldNum, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+    pub paragraphs: Vec<Paragraph>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Picture {
+    pub id: u32,
+    pub name: String,
+    pub rel_id: String,
+    // media file name, resolved through the part's rels
+    pub image: Option<String>,
+    // author provided description from p:cNvPr@descr
+    pub alt_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crop: Option<Crop>,
+    // clockwise rotation in degrees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f64>,
+    // whether the image fills a shape rather than being a picture of its own
+    pub fill: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Video {
+    pub id: u32,
+    pub name: String,
+    // relationship of the video, p14:media@r:embed or a:videoFile@r:link
+    pub rel_id: String,
+    // exported file name, or the target of a linked video
+    pub media: Option<String>,
+    // exported file name of the image shown before the video plays
+    pub poster: Option<String>,
+    #[serde(skip)]
+    pub poster_rel_id: Option<String>,
+    // from the container, with the media-probe feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub codecs: Vec<String>,
+}
+
+// where a shape sits on the slide in EMU, with the transforms of the groups
+// it is in applied
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+pub struct Position {
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+// maps the child coordinates of a group onto the slide
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Transform {
+    scale_x: f64,
+    scale_y: f64,
+    dx: f64,
+    dy: f64,
+}
+
+pub(crate) const IDENTITY: Transform = Transform {
+    scale_x: 1.0,
+    scale_y: 1.0,
+    dx: 0.0,
+    dy: 0.0,
+};
+
+impl Transform {
+    // the transform inside the p:grpSp, the group's a:xfrm maps the child
+    // offset and extents a:chOff and a:chExt onto its own a:off and a:ext
+    pub(crate) fn group(&self, grp_sp: Node) -> Transform {
+        let xfrm = match grp_sp
+            .child(P, "grpSpPr")
+            .and_then(|grp_sp_pr| grp_sp_pr.child(A, "xfrm"))
+        {
+            Some(xfrm) => xfrm,
+            None => return *self,
+        };
+        let (x, y) = point(xfrm, "off", "x", "y");
+        let (cx, cy) = point(xfrm, "ext", "cx", "cy");
+        let (ch_x, ch_y) = point(xfrm, "chOff", "x", "y");
+        let (ch_cx, ch_cy) = point(xfrm, "chExt", "cx", "cy");
+        let scale = |ext: f64, ch_ext: f64| if ch_ext == 0.0 { 1.0 } else { ext / ch_ext };
+        let (scale_x, scale_y) = (scale(cx, ch_cx), scale(cy, ch_cy));
+        // child point p lands on off + (p - chOff) * scale within the parent
+        Transform {
+            scale_x: self.scale_x * scale_x,
+            scale_y: self.scale_y * scale_y,
+            dx: self.dx + self.scale_x * (x - ch_x * scale_x),
+            dy: self.dy + self.scale_y * (y - ch_y * scale_y),
+        }
+    }
+
+    // the position of a shape from its a:xfrm, or p:xfrm for graphic frames
+    pub(crate) fn position(&self, shape: Node) -> Option<Position> {
+        let xfrm = shape
+            .child(P, "spPr")
+            .and_then(|sp_pr| sp_pr.child(A, "xfrm"))
+            .or_else(|| shape.child(P, "xfrm"))?;
+        let (x, y) = point(xfrm, "off", "x", "y");
+        let (cx, cy) = point(xfrm, "ext", "cx", "cy");
+        Some(Position {
+            x: (self.dx + x * self.scale_x).round() as i64,
+            y: (self.dy + y * self.scale_y).round() as i64,
+            width: (cx * self.scale_x).round() as i64,
+            height: (cy * self.scale_y).round() as i64,
+        })
+    }
+}
+
+// the two attributes of a child of an a:xfrm, a:off@x and a:off@y say
+fn point(xfrm: Node, name: &str, x: &str, y: &str) -> (f64, f64) {
+    let el = xfrm.child(A, name);
+    let value = |attr: &str| {
+        el.and_then(|el| el.attribute(attr))
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or_default()
+    };
+    (value(x), value(y))
+}
+
+// the part of the image cut off on each side by a:srcRect, in percent
+#[derive(Debug, Clone, Copy, Default, Serialize, JsonSchema)]
+pub struct Crop {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Background {
+    // solid, gradient, picture, pattern, none, or theme for a p:bgRef into
+    // the theme's background fill styles
+    pub fill: String,
+    // srgb hex values, scheme colors as scheme:<name>
+    pub colors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rel_id: Option<String>,
+    // media file name, resolved through the part's rels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+// get the shapes with text in the part, descending into group shapes and
+// leaving out placeholders of the excluded types
+pub fn shapes(el: Node, exclude: &[String]) -> Vec<Shape> {
+    let mut res = Vec::new();
+    walk_shapes(el, exclude, &IDENTITY, &mut res);
+    res
+}
+
+fn walk_shapes(el: Node, exclude: &[String], transform: &Transform, res: &mut Vec<Shape>) {
+    for child in children(el) {
+        match pml_name(child) {
+            "sp" | "graphicFrame" | "cxnSp" => {
+                let placeholder = placeholder(child);
+                if is_excluded(placeholder.as_deref(), exclude) {
+                    continue;
+                }
+                let paragraphs = paragraphs(child, exclude);
+                if paragraphs.is_empty() {
+                    continue;
+                }
+                let (id, name) = id_and_name(child);
+                res.push(Shape {
+                    id,
+                    name,
+                    placeholder,
+                    position: transform.position(child),
+                    paragraphs,
+                });
+            }
+            "grpSp" => walk_shapes(child, exclude, &transform.group(child), res),
+            _ => walk_shapes(child, exclude, transform, res),
+        }
+    }
+}
+
+// get the pictures in the part, descending into group shapes
+pub fn pictures(el: Node) -> Vec<Picture> {
+    let mut res = Vec::new();
+    walk_pictures(el, &IDENTITY, &mut res);
+    res
+}
+
+fn walk_pictures(el: Node, transform: &Transform, res: &mut Vec<Picture>) {
+    for child in children(el) {
+        let (blip_fill, fill) = match pml_name(child) {
+            "pic" => (child.child(P, "blipFill"), false),
+            "sp" => (
+                child
+                    .child(P, "spPr")
+                    .and_then(|sp_pr| sp_pr.child(A, "blipFill")),
+                true,
+            ),
+            "grpSp" => {
+                walk_pictures(child, &transform.group(child), res);
+                continue;
+            }
+            _ => {
+                walk_pictures(child, transform, res);
+                continue;
+            }
+        };
+        if let Some(mut picture) = blip_fill.and_then(|blip_fill| picture(child, blip_fill, fill)) {
+            picture.position = transform.position(child);
+            res.push(picture);
+        }
+    }
+}
+
+fn picture(shape: Node, blip_fill: Node, fill: bool) -> Option<Picture> {
+    let blip = blip_fill.child(A, "blip")?;
+    let rel_id = blip
+        .attribute((R, "embed"))
+        .or_else(|| blip.attribute((R, "link")))?;
+    let (id, name) = id_and_name(shape);
+    Some(Picture {
+        id,
+        name,
+        rel_id: rel_id.to_owned(),
+        image: None,
+        position: None,
+        alt_text: non_visual_props(shape)
+            .and_then(|c_nv_pr| c_nv_pr.attribute("descr"))
+            .filter(|descr| !descr.is_empty())
+            .map(String::from),
+        crop: blip_fill.child(A, "srcRect").map(|src_rect| {
+            // a:srcRect values are in thousandths of a percent
+            let side = |name: &str| {
+                src_rect
+                    .attribute(name)
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or_default()
+                    / 1000.0
+            };
+            Crop {
+                left: side("l"),
+                top: side("t"),
+                right: side("r"),
+                bottom: side("b"),
+            }
+        }),
+        // a:xfrm@rot is in 60000ths of a degree
+        rotation: shape
+            .child(P, "spPr")
+            .and_then(|sp_pr| sp_pr.child(A, "xfrm"))
+            .and_then(|xfrm| xfrm.attribute("rot"))
+            .and_then(|rot| rot.parse::<f64>().ok())
+            .map(|rot| rot / 60000.0),
+        fill,
+    })
+}
+
+// get the videos in the part, pictures with a video in their p:nvPr,
+// descending into group shapes
+pub fn videos(el: Node) -> Vec<Video> {
+    let mut res = Vec::new();
+    for child in children(el) {
+        if !child.has_tag_name((P, "pic")) {
+            res.extend(videos(child));
+            continue;
+        }
+        let nv_pr = match child
+            .child(P, "nvPicPr")
+            .and_then(|nv_pic_pr| nv_pic_pr.child(P, "nvPr"))
+        {
+            Some(nv_pr) => nv_pr,
+            None => continue,
+        };
+        // the p14:media extension holds the embedded video, a:videoFile may
+        // only link to it
+        let embedded = nv_pr
+            .child(P, "extLst")
+            .into_iter()
+            .flat_map(children)
+            .filter_map(|ext| ext.child(P14, "media"))
+            .find_map(|media| media.attribute((R, "embed")));
+        let linked = nv_pr
+            .child(A, "videoFile")
+            .and_then(|video_file| video_file.attribute((R, "link")));
+        let rel_id = match embedded.or(linked) {
+            Some(rel_id) => rel_id.to_owned(),
+            None => continue,
+        };
+        let (id, name) = id_and_name(child);
+        res.push(Video {
+            id,
+            name,
+            rel_id,
+            media: None,
+            poster: None,
+            poster_rel_id: child
+                .child(P, "blipFill")
+                .and_then(|blip_fill| blip_fill.child(A, "blip"))
+                .and_then(|blip| blip.attribute((R, "embed")))
+                .map(String::from),
+            duration_ms: None,
+            codecs: Vec::new(),
+        });
+    }
+    res
+}
+
+// get the background of the part from p:cSld/p:bg
+pub fn background(el: Node) -> Option<Background> {
+    let bg = el.child(P, "cSld")?.child(P, "bg")?;
+    if let Some(bg_ref) = bg.child(P, "bgRef") {
+        return Some(Background {
+            fill: String::from("theme"),
+            colors: colors(bg_ref),
+            rel_id: None,
+            image: None,
+        });
+    }
+    let fill_el = children(bg.child(P, "bgPr")?).next()?;
+    let fill = match dml_name(fill_el) {
+        "solidFill" => "solid",
+        "gradFill" => "gradient",
+        "blipFill" => "picture",
+        "pattFill" => "pattern",
+        "noFill" => "none",
+        _ => return None,
+    };
+    Some(Background {
+        fill: String::from(fill),
+        colors: colors(fill_el),
+        rel_id: fill_el
+            .child(A, "blip")
+            .and_then(|blip| blip.attribute((R, "embed")))
+            .map(String::from),
+        image: None,
+    })
+}
+
+// every color used within a fill, in document order
+fn colors(el: Node) -> Vec<String> {
+    let mut res = Vec::new();
+    for child in children(el) {
+        let color = match dml_name(child) {
+            "srgbClr" => child.attribute("val").map(String::from),
+            "sysClr" => child.attribute("lastClr").map(String::from),
+            "schemeClr" => child.attribute("val").map(|val| format!("scheme:{}", val)),
+            "prstClr" => child.attribute("val").map(|val| format!("preset:{}", val)),
+            _ => {
+                res.extend(colors(child));
+                None
+            }
+        };
+        res.extend(color);
+    }
+    res
+}
+
+// p:cNvPr inside the shape's p:nvSpPr, p:nvPicPr, p:nvGraphicFramePr, ...
+fn non_visual_props<'a, 'input>(shape: Node<'a, 'input>) -> Option<Node<'a, 'input>> {
+    children(shape)
+        .find(|child| pml_name(*child).starts_with("nv"))
+        .and_then(|nv_pr| nv_pr.child(P, "cNvPr"))
+}
+
+pub(crate) fn id_and_name(shape: Node) -> (u32, String) {
+    let c_nv_pr = non_visual_props(shape);
+    (
+        c_nv_pr
+            .and_then(|c_nv_pr| c_nv_pr.attribute("id"))
+            .and_then(|id| id.parse().ok())
+            .unwrap_or_default(),
+        c_nv_pr
+            .and_then(|c_nv_pr| c_nv_pr.attribute("name"))
+            .map(String::from)
+            .unwrap_or_default(),
+    )
+}
+
+// placeholder type of a shape, a p:ph without a type is an object placeholder
+fn placeholder(shape: Node) -> Option<String> {
+    children(shape)
+        .find(|child| pml_name(*child).starts_with("nv"))
+        .and_then(|nv_pr| nv_pr.child(P, "nvPr"))
+        .and_then(|nv_pr| nv_pr.child(P, "ph"))
+        .map(|ph| ph.attribute("type").unwrap_or("obj").to_owned())
+}
+
+// the text of a shape, its paragraphs a line each
+fn shape_text(shape: Node) -> String {
+    paragraphs(shape, &[])
+        .into_iter()
+        .map(|paragraph| paragraph.text)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_owned()
+}
+
+// get the text of the placeholders of a layout or master, which is only the
+// prompt shown in the editor while a slide's placeholder is empty, e.g. "Click
+// to add title"
+pub fn prompt_texts(el: Node) -> HashSet<String> {
+    let mut res = HashSet::new();
+    walk_prompt_texts(el, &mut res);
+    res
+}
+
+fn walk_prompt_texts(el: Node, res: &mut HashSet<String>) {
+    for child in children(el) {
+        if is_prompt_candidate(child) {
+            let text = shape_text(child);
+            if !text.is_empty() {
+                res.insert(text);
+            }
+            continue;
+        }
+        walk_prompt_texts(child, res);
+    }
+}
+
+// the part without the placeholders whose text is one of the prompts, as
+// tools copying the layout's placeholders along with their text leave them.
+// The parsed tree can't be changed, so the placeholders are cut out of the
+// text to be parsed again, none when there are none to cut
+pub fn remove_prompts(doc: &Document, prompts: &HashSet<String>) -> Option<String> {
+    if prompts.is_empty() {
+        return None;
+    }
+    let mut ranges = Vec::new();
+    walk_prompts(doc.root_element(), prompts, &mut ranges);
+    if ranges.is_empty() {
+        return None;
+    }
+    let text = doc.input_text();
+    let mut res = String::with_capacity(text.len());
+    let mut pos = 0;
+    for range in ranges {
+        res.push_str(&text[pos..range.start]);
+        pos = range.end;
+    }
+    res.push_str(&text[pos..]);
+    Some(res)
+}
+
+fn walk_prompts(el: Node, prompts: &HashSet<String>, res: &mut Vec<Range<usize>>) {
+    for child in el.elements() {
+        if is_prompt_candidate(child) && prompts.contains(&shape_text(child)) {
+            res.push(child.range());
+            continue;
+        }
+        walk_prompts(child, prompts, res);
+    }
+}
+
+// placeholder shapes, which can hold the prompt text of their layout
+fn is_prompt_candidate(shape: Node) -> bool {
+    shape.has_tag_name((P, "sp")) && placeholder(shape).is_some()
+}
+
+// count the paragraphs with text that aren't marked a:buNone, which are
+// bulleted unless the placeholder or master has no bullets
+pub fn bullets(el: Node) -> u32 {
+    children(el)
+        .map(|child| match child.has_tag_name((A, "p")) {
+            true => {
+                let bulleted = child.descendants().any(|el| el.has_tag_name((A, "t")))
+                    && child
+                        .child(A, "pPr")
+                        .is_none_or(|ppr| ppr.child(A, "buNone").is_none());
+                bulleted as u32
+            }
+            false => bullets(child),
+        })
+        .sum()
+}
+
+fn is_excluded(placeholder: Option<&str>, exclude: &[String]) -> bool {
+    placeholder.is_some_and(|placeholder| exclude.iter().any(|ex| ex == placeholder))
+}
+
+// get the text of every run in the part in document order, in any kind of
+// shape, leaving out placeholders of the excluded types. A line break ends the
+// text of the run before it with a '\n'
+pub fn run_texts(el: Node, exclude: &[String]) -> Vec<String> {
+    let mut res = Vec::new();
+    walk_run_texts(el, exclude, &mut res);
+    res
+}
+
+fn walk_run_texts(el: Node, exclude: &[String], res: &mut Vec<String>) {
+    for child in children(el) {
+        if matches!(pml_name(child), "sp" | "graphicFrame" | "cxnSp")
+            && is_excluded(placeholder(child).as_deref(), exclude)
+        {
+            continue;
+        }
+        if child.has_tag_name((A, "t")) {
+            let text = child.text().unwrap_or_default();
+            if !text.is_empty() {
+                res.push(text.to_owned());
+            }
+            continue;
+        }
+        if child.has_tag_name((A, "p")) {
+            let start = res.len();
+            for run in children(child) {
+                // a line break ends the text of the run before it in the paragraph
+                if run.has_tag_name((A, "br")) {
+                    if res.len() > start {
+                        res.last_mut().unwrap().push('\n');
+                    }
+                    continue;
+                }
+                walk_run_texts(run, exclude, res);
+            }
+            continue;
+        }
+        walk_run_texts(child, exclude, res);
+    }
+}
+
+// get the paragraphs of every text body in the part in document order,
+// leaving out placeholders of the excluded types
+pub fn paragraphs(el: Node, exclude: &[String]) -> Vec<Paragraph> {
+    let mut res = Vec::new();
+    walk_text_bodies(el, exclude, &mut res);
+    res
+}
+
+fn walk_text_bodies(el: Node, exclude: &[String], res: &mut Vec<Paragraph>) {
+    if matches!(pml_name(el), "sp" | "graphicFrame" | "cxnSp")
+        && is_excluded(placeholder(el).as_deref(), exclude)
+    {
+        return;
+    }
+    // p:txBody of shapes, a:txBody of table cells
+    if el.has_tag_name((P, "txBody")) || el.has_tag_name((A, "txBody")) {
+        res.extend(
+            children(el)
+                .filter(|child| child.has_tag_name((A, "p")))
+                .map(paragraph),
+        );
+        return;
+    }
+    for child in children(el) {
+        walk_text_bodies(child, exclude, res);
+    }
+}
+
+fn paragraph(p: Node) -> Paragraph {
+    let ppr = p.child(A, "pPr");
+    let level = ppr
+        .and_then(|ppr| ppr.attribute("lvl"))
+        .and_then(|lvl| lvl.parse().ok())
+        .unwrap_or(0);
+    let mut text = String::new();
+    for run in children(p) {
+        match dml_name(run) {
+            "r" | "fld" => {
+                if let Some(t) = run.child(A, "t") {
+                    text.push_str(t.text().unwrap_or_default());
+                }
+            }
+            // a line break within the paragraph, shift+enter
+            "br" => text.push('\n'),
+            _ => {}
+        }
+    }
+    let direction = match ppr.and_then(|ppr| ppr.attribute("rtl")) {
+        Some(rtl) if rtl == "1" || rtl == "true" => Direction::Rtl,
+        Some(_) => Direction::Ltr,
+        None => text::direction(&text),
+    };
+    Paragraph {
+        level,
+        text,
+        direction,
+    }
+}
+
+// apply the text options to paragraph texts, dropping those that end up empty
+pub fn clean_paragraphs(paragraphs: Vec<Paragraph>, opts: &TextOptions) -> Vec<Paragraph> {
+    paragraphs
+        .into_iter()
+        .filter_map(|p| text::clean_text(p.text, opts).map(|text| Paragraph { text, ..p }))
+        .collect()
+}
+
+// apply the text options to the paragraphs of shapes, dropping shapes left without text
+pub fn clean_shapes(shapes: Vec<Shape>, opts: &TextOptions) -> Vec<Shape> {
+    shapes
+        .into_iter()
+        .map(|shape| Shape {
+            paragraphs: clean_paragraphs(shape.paragraphs, opts),
+            ..shape
+        })
+        .filter(|shape| !shape.paragraphs.is_empty())
+        .collect()
+}
+
+// the child elements, with an mc:AlternateContent replaced by the content of
+// its first choice, or of its fallback without one, so that WordArt, ink and
+// other shapes saved in both a new and a compatible form are seen once
+pub(crate) fn children<'a, 'input>(el: Node<'a, 'input>) -> std::vec::IntoIter<Node<'a, 'input>> {
+    let mut res = Vec::new();
+    for child in el.elements() {
+        if !child.has_tag_name((MC, "AlternateContent")) {
+            res.push(child);
+            continue;
+        }
+        let branch = child
+            .child(MC, "Choice")
+            .or_else(|| child.child(MC, "Fallback"));
+        if let Some(branch) = branch {
+            res.extend(children(branch));
+        }
+    }
+    res.into_iter()
+}
+
+// the name of a PresentationML element, empty for other elements
+pub(crate) fn pml_name<'a>(el: Node<'a, '_>) -> &'a str {
+    match el.tag_name().namespace() {
+        Some(P) => el.tag_name().name(),
+        _ => "",
+    }
+}
+
+// the name of a DrawingML element, empty for other elements
+pub(crate) fn dml_name<'a>(el: Node<'a, '_>) -> &'a str {
+    match el.tag_name().namespace() {
+        Some(A) => el.tag_name().name(),
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    // a slide with the given shapes in its shape tree
+    fn slide(tree: &str) -> String {
+        format!(
+            r#"<p:sld xmlns:a="{}" xmlns:p="{}" xmlns:mc="{}"><p:cSld><p:spTree>{}</p:spTree></p:cSld></p:sld>"#,
+            A, P, MC, tree
+        )
+    }
+
+    // a shape of the given geometry with a paragraph of text
+    fn shape(id: u32, geometry: &str, text: &str) -> String {
+        format!(
+            r#"<p:sp><p:nvSpPr><p:cNvPr id="{}" name="Shape {}"/></p:nvSpPr><p:spPr><a:prstGeom prst="{}"/></p:spPr><p:txBody><a:bodyPr/><a:p><a:r><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp>"#,
+            id, id, geometry, text
+        )
+    }
+
+    // the run texts, paragraph texts and names of the shapes with text of a slide
+    fn texts(tree: &str) -> (Vec<String>, Vec<String>, Vec<String>) {
+        let content = slide(tree);
+        let doc = xml::parse(&content).unwrap();
+        let el = doc.root_element();
+        let paragraphs = paragraphs(el, &[]).into_iter().map(|p| p.text).collect();
+        let shape_names = shapes(el, &[]).into_iter().map(|s| s.name).collect();
+        (run_texts(el, &[]), paragraphs, shape_names)
+    }
+
+    #[test]
+    fn wordart_in_alternate_content_is_seen_once() {
+        let wordart = format!(
+            r#"<mc:AlternateContent><mc:Choice Requires="wps">{}</mc:Choice><mc:Fallback>{}</mc:Fallback></mc:AlternateContent>"#,
+            shape(2, "textWave1", "Grand Opening"),
+            shape(2, "rect", "Grand Opening"),
+        );
+        let (run_texts, paragraphs, shapes) = texts(&wordart);
+        assert_eq!(run_texts, ["Grand Opening"]);
+        assert_eq!(paragraphs, ["Grand Opening"]);
+        assert_eq!(shapes, ["Shape 2"]);
+    }
+
+    #[test]
+    fn alternate_content_falls_back_without_a_choice() {
+        let wordart = format!(
+            r#"<mc:AlternateContent><mc:Fallback>{}</mc:Fallback></mc:AlternateContent>"#,
+            shape(3, "rect", "Fallback only"),
+        );
+        let (run_texts, paragraphs, _) = texts(&wordart);
+        assert_eq!(run_texts, ["Fallback only"]);
+        assert_eq!(paragraphs, ["Fallback only"]);
+    }
+
+    #[test]
+    fn text_of_geometry_and_grouped_shapes() {
+        let tree = format!(
+            r#"{}<p:grpSp><p:nvGrpSpPr><p:cNvPr id="4" name="Group 4"/></p:nvGrpSpPr>{}</p:grpSp>"#,
+            shape(2, "star5", "Star"),
+            shape(3, "ellipse", "Grouped"),
+        );
+        let (run_texts, paragraphs, shapes) = texts(&tree);
+        assert_eq!(run_texts, ["Star", "Grouped"]);
+        assert_eq!(paragraphs, ["Star", "Grouped"]);
+        assert_eq!(shapes, ["Shape 2", "Shape 3"]);
+    }
+
+    #[test]
+    fn text_in_graphic_frame() {
+        let table = r#"<p:graphicFrame><p:nvGraphicFramePr><p:cNvPr id="5" name="Table 5"/></p:nvGraphicFramePr><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/table"><a:tbl><a:tr><a:tc><a:txBody><a:bodyPr/><a:p><a:r><a:t>Q1</a:t></a:r></a:p></a:txBody></a:tc><a:tc><a:txBody><a:bodyPr/><a:p><a:r><a:t>Q2</a:t></a:r></a:p></a:txBody></a:tc></a:tr></a:tbl></a:graphicData></a:graphic></p:graphicFrame>"#;
+        let (run_texts, paragraphs, shapes) = texts(table);
+        assert_eq!(run_texts, ["Q1", "Q2"]);
+        assert_eq!(paragraphs, ["Q1", "Q2"]);
+        assert_eq!(shapes, ["Table 5"]);
+    }
+
+    #[test]
+    fn line_breaks_within_a_paragraph() {
+        let text_box = r#"<p:sp><p:nvSpPr><p:cNvPr id="2" name="TextBox 2"/></p:nvSpPr><p:txBody><a:bodyPr/><a:p><a:br/><a:r><a:t>First line</a:t></a:r><a:br/><a:r><a:t>Second line</a:t></a:r></a:p><a:p><a:r><a:t>Next paragraph</a:t></a:r></a:p></p:txBody></p:sp>"#;
+        let (run_texts, paragraphs, _) = texts(text_box);
+        assert_eq!(run_texts, ["First line\n", "Second line", "Next paragraph"]);
+        assert_eq!(paragraphs, ["\nFirst line\nSecond line", "Next paragraph"]);
+    }
+}
@@ -0,0 +1,226 @@
+use log::trace;
+use std::io::{Read, Seek};
+
+use roxmltree::Node;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{errors::Errors, package, xml, xml::NodeExt, ExportMediaError};
+
+const REL_SIGNATURE_ORIGIN: &str = "origin";
+const REL_SIGNATURE: &str = "signature";
+
+const NS_XMLDSIG: &str = "http://www.w3.org/2000/09/xmldsig#";
+// mdssi, Office's signature properties
+const NS_DIGITAL_SIGNATURE: &str =
+    "http://schemas.openxmlformats.org/package/2006/digital-signature";
+const NS_XADES: &str = "http://uri.etsi.org/01903/v1.3.2#";
+
+// DER tags used when walking a certificate
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_VERSION: u8 = 0xa0;
+// OID prefix of the X.520 attribute types, 2.5.4
+const OID_X520: [u8; 2] = [0x55, 0x04];
+const OID_EMAIL: [u8; 9] = [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01];
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SignatureRes {
+    pub part_name: String,
+    // common name of the certificate subject
+    pub signer: Option<String>,
+    pub subject: Option<String>,
+    pub issuer: Option<String>,
+    pub signing_time: Option<String>,
+}
+
+// read the signatures in the package's _xmlsignatures parts
+pub fn signatures<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    errors: &mut Errors,
+) -> Result<Vec<SignatureRes>, ExportMediaError> {
+    let origin = match package::part_rels(archive, "")?
+        .into_iter()
+        .find(|rel| rel.kind == REL_SIGNATURE_ORIGIN && !rel.external)
+    {
+        Some(rel) => rel.target,
+        None => return Ok(Vec::new()),
+    };
+    let mut res = Vec::new();
+    for rel in package::part_rels(archive, &origin)? {
+        if rel.kind != REL_SIGNATURE || rel.external {
+            continue;
+        }
+        match signature(archive, &rel.target) {
+            Ok(signature_res) => {
+                trace!("got signature {:?}", signature_res);
+                res.push(signature_res);
+            }
+            Err(e) => errors.error("failed to read signature", Some(&rel.target), e),
+        }
+    }
+    Ok(res)
+}
+
+fn signature<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    part_name: &str,
+) -> Result<SignatureRes, ExportMediaError> {
+    let f = archive.by_name(part_name).map_err(|e| e.to_string())?;
+    let text = xml::read(f)?;
+    let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, part_name.to_owned()))?;
+    let el = doc.root_element();
+    let mut res = SignatureRes {
+        part_name: part_name.to_owned(),
+        signer: None,
+        subject: None,
+        issuer: None,
+        // Office records the time as mdssi:SignatureTime, XAdES as xd:SigningTime
+        signing_time: descendant(el, NS_DIGITAL_SIGNATURE, "SignatureTime")
+            .and_then(|time| time.child(NS_DIGITAL_SIGNATURE, "Value"))
+            .or_else(|| descendant(el, NS_XADES, "SigningTime"))
+            .and_then(|time| time.text())
+            .map(|time| time.trim().to_owned()),
+    };
+    if let Some(cert) = descendant(el, NS_XMLDSIG, "X509Certificate")
+        .and_then(|cert| cert.text())
+        .and_then(base64_decode)
+    {
+        if let Some((issuer, subject)) = certificate_names(&cert) {
+            res.signer = name_attribute(&subject, "CN");
+            res.issuer = Some(format_name(&issuer));
+            res.subject = Some(format_name(&subject));
+        }
+    }
+    if res.subject.is_none() {
+        res.subject = descendant(el, NS_XMLDSIG, "X509SubjectName")
+            .and_then(|subject| subject.text())
+            .map(|subject| subject.trim().to_owned());
+    }
+    Ok(res)
+}
+
+fn descendant<'a, 'input>(el: Node<'a, 'input>, ns: &str, name: &str) -> Option<Node<'a, 'input>> {
+    el.descendants().find(|node| node.has_tag_name((ns, name)))
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut res = Vec::new();
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((acc >> bits) as u8);
+        }
+    }
+    Some(res)
+}
+
+// read one DER element at data, returns its tag, contents and the rest
+fn der(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, data) = data.split_first()?;
+    let (&len, mut data) = data.split_first()?;
+    let len = if len < 0x80 {
+        len as usize
+    } else {
+        let n = (len & 0x7f) as usize;
+        if n > 4 || data.len() < n {
+            return None;
+        }
+        let len = data[..n]
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        data = &data[n..];
+        len
+    };
+    if data.len() < len {
+        return None;
+    }
+    Some((tag, &data[..len], &data[len..]))
+}
+
+// attribute name/value pairs of a distinguished name, e.g. (CN, Jane Doe)
+type Name = Vec<(String, String)>;
+
+// (issuer, subject) of a DER certificate
+fn certificate_names(cert: &[u8]) -> Option<(Name, Name)> {
+    let (_, cert, _) = der(cert).filter(|(tag, _, _)| *tag == TAG_SEQUENCE)?;
+    let (_, tbs, _) = der(cert).filter(|(tag, _, _)| *tag == TAG_SEQUENCE)?;
+    // the version is optional, the serial number comes next either way
+    let (tag, _, mut rest) = der(tbs)?;
+    if tag == TAG_VERSION {
+        rest = der(rest)?.2;
+    }
+    let rest = der(rest)?.2; // signature algorithm
+    let (_, issuer, rest) = der(rest)?;
+    let rest = der(rest)?.2; // validity
+    let (_, subject, _) = der(rest)?;
+    Some((name(issuer), name(subject)))
+}
+
+fn name(mut data: &[u8]) -> Name {
+    let mut res = Vec::new();
+    while let Some((tag, set, rest)) = der(data) {
+        data = rest;
+        if tag != TAG_SET {
+            continue;
+        }
+        let mut set = set;
+        while let Some((_, attr, rest)) = der(set) {
+            set = rest;
+            let (oid, value) = match der(attr) {
+                Some((TAG_OID, oid, value)) => (oid, value),
+                _ => continue,
+            };
+            let (key, value) = match (attribute_key(oid), der(value)) {
+                (Some(key), Some((_, value, _))) => (key, value),
+                _ => continue,
+            };
+            res.push((key.to_owned(), String::from_utf8_lossy(value).into_owned()));
+        }
+    }
+    res
+}
+
+fn attribute_key(oid: &[u8]) -> Option<&'static str> {
+    if oid == OID_EMAIL {
+        return Some("emailAddress");
+    }
+    match oid {
+        [a, b, t] if [*a, *b] == OID_X520 => match t {
+            3 => Some("CN"),
+            6 => Some("C"),
+            7 => Some("L"),
+            8 => Some("ST"),
+            10 => Some("O"),
+            11 => Some("OU"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn name_attribute(name: &Name, key: &str) -> Option<String> {
+    name.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+}
+
+fn format_name(name: &Name) -> String {
+    name.iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,87 @@
+// what a media file is by its first bytes, so that media stored as
+// image1.bin or under the wrong extension are exported as what they are
+
+// enough for the signatures below, EMF's being the furthest in
+pub const SNIFF_LEN: u64 = 44;
+
+// the extension of the format data starts like, none when it's not one of the
+// image, video or audio formats presentations carry
+pub fn extension(data: &[u8]) -> Option<&'static str> {
+    let at = |offset: usize, magic: &[u8]| data.get(offset..).is_some_and(|d| d.starts_with(magic));
+    let ext = if at(0, b"\x89PNG\r\n\x1a\n") {
+        "png"
+    } else if at(0, &[0xff, 0xd8, 0xff]) {
+        "jpg"
+    } else if at(0, b"GIF87a") || at(0, b"GIF89a") {
+        "gif"
+    } else if at(0, b"II*\0") || at(0, b"MM\0*") {
+        "tiff"
+    } else if at(0, b"RIFF") && at(8, b"WEBP") {
+        "webp"
+    } else if at(0, b"RIFF") && at(8, b"AVI ") {
+        "avi"
+    } else if at(0, b"RIFF") && at(8, b"WAVE") {
+        "wav"
+    } else if at(0, &[1, 0, 0, 0]) && at(40, b" EMF") {
+        "emf"
+    } else if at(0, &[0xd7, 0xcd, 0xc6, 0x9a]) || at(0, &[1, 0, 9, 0]) || at(0, &[2, 0, 9, 0]) {
+        "wmf"
+    } else if at(4, b"ftypqt") {
+        "mov"
+    } else if at(4, b"ftypM4A") {
+        "m4a"
+    } else if at(4, b"ftyp") {
+        "mp4"
+    } else if at(0, &[0x30, 0x26, 0xb2, 0x75, 0x8e, 0x66, 0xcf, 0x11]) {
+        // ASF, audio or video alike
+        "wmv"
+    } else if at(0, b"ID3") || at(0, &[0xff, 0xfb]) || at(0, &[0xff, 0xf3]) {
+        "mp3"
+    } else if at(0, b"BM") && data.len() >= 14 {
+        "bmp"
+    } else if at(0, b"%PDF") {
+        "pdf"
+    } else {
+        return None;
+    };
+    Some(ext)
+}
+
+// the extension of a media content type, for media whose first bytes don't
+// tell, e.g. SVG
+pub fn content_type_extension(content_type: &str) -> Option<&'static str> {
+    let ext = match content_type {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/tiff" => "tiff",
+        "image/bmp" => "bmp",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "image/x-emf" | "image/emf" => "emf",
+        "image/x-wmf" | "image/wmf" => "wmf",
+        "video/mp4" => "mp4",
+        "video/quicktime" => "mov",
+        "video/x-msvideo" | "video/avi" => "avi",
+        "video/x-ms-wmv" => "wmv",
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/wav" | "audio/x-wav" => "wav",
+        "audio/mp4" | "audio/x-m4a" => "m4a",
+        _ => return None,
+    };
+    Some(ext)
+}
+
+// whether a file named with extension ext is taken for the format of sniffed,
+// JPEG and TIFF going by two extensions and ASF by several
+pub fn matches(ext: &str, sniffed: &str) -> bool {
+    let ext = ext.to_lowercase();
+    let same: &[&str] = match sniffed {
+        "jpg" => &["jpg", "jpeg", "jpe", "jfif"],
+        "tiff" => &["tiff", "tif"],
+        "wmv" => &["wmv", "wma", "asf"],
+        "mp4" => &["mp4", "m4v"],
+        sniffed => return ext == sniffed,
+    };
+    same.contains(&ext.as_str())
+}
@@ -0,0 +1,29 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use ppt_img_extract::{output::Output, ExportMediaError, Res};
+
+pub const DIR_SLIDES: &str = "slides";
+
+// whether the slides are written to files of their own next to the index or
+// instead of it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitIndex {
+    Also,
+    Only,
+}
+
+// slides/slide_007.json
+pub fn slide_file(page_no: u32) -> String {
+    format!("{}/slide_{:03}.json", DIR_SLIDES, page_no)
+}
+
+// write each slide of the index to a file of its own
+pub fn write(output: &mut Output, res: &Res) -> Result<(), ExportMediaError> {
+    for single_res in res.pages.slides.values() {
+        let j = serde_json::to_string_pretty(single_res).unwrap();
+        output.write(&slide_file(single_res.page_no), j.as_bytes())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::fmt::Write;
+
+use roxmltree::Node;
+
+use crate::{shapes, xml::NS_PRESENTATIONML};
+
+pub const SRT_FILE: &str = "slides.srt";
+// how long a slide shows when it doesn't advance on its own
+pub const DEFAULT_SLIDE_DURATION_MS: u64 = 5000;
+// placeholders of notes slides that aren't the notes themselves
+pub const NOTES_EXCLUDE: &[&str] = &["sldImg", "sldNum", "hdr", "ftr", "dt"];
+
+// a subtitle shown for the duration of a slide
+pub struct Cue {
+    pub duration_ms: u64,
+    pub text: String,
+}
+
+// p:transition@advTm, after how long the slide advances in a slide show,
+// which recording narration sets to the length of the slide's recording
+pub fn advance_ms(sld: Node) -> Option<u64> {
+    shapes::children(sld)
+        .find(|child| child.has_tag_name((NS_PRESENTATIONML, "transition")))
+        .and_then(|transition| transition.attribute("advTm"))
+        .and_then(|advance| advance.parse().ok())
+}
+
+// p:sld@show, hidden slides are skipped in a slide show
+pub fn hidden(sld: Node) -> bool {
+    sld.attribute("show")
+        .is_some_and(|show| show == "0" || show == "false")
+}
+
+// the cues one after another, those without text only taking up their time
+pub fn srt(cues: &[Cue]) -> String {
+    let mut res = String::new();
+    let mut start = 0;
+    let mut n = 0;
+    for cue in cues {
+        let end = start + cue.duration_ms;
+        let text = cue.text.trim();
+        if !text.is_empty() {
+            n += 1;
+            // blank lines end a cue, so they are left out of its text
+            let text: Vec<_> = text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .collect();
+            let _ = write!(
+                res,
+                "{}\n{} --> {}\n{}\n\n",
+                n,
+                timestamp(start),
+                timestamp(end),
+                text.join("\n")
+            );
+        }
+        start = end;
+    }
+    res
+}
+
+// HH:MM:SS,mmm
+fn timestamp(ms: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
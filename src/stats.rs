@@ -0,0 +1,35 @@
+use std::ops::AddAssign;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Default, Clone, Copy, Serialize, JsonSchema)]
+pub struct Stats {
+    pub words: u32,
+    pub chars: u32,
+    pub bullets: u32,
+    pub images: u32,
+}
+
+impl Stats {
+    pub fn new(texts: &[String], bullets: u32, images: usize) -> Self {
+        Stats {
+            words: texts
+                .iter()
+                .map(|text| text.split_whitespace().count() as u32)
+                .sum(),
+            chars: texts.iter().map(|text| text.chars().count() as u32).sum(),
+            bullets,
+            images: images as u32,
+        }
+    }
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bullets += other.bullets;
+        self.images += other.images;
+    }
+}
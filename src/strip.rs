@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+
+// removes metadata that can identify people, places or devices from JPEG,
+// PNG and TIFF images: EXIF (GPS, camera make, model and serial), XMP, IPTC,
+// comments and text chunks. Colour profiles and whatever the image needs to
+// decode are kept. Other data is left as is.
+pub fn strip(data: Vec<u8>) -> Result<Vec<u8>, String> {
+    if data.starts_with(&[0xff, 0xd8]) {
+        jpeg(&data)
+    } else if data.starts_with(PNG_SIGNATURE) {
+        png(&data)
+    } else if data.starts_with(b"II") || data.starts_with(b"MM") {
+        tiff(data)
+    } else {
+        Ok(data)
+    }
+}
+
+pub(crate) const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+// text and time chunks, eXIf carries the same as a JPEG's APP1
+const PNG_DROPPED_CHUNKS: &[&[u8]] = &[b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+fn jpeg(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut res = Vec::with_capacity(data.len());
+    res.extend_from_slice(&data[..2]);
+    let mut pos = 2;
+    loop {
+        if pos + 2 > data.len() || data[pos] != 0xff {
+            return Err(format!("invalid jpeg marker at {}", pos));
+        }
+        let marker = data[pos + 1];
+        // fill bytes before a marker
+        if marker == 0xff {
+            pos += 1;
+            continue;
+        }
+        // markers without a segment
+        if marker == 0x01 || (0xd0..=0xd7).contains(&marker) {
+            res.extend_from_slice(&data[pos..pos + 2]);
+            pos += 2;
+            continue;
+        }
+        if marker == 0xd9 {
+            res.extend_from_slice(&data[pos..]);
+            return Ok(res);
+        }
+        if pos + 4 > data.len() {
+            return Err("truncated jpeg segment".to_owned());
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let end = pos + 2 + len;
+        if len < 2 || end > data.len() {
+            return Err(format!("invalid jpeg segment length at {}", pos));
+        }
+        // the scan and everything after it is image data
+        if marker == 0xda {
+            res.extend_from_slice(&data[pos..]);
+            return Ok(res);
+        }
+        let payload = &data[pos + 4..end];
+        let keep = match marker {
+            // JFIF
+            0xe0 => true,
+            // colour profile, APP2 also carries FlashPix data
+            0xe2 => payload.starts_with(b"ICC_PROFILE\0"),
+            // Adobe, tells how the colours are transformed
+            0xee => true,
+            // EXIF, XMP, IPTC, other application data and comments
+            0xe1..=0xef | 0xfe => false,
+            _ => true,
+        };
+        if keep {
+            res.extend_from_slice(&data[pos..end]);
+        }
+        pos = end;
+    }
+}
+
+fn png(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut res = Vec::with_capacity(data.len());
+    res.extend_from_slice(PNG_SIGNATURE);
+    let mut pos = PNG_SIGNATURE.len();
+    while pos < data.len() {
+        if pos + 8 > data.len() {
+            return Err("truncated png chunk".to_owned());
+        }
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        // length, type, data and crc
+        let end = pos + 12 + len as usize;
+        if end > data.len() {
+            return Err(format!("invalid png chunk length at {}", pos));
+        }
+        let kind = &data[pos + 4..pos + 8];
+        if !PNG_DROPPED_CHUNKS.contains(&kind) {
+            res.extend_from_slice(&data[pos..end]);
+        }
+        pos = end;
+        if kind == b"IEND" {
+            break;
+        }
+    }
+    Ok(res)
+}
+
+// description, make, model, software, date, artist, host computer,
+// copyright, XMP, IPTC, Photoshop, EXIF and GPS
+const TIFF_DROPPED_TAGS: &[u16] = &[
+    270, 271, 272, 305, 306, 315, 316, 33432, 700, 33723, 34377, 34665, 34853,
+];
+// tags pointing to an IFD of their own
+const TIFF_SUB_IFD_TAGS: &[u16] = &[34665, 34853, 40965];
+
+pub(crate) struct Tiff {
+    pub data: Vec<u8>,
+    little_endian: bool,
+    // IFDs already walked, against offsets pointing back
+    visited: HashSet<usize>,
+}
+
+pub(crate) struct Entry {
+    pub tag: u16,
+    pub kind: u16,
+    pub count: u32,
+    // the value itself when it fits in 4 bytes, else the offset of it
+    pub value: u32,
+    // where the value field is, for values read as bytes
+    pub pos: usize,
+}
+
+// the entries of TIFF IFDs can't be removed without moving the data after
+// them, so they are dropped from the entry list in place and the data they
+// pointed to is zeroed
+fn tiff(data: Vec<u8>) -> Result<Vec<u8>, String> {
+    let mut tiff = Tiff::new(data);
+    let mut offset = match tiff.first_ifd()? {
+        Some(offset) => offset,
+        None => return Ok(tiff.data),
+    };
+    while offset != 0 {
+        offset = tiff.strip_ifd(offset)?;
+    }
+    Ok(tiff.data)
+}
+
+impl Tiff {
+    pub(crate) fn new(data: Vec<u8>) -> Self {
+        Tiff {
+            little_endian: data.starts_with(b"II"),
+            data,
+            visited: HashSet::new(),
+        }
+    }
+
+    // the offset of the first IFD, none when the data isn't a TIFF after all
+    pub(crate) fn first_ifd(&self) -> Result<Option<usize>, String> {
+        if !self.data.starts_with(b"II") && !self.data.starts_with(b"MM") {
+            return Ok(None);
+        }
+        match self.u16(2)? {
+            42 => Ok(Some(self.u32(4)? as usize)),
+            // BigTIFF
+            43 => Err("BigTIFF is not supported".to_owned()),
+            _ => Ok(None),
+        }
+    }
+
+    fn bytes<const N: usize>(&self, pos: usize) -> Result<[u8; N], String> {
+        self.data
+            .get(pos..pos + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("tiff offset {} out of bounds", pos))
+    }
+
+    fn u16(&self, pos: usize) -> Result<u16, String> {
+        let bytes = self.bytes(pos)?;
+        Ok(match self.little_endian {
+            true => u16::from_le_bytes(bytes),
+            false => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, pos: usize) -> Result<u32, String> {
+        let bytes = self.bytes(pos)?;
+        Ok(match self.little_endian {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn put_u16(&mut self, pos: usize, value: u16) {
+        let bytes = match self.little_endian {
+            true => value.to_le_bytes(),
+            false => value.to_be_bytes(),
+        };
+        self.data[pos..pos + 2].copy_from_slice(&bytes);
+    }
+
+    fn put_u32(&mut self, pos: usize, value: u32) {
+        let bytes = match self.little_endian {
+            true => value.to_le_bytes(),
+            false => value.to_be_bytes(),
+        };
+        self.data[pos..pos + 4].copy_from_slice(&bytes);
+    }
+
+    fn zero(&mut self, pos: usize, len: usize) -> Result<(), String> {
+        match self.data.get_mut(pos..pos + len) {
+            Some(bytes) => {
+                bytes.fill(0);
+                Ok(())
+            }
+            None => Err(format!("tiff offset {} out of bounds", pos)),
+        }
+    }
+
+    // read the IFD at offset, returning its entries and the offset of the next
+    pub(crate) fn ifd(&mut self, offset: usize) -> Result<(Vec<Entry>, usize), String> {
+        if !self.visited.insert(offset) {
+            return Err(format!("tiff IFD at {} is referenced twice", offset));
+        }
+        let count = self.u16(offset)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            let pos = offset + 2 + i * 12;
+            entries.push(Entry {
+                tag: self.u16(pos)?,
+                kind: self.u16(pos + 2)?,
+                count: self.u32(pos + 4)?,
+                value: self.u32(pos + 8)?,
+                pos: pos + 8,
+            });
+        }
+        let next = self.u32(offset + 2 + count * 12)? as usize;
+        Ok((entries, next))
+    }
+
+    fn strip_ifd(&mut self, offset: usize) -> Result<usize, String> {
+        let (entries, next) = self.ifd(offset)?;
+        let mut kept = Vec::with_capacity(entries.len());
+        for (i, entry) in entries.iter().enumerate() {
+            if TIFF_DROPPED_TAGS.contains(&entry.tag) {
+                self.zero_entry(entry)?;
+            } else {
+                kept.push(i);
+            }
+        }
+        if kept.len() == entries.len() {
+            return Ok(next);
+        }
+        // move the kept entries up and zero what's left of the old list
+        for (to, from) in kept.iter().enumerate() {
+            let (to, from) = (offset + 2 + to * 12, offset + 2 + from * 12);
+            self.data.copy_within(from..from + 12, to);
+        }
+        let end = offset + 2 + kept.len() * 12;
+        self.put_u16(offset, kept.len() as u16);
+        self.put_u32(end, next as u32);
+        self.zero(end + 4, (entries.len() - kept.len()) * 12)?;
+        Ok(next)
+    }
+
+    // zero the data an entry points to, and for IFD pointers all of the IFD
+    fn zero_entry(&mut self, entry: &Entry) -> Result<(), String> {
+        if TIFF_SUB_IFD_TAGS.contains(&entry.tag) {
+            let offset = entry.value as usize;
+            let (entries, _) = self.ifd(offset)?;
+            for entry in &entries {
+                self.zero_entry(entry)?;
+            }
+            return self.zero(offset, 2 + entries.len() * 12 + 4);
+        }
+        match self.value_range(entry) {
+            Some((pos, len)) if pos != entry.pos => self.zero(pos, len),
+            _ => Ok(()),
+        }
+    }
+
+    // where the value of an entry is and its length in bytes, none for
+    // unknown types
+    fn value_range(&self, entry: &Entry) -> Option<(usize, usize)> {
+        let size = match entry.kind {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 | 13 => 4,
+            5 | 10 | 12 => 8,
+            _ => return None,
+        };
+        let len = size * entry.count as usize;
+        // values of up to 4 bytes are inline
+        match len > 4 {
+            true => Some((entry.value as usize, len)),
+            false => Some((entry.pos, len)),
+        }
+    }
+
+    // the value of an ASCII entry, up to its terminating nul
+    pub(crate) fn ascii(&self, entry: &Entry) -> Option<String> {
+        if entry.kind != 2 {
+            return None;
+        }
+        let (pos, len) = self.value_range(entry)?;
+        let bytes = self.data.get(pos..pos + len)?;
+        let bytes = bytes.split(|b| *b == 0).next().unwrap_or_default();
+        Some(String::from_utf8_lossy(bytes).trim().to_owned()).filter(|s| !s.is_empty())
+    }
+    // the value of a single SHORT or LONG entry
+    pub(crate) fn number(&self, entry: &Entry) -> Option<u32> {
+        match (entry.kind, entry.count) {
+            (3, 1) => self.u16(entry.pos).ok().map(u32::from),
+            (4, 1) => Some(entry.value),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::{fmt, time::Duration};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::Res;
+
+// what an extraction came to, in a line of the log and in the index
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct Summary {
+    pub slides: usize,
+    pub texts: usize,
+    pub media: usize,
+    pub media_bytes: u64,
+    // warnings and errors logged while extracting, counted when logging
+    // through logging::init
+    pub warnings: usize,
+    pub duration_ms: f64,
+}
+
+impl Summary {
+    pub fn new(res: &Res, warnings: usize, duration: Duration) -> Self {
+        Summary {
+            slides: res.pages.slides.len(),
+            texts: res
+                .pages
+                .slides
+                .values()
+                .map(|slide| slide.texts.len())
+                .sum(),
+            media: res.media.len(),
+            media_bytes: res.media.values().map(|checksum| checksum.size).sum(),
+            warnings,
+            duration_ms: duration.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} slides, {} texts, {} media files of {} bytes, {} warnings in {:.1?}",
+            self.slides,
+            self.texts,
+            self.media,
+            self.media_bytes,
+            self.warnings,
+            Duration::from_secs_f64(self.duration_ms / 1000.0)
+        )
+    }
+}
@@ -0,0 +1,565 @@
+use std::{collections::BTreeMap, f64::consts::PI, fmt::Write};
+
+use roxmltree::Node;
+
+use crate::{
+    reveal::escape,
+    shapes::{self, children, dml_name, pml_name, Transform, IDENTITY},
+    xml::{NodeExt, NS_DRAWINGML as A, NS_PRESENTATIONML as P},
+};
+
+pub const DIR_SHAPES: &str = "shapes";
+// 16:9, when the presentation doesn't say
+const DEFAULT_SLIDE_SIZE: (i64, i64) = (12192000, 6858000);
+// EMU per pixel at 96 dpi, and per point
+const EMU_PER_PX: i64 = 9525;
+const EMU_PER_PT: f64 = 12700.0;
+// a:ln@w and a:rPr@sz when not given, 0.75pt and 18pt
+const DEFAULT_LINE_WIDTH: f64 = 9525.0;
+const DEFAULT_FONT_SIZE: f64 = 1800.0;
+
+// shapes/slide_007.svg
+pub fn svg_file(page_no: u32) -> String {
+    format!("{}/slide_{:03}.svg", DIR_SHAPES, page_no)
+}
+
+// the slide size in EMU from p:sldSz of the presentation part, the default
+// one when it's missing or not positive
+pub fn slide_size(presentation: Node) -> (i64, i64) {
+    let size = |attr: &str| {
+        presentation
+            .child(P, "sldSz")
+            .and_then(|sld_sz| sld_sz.attribute(attr))
+            .and_then(|v| v.parse().ok())
+    };
+    match (size("cx"), size("cy")) {
+        (Some(cx), Some(cy)) if cx > 0 && cy > 0 => (cx, cy),
+        _ => DEFAULT_SLIDE_SIZE,
+    }
+}
+
+// an SVG of the shapes drawn on the slide, the size of the slide in EMU: the
+// preset geometries, lines, connectors and freeform paths with their fills
+// and outlines, and the text of the shapes drawn. Pictures, charts and
+// placeholders without a fill or outline are left out, as is the rest of what
+// the slide inherits. None when no shape is drawn.
+//
+// colors are those of the shape or its style, scheme colors resolved through
+// the theme's colors and unresolved ones drawn grey. Presets other than the
+// common ones below are drawn as their box.
+pub fn svg(el: Node, size: (i64, i64), theme: &BTreeMap<String, String>) -> Option<String> {
+    let mut body = String::new();
+    walk(el, &IDENTITY, theme, &mut body);
+    if body.is_empty() {
+        return None;
+    }
+    let mut res = String::new();
+    writeln!(
+        res,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        size.0 / EMU_PER_PX,
+        size.1 / EMU_PER_PX,
+        size.0,
+        size.1
+    )
+    .unwrap();
+    // arrowheads take the color of the line they end
+    res.push_str(
+        "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"5\" refY=\"5\" \
+         markerWidth=\"4\" markerHeight=\"4\" orient=\"auto-start-reverse\">\
+         <path d=\"M0,0 L10,5 L0,10 Z\" fill=\"context-stroke\"/></marker></defs>\n",
+    );
+    res.push_str(&body);
+    res.push_str("</svg>\n");
+    Some(res)
+}
+
+fn walk(el: Node, transform: &Transform, theme: &BTreeMap<String, String>, res: &mut String) {
+    for child in children(el) {
+        match pml_name(child) {
+            "sp" | "cxnSp" => shape(child, transform, theme, res),
+            "grpSp" => walk(child, &transform.group(child), theme, res),
+            // pictures and graphic frames aren't drawn shapes
+            "pic" | "graphicFrame" => {}
+            _ => walk(child, transform, theme, res),
+        }
+    }
+}
+
+fn shape(sp: Node, transform: &Transform, theme: &BTreeMap<String, String>, res: &mut String) {
+    let sp_pr = match sp.child(P, "spPr") {
+        Some(sp_pr) => sp_pr,
+        None => return,
+    };
+    // picture fills are exported as images
+    if sp_pr.child(A, "blipFill").is_some() {
+        return;
+    }
+    let position = match transform.position(sp) {
+        Some(position) => position,
+        None => return,
+    };
+    let style = sp.child(P, "style");
+    let connector = pml_name(sp) == "cxnSp";
+    let fill = match connector {
+        true => None,
+        false => fill(sp_pr, style, theme),
+    };
+    let ln = sp_pr.child(A, "ln");
+    let stroke = stroke(ln, style, theme);
+    if fill.is_none() && stroke.is_none() {
+        return;
+    }
+    let (w, h) = (position.width as f64, position.height as f64);
+    let geometry = match sp_pr.child(A, "custGeom") {
+        Some(cust_geom) => custom(cust_geom, w, h),
+        None => {
+            let prst = sp_pr
+                .child(A, "prstGeom")
+                .and_then(|prst_geom| prst_geom.attribute("prst"))
+                .unwrap_or("rect");
+            vec![(preset(prst, w, h), true, true)]
+        }
+    };
+
+    let xfrm = sp_pr.child(A, "xfrm");
+    let rot = xfrm
+        .and_then(|xfrm| xfrm.attribute("rot"))
+        .and_then(|rot| rot.parse::<f64>().ok())
+        .unwrap_or_default()
+        / 60000.0;
+    let flip = |attr: &str| {
+        xfrm.and_then(|xfrm| xfrm.attribute(attr))
+            .is_some_and(|v| v == "1" || v == "true")
+    };
+    let mut transforms = format!("translate({} {})", position.x, position.y);
+    if rot != 0.0 {
+        write!(transforms, " rotate({} {:.0} {:.0})", rot, w / 2.0, h / 2.0).unwrap();
+    }
+    if flip("flipH") {
+        write!(transforms, " translate({:.0} 0) scale(-1 1)", w).unwrap();
+    }
+    if flip("flipV") {
+        write!(transforms, " translate(0 {:.0}) scale(1 -1)", h).unwrap();
+    }
+    let (_, name) = shapes::id_and_name(sp);
+    writeln!(
+        res,
+        "<g data-name=\"{}\" transform=\"{}\">",
+        escape(&name),
+        transforms
+    )
+    .unwrap();
+    let line_end = |name: &str| {
+        ln.and_then(|ln| ln.child(A, name))
+            .and_then(|end| end.attribute("type"))
+            .is_some_and(|kind| kind != "none")
+    };
+    for (d, filled, stroked) in geometry {
+        let fill = fill.as_deref().filter(|_| filled).unwrap_or("none");
+        write!(res, "<path d=\"{}\" fill=\"{}\"", d, fill).unwrap();
+        match stroke.as_ref().filter(|_| stroked) {
+            Some((color, width)) => {
+                write!(res, " stroke=\"{}\" stroke-width=\"{:.0}\"", color, width).unwrap();
+                if line_end("headEnd") {
+                    res.push_str(" marker-start=\"url(#arrow)\"");
+                }
+                if line_end("tailEnd") {
+                    res.push_str(" marker-end=\"url(#arrow)\"");
+                }
+            }
+            None => res.push_str(" stroke=\"none\""),
+        }
+        res.push_str("/>\n");
+    }
+    label(sp, w, h, style, theme, res);
+    res.push_str("</g>\n");
+}
+
+// the paragraphs of the shape centered in it, one line each
+fn label(
+    sp: Node,
+    w: f64,
+    h: f64,
+    style: Option<Node>,
+    theme: &BTreeMap<String, String>,
+    res: &mut String,
+) {
+    let paragraphs = shapes::paragraphs(sp, &[]);
+    if paragraphs.is_empty() {
+        return;
+    }
+    let r_pr = sp
+        .descendants()
+        .find(|el| el.has_tag_name((A, "rPr")) || el.has_tag_name((A, "endParaRPr")));
+    let size = r_pr
+        .and_then(|r_pr| r_pr.attribute("sz"))
+        .and_then(|sz| sz.parse::<f64>().ok())
+        .unwrap_or(DEFAULT_FONT_SIZE)
+        / 100.0
+        * EMU_PER_PT;
+    let color = r_pr
+        .and_then(|r_pr| r_pr.child(A, "solidFill"))
+        .and_then(|fill| color(fill, theme))
+        .or_else(|| {
+            style
+                .and_then(|style| style.child(A, "fontRef"))
+                .and_then(|font_ref| color(font_ref, theme))
+        })
+        .or_else(|| theme.get("dk1").map(|dk1| format!("#{}", dk1)))
+        .unwrap_or_else(|| String::from("#000000"));
+    let line_height = size * 1.2;
+    let top = h / 2.0 - line_height * (paragraphs.len() as f64 - 1.0) / 2.0;
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        writeln!(
+            res,
+            "<text x=\"{:.0}\" y=\"{:.0}\" font-size=\"{:.0}\" fill=\"{}\" \
+             text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>",
+            w / 2.0,
+            top + line_height * i as f64,
+            size,
+            color,
+            escape(&paragraph.text)
+        )
+        .unwrap();
+    }
+}
+
+// the fill of spPr, or else of the style's a:fillRef
+fn fill(sp_pr: Node, style: Option<Node>, theme: &BTreeMap<String, String>) -> Option<String> {
+    for child in children(sp_pr) {
+        match dml_name(child) {
+            "noFill" => return None,
+            // gradients and patterns are drawn in their first color
+            "solidFill" | "gradFill" | "pattFill" => return first_color(child, theme),
+            _ => {}
+        }
+    }
+    style_color(style?, "fillRef", theme)
+}
+
+// the color and width of a:ln, or else of the style's a:lnRef
+fn stroke(
+    ln: Option<Node>,
+    style: Option<Node>,
+    theme: &BTreeMap<String, String>,
+) -> Option<(String, f64)> {
+    let width = ln
+        .and_then(|ln| ln.attribute("w"))
+        .and_then(|w| w.parse::<f64>().ok())
+        .unwrap_or(DEFAULT_LINE_WIDTH);
+    if let Some(ln) = ln {
+        for child in children(ln) {
+            match dml_name(child) {
+                "noFill" => return None,
+                "solidFill" | "gradFill" | "pattFill" => {
+                    return first_color(child, theme).map(|color| (color, width))
+                }
+                _ => {}
+            }
+        }
+    }
+    style_color(style?, "lnRef", theme).map(|color| (color, width))
+}
+
+// the color of a style reference, none for idx 0, which refers to no fill or
+// line
+fn style_color(style: Node, name: &str, theme: &BTreeMap<String, String>) -> Option<String> {
+    let style_ref = style.child(A, name)?;
+    if style_ref.attribute("idx") == Some("0") {
+        return None;
+    }
+    color(style_ref, theme)
+}
+
+fn first_color(el: Node, theme: &BTreeMap<String, String>) -> Option<String> {
+    el.descendants().find_map(|child| match dml_name(child) {
+        "srgbClr" | "schemeClr" | "sysClr" | "prstClr" => color(child.parent()?, theme),
+        _ => None,
+    })
+}
+
+// the color element within el as an SVG color, ignoring tints, shades and
+// transparency
+fn color(el: Node, theme: &BTreeMap<String, String>) -> Option<String> {
+    let child = children(el).next()?;
+    let val = child.attribute("val");
+    let hex = match dml_name(child) {
+        "srgbClr" => val?.to_owned(),
+        "sysClr" => child.attribute("lastClr")?.to_owned(),
+        "prstClr" => return val.map(String::from),
+        "schemeClr" => {
+            // the slot a master's default p:clrMap maps the name to
+            let slot = match val? {
+                "tx1" => "dk1",
+                "bg1" => "lt1",
+                "tx2" => "dk2",
+                "bg2" => "lt2",
+                val => val,
+            };
+            match theme.get(slot) {
+                Some(hex) => hex.clone(),
+                None => return Some(String::from("#808080")),
+            }
+        }
+        _ => return None,
+    };
+    Some(format!("#{}", hex))
+}
+
+// the path of a preset geometry w x h, with its adjustments at their defaults
+fn preset(prst: &str, w: f64, h: f64) -> String {
+    let ss = w.min(h);
+    let polygon = |points: &[(f64, f64)]| {
+        let mut d = String::new();
+        for (i, (x, y)) in points.iter().enumerate() {
+            let command = if i == 0 { 'M' } else { 'L' };
+            write!(d, "{}{:.0},{:.0} ", command, x, y).unwrap();
+        }
+        d.push('Z');
+        d
+    };
+    // a right arrow of shaft thickness half the height and head length half
+    // the shorter side, mapped onto the box by f
+    let arrow = |f: &dyn Fn(f64, f64) -> (f64, f64), len: f64, thick: f64| {
+        let head = len.min(thick) / 2.0;
+        polygon(
+            &[
+                (0.0, thick / 4.0),
+                (len - head, thick / 4.0),
+                (len - head, 0.0),
+                (len, thick / 2.0),
+                (len - head, thick),
+                (len - head, thick * 3.0 / 4.0),
+                (0.0, thick * 3.0 / 4.0),
+            ]
+            .map(|(x, y)| f(x, y)),
+        )
+    };
+    match prst {
+        "ellipse" | "flowChartConnector" => format!(
+            "M0,{:.0} A{:.0},{:.0} 0 1 1 {:.0},{:.0} A{:.0},{:.0} 0 1 1 0,{:.0} Z",
+            h / 2.0,
+            w / 2.0,
+            h / 2.0,
+            w,
+            h / 2.0,
+            w / 2.0,
+            h / 2.0,
+            h / 2.0
+        ),
+        "roundRect" | "flowChartAlternateProcess" => {
+            let r = ss * 0.16667;
+            format!(
+                "M{r:.0},0 L{:.0},0 A{r:.0},{r:.0} 0 0 1 {w:.0},{r:.0} L{w:.0},{:.0} \
+                 A{r:.0},{r:.0} 0 0 1 {:.0},{h:.0} L{r:.0},{h:.0} A{r:.0},{r:.0} 0 0 1 0,{:.0} \
+                 L0,{r:.0} A{r:.0},{r:.0} 0 0 1 {r:.0},0 Z",
+                w - r,
+                h - r,
+                w - r,
+                h - r,
+            )
+        }
+        "triangle" | "flowChartExtract" => polygon(&[(w / 2.0, 0.0), (w, h), (0.0, h)]),
+        "rtTriangle" => polygon(&[(0.0, 0.0), (w, h), (0.0, h)]),
+        "diamond" | "flowChartDecision" => {
+            polygon(&[(w / 2.0, 0.0), (w, h / 2.0), (w / 2.0, h), (0.0, h / 2.0)])
+        }
+        "parallelogram" | "flowChartInputOutput" => {
+            let off = ss * 0.25;
+            polygon(&[(off, 0.0), (w, 0.0), (w - off, h), (0.0, h)])
+        }
+        "trapezoid" => {
+            let off = ss * 0.25;
+            polygon(&[(0.0, h), (off, 0.0), (w - off, 0.0), (w, h)])
+        }
+        "hexagon" => {
+            let off = ss * 0.25;
+            polygon(&[
+                (off, 0.0),
+                (w - off, 0.0),
+                (w, h / 2.0),
+                (w - off, h),
+                (off, h),
+                (0.0, h / 2.0),
+            ])
+        }
+        "pentagon" => polygon(&[
+            (w / 2.0, 0.0),
+            (w, h * 0.382),
+            (w * 0.809, h),
+            (w * 0.191, h),
+            (0.0, h * 0.382),
+        ]),
+        "homePlate" => {
+            let off = ss * 0.5;
+            polygon(&[
+                (0.0, 0.0),
+                (w - off, 0.0),
+                (w, h / 2.0),
+                (w - off, h),
+                (0.0, h),
+            ])
+        }
+        "chevron" => {
+            let off = ss * 0.5;
+            polygon(&[
+                (0.0, 0.0),
+                (w - off, 0.0),
+                (w, h / 2.0),
+                (w - off, h),
+                (0.0, h),
+                (off, h / 2.0),
+            ])
+        }
+        "rightArrow" => arrow(&|x, y| (x, y), w, h),
+        "leftArrow" => arrow(&|x, y| (w - x, y), w, h),
+        "downArrow" => arrow(&|x, y| (y, x), h, w),
+        "upArrow" => arrow(&|x, y| (y, h - x), h, w),
+        "line" | "straightConnector1" => format!("M0,0 L{:.0},{:.0}", w, h),
+        "bentConnector2" => format!("M0,0 L{:.0},0 L{:.0},{:.0}", w, w, h),
+        "bentConnector3" | "bentConnector4" | "bentConnector5" => format!(
+            "M0,0 L{:.0},0 L{:.0},{:.0} L{:.0},{:.0}",
+            w / 2.0,
+            w / 2.0,
+            h,
+            w,
+            h
+        ),
+        "curvedConnector2" | "curvedConnector3" | "curvedConnector4" | "curvedConnector5" => {
+            format!(
+                "M0,0 C{:.0},0 {:.0},{:.0} {:.0},{:.0}",
+                w / 2.0,
+                w / 2.0,
+                h,
+                w,
+                h
+            )
+        }
+        _ => polygon(&[(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)]),
+    }
+}
+
+// the paths of a:custGeom scaled onto w x h, with whether each is filled and
+// stroked. Paths using guides rather than numbers are left out.
+fn custom(cust_geom: Node, w: f64, h: f64) -> Vec<(String, bool, bool)> {
+    let mut res = Vec::new();
+    let path_lst = match cust_geom.child(A, "pathLst") {
+        Some(path_lst) => path_lst,
+        None => return res,
+    };
+    for path in path_lst
+        .elements()
+        .filter(|el| el.has_tag_name((A, "path")))
+    {
+        let size = |attr: &str, ext: f64| {
+            path.attribute(attr)
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v > 0.0)
+                .unwrap_or(ext)
+        };
+        let (sx, sy) = (w / size("w", w), h / size("h", h));
+        if let Some(d) = path_data(path, sx, sy) {
+            res.push((
+                d,
+                path.attribute("fill") != Some("none"),
+                path.attribute("stroke") != Some("0") && path.attribute("stroke") != Some("false"),
+            ));
+        }
+    }
+    res
+}
+
+fn path_data(path: Node, sx: f64, sy: f64) -> Option<String> {
+    let num = |el: Node, attr: &str| el.attribute(attr)?.parse::<f64>().ok();
+    let pts = |command: Node| -> Option<Vec<(f64, f64)>> {
+        command
+            .elements()
+            .filter(|el| el.has_tag_name((A, "pt")))
+            .map(|pt| Some((num(pt, "x")? * sx, num(pt, "y")? * sy)))
+            .collect()
+    };
+    let mut d = String::new();
+    let mut current = (0.0, 0.0);
+    for command in path.elements() {
+        match dml_name(command) {
+            "moveTo" | "lnTo" => {
+                let (x, y) = *pts(command)?.first()?;
+                let letter = if dml_name(command) == "moveTo" {
+                    'M'
+                } else {
+                    'L'
+                };
+                write!(d, "{}{:.0},{:.0} ", letter, x, y).unwrap();
+                current = (x, y);
+            }
+            "cubicBezTo" | "quadBezTo" => {
+                let pts = pts(command)?;
+                let letter = if pts.len() == 3 { 'C' } else { 'Q' };
+                write!(d, "{}", letter).unwrap();
+                for (x, y) in &pts {
+                    write!(d, "{:.0},{:.0} ", x, y).unwrap();
+                }
+                current = *pts.last()?;
+            }
+            "arcTo" => {
+                let (wr, hr) = (num(command, "wR")? * sx, num(command, "hR")? * sy);
+                let start = num(command, "stAng")? / 60000.0 * PI / 180.0;
+                let swing = num(command, "swAng")? / 60000.0 * PI / 180.0;
+                // the arc starts at the current point, at stAng around its
+                // center
+                let (cx, cy) = (current.0 - wr * start.cos(), current.1 - hr * start.sin());
+                let end = (
+                    cx + wr * (start + swing).cos(),
+                    cy + hr * (start + swing).sin(),
+                );
+                write!(
+                    d,
+                    "A{:.0},{:.0} 0 {} {} {:.0},{:.0} ",
+                    wr,
+                    hr,
+                    (swing.abs() > PI) as u8,
+                    (swing > 0.0) as u8,
+                    end.0,
+                    end.1
+                )
+                .unwrap();
+                current = end;
+            }
+            "close" => d.push_str("Z "),
+            _ => {}
+        }
+    }
+    Some(d.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    fn size(sld_sz: &str) -> (i64, i64) {
+        let presentation = format!(
+            r#"<p:presentation xmlns:p="{}">{}</p:presentation>"#,
+            P, sld_sz
+        );
+        slide_size(xml::parse(&presentation).unwrap().root_element())
+    }
+
+    #[test]
+    fn slide_size_falls_back_when_not_positive() {
+        assert_eq!(
+            size(r#"<p:sldSz cx="9144000" cy="6858000"/>"#),
+            (9144000, 6858000)
+        );
+        assert_eq!(
+            size(r#"<p:sldSz cx="0" cy="6858000"/>"#),
+            DEFAULT_SLIDE_SIZE
+        );
+        assert_eq!(
+            size(r#"<p:sldSz cx="9144000" cy="-1"/>"#),
+            DEFAULT_SLIDE_SIZE
+        );
+        assert_eq!(size(""), DEFAULT_SLIDE_SIZE);
+    }
+}
@@ -0,0 +1,205 @@
+use log::{trace, warn};
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{Read, Seek},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{
+    checksum,
+    package::{self, Package},
+    ExportMediaError,
+};
+
+const KEYS: &[&str] = &["doc", "slide", "n", "rid", "hash", "name", "ext"];
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    // {key} or {key:width}, numbers are padded to width, with zeros when
+    // width starts with 0, and text is cut to width
+    Var {
+        key: &'static str,
+        width: Option<usize>,
+        zero: bool,
+    },
+}
+
+// how exported media files are named, e.g. {doc}_{slide:03}_{n:02}.{ext}
+#[derive(Debug, Clone)]
+pub struct NameTemplate {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for NameTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_owned()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("unclosed placeholder in {}", s))?;
+            let (name, width) = match rest[start + 1..end].split_once(':') {
+                Some((name, width)) => (name, Some(width)),
+                None => (&rest[start + 1..end], None),
+            };
+            let key = KEYS.iter().find(|key| **key == name).ok_or_else(|| {
+                format!(
+                    "unknown placeholder {{{}}}, expected one of {:?}",
+                    name, KEYS
+                )
+            })?;
+            segments.push(Segment::Var {
+                key,
+                width: width
+                    .map(|width| {
+                        width
+                            .parse()
+                            .map_err(|_| format!("invalid width {}", width))
+                    })
+                    .transpose()?,
+                zero: width.is_some_and(|width| width.starts_with('0')),
+            });
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_owned()));
+        }
+        Ok(NameTemplate { segments })
+    }
+}
+
+// the values of the placeholders for one media file
+struct Vars<'a> {
+    doc: &'a str,
+    // first slide using the media, 0 when only masters and layouts do
+    slide: u32,
+    // position of the media among those of its slide
+    n: u32,
+    rid: &'a str,
+    hash: Option<String>,
+    name: &'a str,
+    ext: &'a str,
+}
+
+impl NameTemplate {
+    fn uses(&self, key: &str) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Var { key: k, .. } if *k == key))
+    }
+
+    fn render(&self, vars: &Vars) -> String {
+        let mut res = String::new();
+        for segment in &self.segments {
+            let (key, width, zero) = match segment {
+                Segment::Literal(literal) => {
+                    res.push_str(literal);
+                    continue;
+                }
+                Segment::Var { key, width, zero } => (*key, width.unwrap_or(0), *zero),
+            };
+            let number = match key {
+                "slide" => Some(vars.slide),
+                "n" => Some(vars.n),
+                _ => None,
+            };
+            if let Some(number) = number {
+                if zero {
+                    res.push_str(&format!("{:0width$}", number, width = width));
+                } else {
+                    res.push_str(&format!("{:width$}", number, width = width));
+                }
+                continue;
+            }
+            let text = match key {
+                "doc" => vars.doc,
+                "rid" => vars.rid,
+                "hash" => vars.hash.as_deref().unwrap_or_default(),
+                "name" => vars.name,
+                _ => vars.ext,
+            };
+            match width {
+                0 => res.push_str(text),
+                width => res.extend(text.chars().take(width)),
+            }
+        }
+        res
+    }
+}
+
+// name every media part of the package after the template, doc being the
+// name of the presentation
+pub fn media_names<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    pkg: &Package,
+    template: &NameTemplate,
+    doc: &str,
+) -> Result<BTreeMap<String, String>, ExportMediaError> {
+    // media part -> (slide, n, rel id) of its first use
+    let mut uses = BTreeMap::new();
+    for (i, slide) in pkg.slides.iter().enumerate() {
+        let mut n = 0;
+        for rel in package::part_rels(archive, slide)? {
+            if rel.external || !pkg.media.contains(&rel.target) {
+                continue;
+            }
+            n += 1;
+            uses.entry(rel.target).or_insert((i as u32 + 1, n, rel.id));
+        }
+    }
+    let mut unused = 0;
+    let mut res = BTreeMap::new();
+    let mut seen = HashSet::new();
+    for part_name in &pkg.media {
+        let (slide, n, rid) = match uses.get(part_name) {
+            Some((slide, n, rid)) => (*slide, *n, rid.as_str()),
+            None => {
+                unused += 1;
+                (0, unused, "")
+            }
+        };
+        let hash = if template.uses("hash") {
+            let mut f = archive.by_name(part_name).map_err(|e| e.to_string())?;
+            Some(checksum::checksum(&mut f, false)?.sha256)
+        } else {
+            None
+        };
+        let path = Path::new(part_name);
+        let name = template.render(&Vars {
+            doc,
+            slide,
+            n,
+            rid,
+            hash,
+            name: &path.file_stem().unwrap_or_default().to_string_lossy(),
+            ext: &path.extension().unwrap_or_default().to_string_lossy(),
+        });
+        trace!("media {} named {}", part_name, name);
+        // a name already taken gets a -2, -3, ... suffix so nothing is overwritten
+        let mut unique = name.clone();
+        let mut suffix = 1;
+        while !seen.insert(unique.clone()) {
+            suffix += 1;
+            unique = match name.rsplit_once('.') {
+                Some((stem, ext)) if !ext.contains('/') => format!("{}-{}.{}", stem, suffix, ext),
+                _ => format!("{}-{}", name, suffix),
+            };
+        }
+        if unique != name {
+            warn!(
+                "media {} is named {} like another media file, exporting it as {}",
+                part_name, name, unique
+            );
+        }
+        res.insert(part_name.clone(), unique);
+    }
+    Ok(res)
+}
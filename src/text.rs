@@ -0,0 +1,112 @@
+use clap::ValueEnum;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+static RE_ENTITY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap());
+
+// decode the predefined XML entities and numeric character references,
+// leaving anything unknown or invalid as it is
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_owned();
+    }
+    RE_ENTITY
+        .replace_all(text, |cap: &Captures| {
+            let entity = &cap[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                    .and_then(|code| code.ok())
+                    .and_then(char::from_u32),
+            };
+            match decoded {
+                Some(c) => c.to_string(),
+                None => cap[0].to_owned(),
+            }
+        })
+        .into_owned()
+}
+
+// base direction of a block of text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+// the direction of the first strongly directional character, as the Unicode
+// bidi algorithm picks the paragraph direction, ltr when there is none
+pub fn direction(text: &str) -> Direction {
+    match text.chars().find(|c| c.is_alphabetic()) {
+        Some(c) if is_rtl(c) => Direction::Rtl,
+        _ => Direction::Ltr,
+    }
+}
+
+// letters of the right-to-left scripts, Hebrew, Arabic, Syriac, Thaana, NKo,
+// Samaritan, Mandaic and their presentation forms
+fn is_rtl(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08FF}'
+        | '\u{FB1D}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFF}'
+        | '\u{10800}'..='\u{10FFF}'
+        | '\u{1E800}'..='\u{1EFFF}')
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalization {
+    Nfc,
+    Nfkc,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TextOptions {
+    pub normalize: Option<Normalization>,
+    pub trim: bool,
+}
+
+// apply the requested normalization and whitespace cleanup to extracted texts
+pub fn clean(texts: Vec<String>, opts: &TextOptions) -> Vec<String> {
+    texts
+        .into_iter()
+        .filter_map(|text| clean_text(text, opts))
+        .collect()
+}
+
+// clean up a single text, None if trimming leaves nothing of it
+pub fn clean_text(text: String, opts: &TextOptions) -> Option<String> {
+    let text: String = match opts.normalize {
+        Some(Normalization::Nfc) => text.nfc().collect(),
+        Some(Normalization::Nfkc) => text.nfkc().collect(),
+        None => text,
+    };
+    if !opts.trim {
+        return Some(text);
+    }
+    // collapse the whitespace of each line, keeping the line breaks
+    let lines: Vec<_> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    let text = lines.join("\n").trim_matches('\n').to_owned();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+use roxmltree::Node;
+use schemars::JsonSchema;
+use serde::Serialize;
+use zip::read::ZipFile;
+
+use crate::{
+    xml::{self, NodeExt, NS_DRAWINGML as A},
+    ExportMediaError,
+};
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ThemeRes {
+    pub theme_no: u32,
+    pub part_name: String,
+    pub name: String,
+    // color scheme slot (dk1, accent1, hlink, ...) -> hex value
+    pub colors: BTreeMap<String, String>,
+    pub major_font: Option<String>,
+    pub minor_font: Option<String>,
+}
+
+pub fn theme(f: ZipFile, theme_no: u32) -> Result<ThemeRes, ExportMediaError> {
+    let part_name = f.name().to_owned();
+    let text = xml::read(f)?;
+    let doc = xml::parse(&text).map_err(|e| ExportMediaError::Parse(e, part_name.clone()))?;
+    let el = doc.root_element();
+    let mut res = ThemeRes {
+        theme_no,
+        part_name,
+        name: el.attribute("name").unwrap_or_default().to_owned(),
+        colors: BTreeMap::new(),
+        major_font: None,
+        minor_font: None,
+    };
+    let elements = match el.child(A, "themeElements") {
+        Some(elements) => elements,
+        None => return Ok(res),
+    };
+    if let Some(clr_scheme) = elements.child(A, "clrScheme") {
+        for slot in clr_scheme.elements() {
+            if let Some(color) = slot_color(slot) {
+                res.colors.insert(slot.tag_name().name().to_owned(), color);
+            }
+        }
+    }
+    if let Some(font_scheme) = elements.child(A, "fontScheme") {
+        res.major_font = latin_typeface(font_scheme.child(A, "majorFont"));
+        res.minor_font = latin_typeface(font_scheme.child(A, "minorFont"));
+    }
+    Ok(res)
+}
+
+// a slot holds either an explicit srgbClr or a sysClr with the last computed value
+fn slot_color(slot: Node) -> Option<String> {
+    if let Some(srgb) = slot.child(A, "srgbClr") {
+        return srgb.attribute("val").map(String::from);
+    }
+    slot.child(A, "sysClr")
+        .and_then(|sys| sys.attribute("lastClr").map(String::from))
+}
+
+fn latin_typeface(font: Option<Node>) -> Option<String> {
+    font.and_then(|font| font.child(A, "latin"))
+        .and_then(|latin| latin.attribute("typeface").map(String::from))
+        .filter(|typeface| !typeface.is_empty())
+}
@@ -0,0 +1,83 @@
+use log::debug;
+use std::{
+    fs,
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use crate::{limits::Limited, ExportMediaError};
+
+// the files of a package stored unzipped, as a directory tree, by part name,
+// in name order. Symbolic links are left out so that the parts stay within
+// the tree.
+pub fn files(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut res = Vec::new();
+    let mut dirs = vec![(String::new(), dir.to_path_buf())];
+    while let Some((prefix, dir)) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                dirs.push((format!("{}/", name), entry.path()));
+            } else if file_type.is_file() {
+                res.push((name, entry.path()));
+            } else {
+                debug!("skipping {}, not a file", entry.path().display());
+            }
+        }
+    }
+    res.sort();
+    Ok(res)
+}
+
+// pack a package stored unzipped into an archive in memory of at most
+// max_size bytes, the same tree packing the same so that its checksum is
+// that of its contents
+pub fn pack(dir: &Path, max_size: u64) -> Result<Vec<u8>, ExportMediaError> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    // the same time for every entry rather than that of packing it
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .last_modified_time(DateTime::default());
+    let mut total = 0;
+    for (name, path) in files(dir)? {
+        let mut content = Vec::new();
+        Limited::new(fs::File::open(&path)?, max_size - total)
+            .read_to_end(&mut content)
+            .map_err(|e| format!("failed to read {}: {}", name, e))?;
+        writer
+            .start_file(name.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        writer.write_all(&content)?;
+        total += content.len() as u64;
+    }
+    let cursor = writer.finish().map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Cursor, process};
+
+    use zip::{DateTime, ZipArchive};
+
+    use super::pack;
+
+    #[test]
+    fn same_tree_packs_to_the_same_bytes() {
+        let dir = env::temp_dir().join(format!("ppt-img-extract-unpacked-{}", process::id()));
+        fs::create_dir_all(dir.join("ppt/slides")).unwrap();
+        fs::write(dir.join("[Content_Types].xml"), "<Types/>").unwrap();
+        fs::write(dir.join("ppt/slides/slide1.xml"), "<p:sld/>").unwrap();
+        let first = pack(&dir, u64::MAX).unwrap();
+        let second = pack(&dir, u64::MAX).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(first, second);
+        let mut archive = ZipArchive::new(Cursor::new(first)).unwrap();
+        let file = archive.by_name("ppt/slides/slide1.xml").unwrap();
+        assert_eq!(file.last_modified(), Some(DateTime::default()));
+    }
+}
@@ -0,0 +1,180 @@
+use std::{collections::HashSet, fs, io, path::Path};
+
+use clap::Args;
+use serde::Serialize;
+
+use crate::{
+    package::{self, ContentTypes},
+    xml, ExportMediaError,
+};
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    /// Input file
+    input_file: String,
+
+    /// Print the issues as a JSON array
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum IssueKind {
+    // a relationship to a part the package doesn't have
+    DanglingRelationship,
+    // an image, video or audio relationship to a part the package doesn't have
+    MissingMedia,
+    MalformedXml,
+    // a part [Content_Types].xml gives no content type
+    UnknownContentType,
+}
+
+#[derive(Debug, Serialize)]
+struct Issue {
+    part: String,
+    kind: IssueKind,
+    message: String,
+}
+
+// check that the relationships of a presentation lead to parts it has, that
+// its XML parts parse and that each part has a content type, printing the
+// issues found. Returns whether there are any.
+pub fn run(args: &ValidateArgs) -> Result<bool, ExportMediaError> {
+    let archivef = fs::File::open(Path::new(&args.input_file))?;
+    let mut archive =
+        zip::ZipArchive::new(io::BufReader::new(archivef)).map_err(|e| e.to_string())?;
+    let part_names: Vec<String> = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(str::to_owned)
+        .collect();
+    let parts: HashSet<&str> = part_names.iter().map(String::as_str).collect();
+    let mut issues = Vec::new();
+
+    let content_types = content_types(&mut archive, &mut issues)?;
+    for part_name in &part_names {
+        if part_name == package::CONTENT_TYPES_FILE {
+            continue;
+        }
+        let content_type = content_types.get(part_name);
+        if content_type.is_none() {
+            issues.push(Issue {
+                part: part_name.clone(),
+                kind: IssueKind::UnknownContentType,
+                message: "no Default or Override content type".to_owned(),
+            });
+        }
+        if !is_xml(part_name, content_type) {
+            continue;
+        }
+        let f = archive.by_name(part_name).map_err(|e| e.to_string())?;
+        let malformed = match xml::read(f) {
+            Ok(text) => xml::parse(&text).err().map(|e| e.to_string()),
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(message) = malformed {
+            issues.push(Issue {
+                part: part_name.clone(),
+                kind: IssueKind::MalformedXml,
+                message,
+            });
+            continue;
+        }
+        if let Some(source) = rels_source(part_name) {
+            relationships(&mut archive, &source, part_name, &parts, &mut issues)?;
+        }
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&issues).map_err(|e| e.to_string())?
+        );
+    } else {
+        for issue in &issues {
+            println!("{}: {}", issue.part, issue.message);
+        }
+        println!("{} issues in {} parts", issues.len(), part_names.len());
+    }
+    Ok(!issues.is_empty())
+}
+
+fn content_types<R: io::Read + io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    issues: &mut Vec<Issue>,
+) -> Result<ContentTypes, ExportMediaError> {
+    if archive
+        .index_for_name(package::CONTENT_TYPES_FILE)
+        .is_none()
+    {
+        issues.push(Issue {
+            part: package::CONTENT_TYPES_FILE.to_owned(),
+            kind: IssueKind::UnknownContentType,
+            message: "missing, no part has a content type".to_owned(),
+        });
+        return Ok(ContentTypes::default());
+    }
+    match package::content_types(archive) {
+        Ok(content_types) => Ok(content_types),
+        Err(ExportMediaError::Parse(e, _)) => {
+            issues.push(Issue {
+                part: package::CONTENT_TYPES_FILE.to_owned(),
+                kind: IssueKind::MalformedXml,
+                message: e.to_string(),
+            });
+            Ok(ContentTypes::default())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// the relationships of source, read from its rels part, that lead nowhere
+fn relationships<R: io::Read + io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    source: &str,
+    rels_part: &str,
+    parts: &HashSet<&str>,
+    issues: &mut Vec<Issue>,
+) -> Result<(), ExportMediaError> {
+    for rel in package::part_rels(archive, source)? {
+        if rel.external || parts.contains(rel.target.as_str()) {
+            continue;
+        }
+        let kind = match package::REL_MEDIA.contains(&rel.kind.as_str()) {
+            true => IssueKind::MissingMedia,
+            false => IssueKind::DanglingRelationship,
+        };
+        issues.push(Issue {
+            part: rels_part.to_owned(),
+            kind,
+            message: format!(
+                "{} ({}) targets missing part {}",
+                rel.id, rel.kind, rel.target
+            ),
+        });
+    }
+    Ok(())
+}
+
+// whether a part is XML, by its content type or else its extension
+fn is_xml(part_name: &str, content_type: Option<&str>) -> bool {
+    match content_type {
+        Some(content_type) => content_type.ends_with("xml"),
+        None => part_name.ends_with(".xml") || part_name.ends_with(".rels"),
+    }
+}
+
+// the part a rels part holds the relationships of, ppt/slides/_rels/slide1.xml.rels
+// -> ppt/slides/slide1.xml, the package itself being the empty part name
+fn rels_source(part_name: &str) -> Option<String> {
+    let file_name = part_name.strip_suffix(".rels")?;
+    let (dir, file_name) = match file_name.rsplit_once("/_rels/") {
+        Some((dir, file_name)) => (Some(dir), file_name),
+        None => (None, file_name.strip_prefix("_rels/")?),
+    };
+    Some(match dir {
+        Some(dir) => format!("{}/{}", dir, file_name),
+        None => file_name.to_owned(),
+    })
+}
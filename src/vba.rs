@@ -0,0 +1,74 @@
+use std::{
+    io::{Cursor, Read, Seek},
+    path::Path,
+};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{output::Output, package::Package, ExportMediaError};
+
+const REL_VBA_PROJECT: &str = "vbaProject";
+const STORAGE_VBA: &str = "/VBA";
+// streams in the VBA storage that describe the project rather than a module
+const NON_MODULE_STREAMS: &[&str] = &["dir", "_VBA_PROJECT"];
+const PREFIX_SRP_STREAM: &str = "__SRP_";
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VbaRes {
+    pub part_name: String,
+    // names of the module streams, which are named after their modules
+    pub modules: Vec<String>,
+    // exported file name when extraction was requested
+    pub exported: Option<String>,
+}
+
+// find the VBA project of the presentation and list its modules, exporting
+// the project to output when given
+pub fn vba<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    pkg: &Package,
+    output: Option<&mut Output>,
+) -> Result<Option<VbaRes>, ExportMediaError> {
+    let part_name = match pkg
+        .main_rels
+        .iter()
+        .find(|rel| rel.kind == REL_VBA_PROJECT && !rel.external)
+    {
+        Some(rel) => rel.target.clone(),
+        None => return Ok(None),
+    };
+    let mut data = Vec::new();
+    archive
+        .by_name(&part_name)
+        .map_err(|e| e.to_string())?
+        .read_to_end(&mut data)?;
+
+    let mut res = VbaRes {
+        part_name,
+        modules: Vec::new(),
+        exported: None,
+    };
+    let project = cfb::CompoundFile::open(Cursor::new(&data))?;
+    for entry in project.read_storage(STORAGE_VBA)? {
+        let name = entry.name();
+        if entry.is_stream()
+            && !NON_MODULE_STREAMS.contains(&name)
+            && !name.starts_with(PREFIX_SRP_STREAM)
+        {
+            res.modules.push(name.to_owned());
+        }
+    }
+    res.modules.sort();
+
+    if let Some(output) = output {
+        let outfilename = Path::new(&res.part_name)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        output.write(&outfilename, &data)?;
+        res.exported = Some(outfilename);
+    }
+    Ok(Some(res))
+}
@@ -0,0 +1,151 @@
+use std::{fs, io, path::Path};
+
+use clap::Args;
+use serde::Serialize;
+
+use crate::{catalog, checksum, ExportMediaError, INDEX_FILE};
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Output directory of an extraction, or of a batch run with a catalog.json
+    output_dir: String,
+
+    /// Print the issues as a JSON array
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum IssueKind {
+    Missing,
+    // the file can't be read for another reason than being missing
+    Unreadable,
+    // the file isn't the size the index says
+    SizeMismatch,
+    // the file's checksums differ from the index's while its size is the same
+    Corrupted,
+}
+
+#[derive(Debug, Serialize)]
+struct Issue {
+    // relative to the output directory
+    file: String,
+    kind: IssueKind,
+    message: String,
+}
+
+// check that each media file the index lists is in the output directory with
+// the size and checksums the index has for it, or the same for each deck of
+// a catalog, printing the issues found. Returns whether there are any.
+pub fn run(args: &VerifyArgs) -> Result<bool, ExportMediaError> {
+    let output_dir = Path::new(&args.output_dir);
+    let mut issues = Vec::new();
+    let mut files = 0;
+    if output_dir.join(INDEX_FILE).is_file() {
+        files += verify_index(output_dir, "", &mut issues)?;
+    } else if output_dir.join(catalog::CATALOG_FILE).is_file() {
+        let catalog = read_json(&output_dir.join(catalog::CATALOG_FILE))?;
+        for deck in catalog["decks"].as_array().into_iter().flatten() {
+            // a deck that failed to extract has nothing to check
+            if deck["error"].is_string() {
+                continue;
+            }
+            let dir = deck["output_dir"].as_str().unwrap_or_default();
+            let prefix = format!("{}/", dir);
+            files += verify_index(&output_dir.join(dir), &prefix, &mut issues)?;
+        }
+    } else {
+        return Err(format!(
+            "{} has neither an {} nor a {}",
+            output_dir.display(),
+            INDEX_FILE,
+            catalog::CATALOG_FILE
+        )
+        .into());
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&issues).map_err(|e| e.to_string())?
+        );
+    } else {
+        for issue in &issues {
+            println!("{}: {}", issue.file, issue.message);
+        }
+        println!("{} issues in {} files", issues.len(), files);
+    }
+    Ok(!issues.is_empty())
+}
+
+// check the media of the index in dir, the names of the issues found starting
+// with prefix, returns how many were checked
+fn verify_index(
+    dir: &Path,
+    prefix: &str,
+    issues: &mut Vec<Issue>,
+) -> Result<usize, ExportMediaError> {
+    let path = dir.join(INDEX_FILE);
+    if !path.is_file() {
+        issues.push(Issue {
+            file: format!("{}{}", prefix, INDEX_FILE),
+            kind: IssueKind::Missing,
+            message: String::from("missing"),
+        });
+        return Ok(0);
+    }
+    let index = read_json(&path)?;
+    let media = match index["media"].as_object() {
+        Some(media) => media,
+        None => return Ok(0),
+    };
+    for (filename, recorded) in media {
+        let file = format!("{}{}", prefix, filename);
+        let md5 = recorded["md5"].as_str();
+        let actual = match fs::File::open(dir.join(filename))
+            .and_then(|mut f| checksum::checksum(&mut f, md5.is_some()))
+        {
+            Ok(actual) => actual,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                issues.push(Issue {
+                    file,
+                    kind: IssueKind::Missing,
+                    message: String::from("missing"),
+                });
+                continue;
+            }
+            Err(e) => {
+                issues.push(Issue {
+                    file,
+                    kind: IssueKind::Unreadable,
+                    message: format!("unreadable, error: {}", e),
+                });
+                continue;
+            }
+        };
+        let size = recorded["size"].as_u64();
+        if size.is_some_and(|size| size != actual.size) {
+            issues.push(Issue {
+                file,
+                kind: IssueKind::SizeMismatch,
+                message: format!("{} bytes, the index has {}", actual.size, size.unwrap()),
+            });
+        } else if recorded["sha256"].as_str() != Some(actual.sha256.as_str())
+            || md5.is_some_and(|md5| Some(md5) != actual.md5.as_deref())
+        {
+            issues.push(Issue {
+                file,
+                kind: IssueKind::Corrupted,
+                message: String::from("checksum differs from the index's"),
+            });
+        }
+    }
+    Ok(media.len())
+}
+
+fn read_json(path: &Path) -> Result<serde_json::Value, ExportMediaError> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("failed to parse {}, error: {}", path.display(), e).into())
+}
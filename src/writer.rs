@@ -0,0 +1,197 @@
+use log::trace;
+use std::{
+    io::{Cursor, Read},
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use zip::{read::ZipFile, ZipArchive, ZipWriter};
+
+use crate::{
+    checksum::Checksum,
+    dimensions::Dimensions,
+    errors::{Errors, PartError},
+    exif::Exif,
+    export_media, limits,
+    output::Output,
+    ExportMediaError, Options,
+};
+
+// media copied out of the archive still compressed and waiting to be
+// decompressed and written, bounding how much of the package is held in
+// memory at once
+const QUEUE_LEN: usize = 8;
+
+struct Job {
+    part_name: String,
+    filename: String,
+    // the part on its own in an archive of its own, as compressed in the package
+    packed: Vec<u8>,
+    // whether the data is handed back for the visitor and plugins
+    visit: bool,
+}
+
+// a media part that was written
+pub struct Written {
+    pub part_name: String,
+    pub filename: String,
+    pub checksum: Checksum,
+    pub exif: Option<Exif>,
+    pub dimensions: Option<Dimensions>,
+    pub elapsed: Duration,
+}
+
+// a media part as decompressed by the writer, handed back to be visited on
+// the thread extracting the package
+pub struct Decompressed {
+    pub part_name: String,
+    pub filename: String,
+    pub data: Vec<u8>,
+    // the name it was written as, none when it failed to be
+    pub written: Option<String>,
+}
+
+// the output given back once the media are written, with what was written,
+// the media that failed to be and those decompressed but not yet visited
+pub struct Finished {
+    pub output: Output,
+    pub written: Vec<Written>,
+    pub errors: Vec<PartError>,
+    pub decompressed: Vec<Decompressed>,
+}
+
+// decompresses and writes media on a thread of their own, owning the output
+// meanwhile, so that decompressing, hashing, converting and writing them
+// overlaps with parsing the slides
+pub struct MediaWriter {
+    sender: SyncSender<Job>,
+    decompressed: Receiver<Decompressed>,
+    handle: JoinHandle<(Output, Vec<Written>, Vec<PartError>)>,
+}
+
+impl MediaWriter {
+    pub fn spawn(mut output: Output, options: Options) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(QUEUE_LEN);
+        let (decompressed_sender, decompressed) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut written = Vec::new();
+            let mut errors = Errors::new("media");
+            for job in receiver {
+                let start = Instant::now();
+                let data = match unpack(job.packed, &options) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        errors.error("failed to read media:", Some(&job.part_name), e);
+                        continue;
+                    }
+                };
+                // in its place before it's reported written
+                let exported = export_media(
+                    &mut output,
+                    &mut data.as_slice(),
+                    job.filename.clone(),
+                    &options,
+                )
+                .and_then(|exported| output.complete().map(|_| exported));
+                let mut written_as = None;
+                match exported {
+                    Ok(exported) => {
+                        trace!(
+                            "exported media {}, checksum: {:?}",
+                            job.part_name,
+                            exported.checksum
+                        );
+                        written_as = Some(exported.filename.clone());
+                        written.push(Written {
+                            part_name: job.part_name.clone(),
+                            filename: exported.filename,
+                            checksum: exported.checksum,
+                            exif: exported.exif,
+                            dimensions: exported.dimensions,
+                            elapsed: start.elapsed(),
+                        });
+                    }
+                    Err(e) => errors.error("failed to export media:", Some(&job.part_name), e),
+                }
+                // the receiver only goes away with a writer dropped unfinished
+                if job.visit {
+                    let _ = decompressed_sender.send(Decompressed {
+                        part_name: job.part_name,
+                        filename: job.filename,
+                        data,
+                        written: written_as,
+                    });
+                }
+            }
+            (output, written, errors.take())
+        });
+        MediaWriter {
+            sender,
+            decompressed,
+            handle,
+        }
+    }
+
+    // queue a media part as read raw out of the archive, waiting while the
+    // queue is full. With visit, its data comes back from decompressed once
+    // it's written
+    pub fn send(
+        &self,
+        part_name: &str,
+        filename: String,
+        file: ZipFile,
+        visit: bool,
+    ) -> Result<(), ExportMediaError> {
+        let job = Job {
+            part_name: part_name.to_owned(),
+            filename,
+            packed: pack(file)?,
+            visit,
+        };
+        self.sender
+            .send(job)
+            .map_err(|_| String::from("the media writer stopped").into())
+    }
+
+    // the media decompressed since last asked, without waiting for more
+    pub fn decompressed(&self) -> impl Iterator<Item = Decompressed> + '_ {
+        self.decompressed.try_iter()
+    }
+
+    // wait for the queued media to be written, giving the output back, an
+    // error if the writer panicked and took the output with it
+    pub fn finish(self) -> Result<Finished, ExportMediaError> {
+        drop(self.sender);
+        let (output, written, errors) = self
+            .handle
+            .join()
+            .map_err(|_| String::from("the media writer panicked"))?;
+        Ok(Finished {
+            output,
+            written,
+            errors,
+            decompressed: self.decompressed.try_iter().collect(),
+        })
+    }
+}
+
+// copy a part into an archive of its own without decompressing it, which
+// is left to the writer
+fn pack(file: ZipFile) -> Result<Vec<u8>, ExportMediaError> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer.raw_copy_file(file).map_err(|e| e.to_string())?;
+    let packed = writer.finish().map_err(|e| e.to_string())?;
+    Ok(packed.into_inner())
+}
+
+// decompress the part packed on its own, up to the media size limit and
+// before the deadline
+fn unpack(packed: Vec<u8>, options: &Options) -> Result<Vec<u8>, ExportMediaError> {
+    let reader = limits::Timed::new(Cursor::new(packed), options.deadline);
+    let mut archive = ZipArchive::new(reader).map_err(|e| e.to_string())?;
+    let file = archive.by_index(0).map_err(|e| e.to_string())?;
+    let mut data = Vec::with_capacity(file.size().min(options.max_media_size) as usize);
+    limits::Limited::new(file, options.max_media_size).read_to_end(&mut data)?;
+    Ok(data)
+}
@@ -0,0 +1,55 @@
+use std::io::{self, Read};
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+pub const NS_PRESENTATIONML: &str = "http://schemas.openxmlformats.org/presentationml/2006/main";
+pub const NS_DRAWINGML: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
+// r:id, r:embed, r:link, ...
+pub const NS_RELATIONSHIPS: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+pub const NS_PACKAGE_RELATIONSHIPS: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships";
+pub const NS_CONTENT_TYPES: &str = "http://schemas.openxmlformats.org/package/2006/content-types";
+pub const NS_MARKUP_COMPATIBILITY: &str =
+    "http://schemas.openxmlformats.org/markup-compatibility/2006";
+// p14, PowerPoint 2010 extensions
+pub const NS_POWERPOINT_2010: &str = "http://schemas.microsoft.com/office/powerpoint/2010/main";
+
+// read a part as text to parse, XML parts being UTF-8
+pub fn read(mut f: impl Read) -> io::Result<String> {
+    let mut res = String::new();
+    f.read_to_string(&mut res)?;
+    Ok(res)
+}
+
+// parse a part, skipping a byte order mark and allowing a DTD, which parts
+// written by other tools can have
+pub fn parse(text: &str) -> Result<Document<'_>, roxmltree::Error> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    Document::parse_with_options(
+        text,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )
+}
+
+// namespace aware lookups on elements, matching the namespace rather than
+// whatever prefix a part binds it to
+pub trait NodeExt<'a, 'input: 'a> {
+    // the child elements
+    fn elements(self) -> impl Iterator<Item = Node<'a, 'input>>;
+    // the first child element with the name in the namespace
+    fn child(self, ns: &str, name: &str) -> Option<Node<'a, 'input>>;
+}
+
+impl<'a, 'input: 'a> NodeExt<'a, 'input> for Node<'a, 'input> {
+    fn elements(self) -> impl Iterator<Item = Node<'a, 'input>> {
+        self.children().filter(|child| child.is_element())
+    }
+
+    fn child(self, ns: &str, name: &str) -> Option<Node<'a, 'input>> {
+        self.children().find(|child| child.has_tag_name((ns, name)))
+    }
+}